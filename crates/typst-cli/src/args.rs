@@ -55,6 +55,13 @@ pub enum Command {
     /// Lists all discovered fonts in system and custom font paths
     Fonts(FontsCommand),
 
+    /// Copies all package dependencies of a project into its vendor directory
+    Vendor(VendorCommand),
+
+    /// Manages local packages and the package cache
+    #[command(subcommand)]
+    Package(PackageCommand),
+
     /// Self update the Typst CLI
     #[cfg_attr(not(feature = "self-update"), doc = " (disabled)")]
     Update(UpdateCommand),
@@ -83,6 +90,38 @@ pub struct CompileCommand {
     #[arg(long = "ppi", default_value_t = 144.0)]
     pub ppi: f32,
 
+    /// Compiles the document once for each set of inputs in a JSON or CSV file
+    ///
+    /// A JSON file must contain an array of objects and a CSV file must have a
+    /// header row naming the inputs. Each set's inputs are added to those
+    /// given through `--input`. The output path may contain `{i}` for the
+    /// set's one-based index and `{key}` for the value of the input `key`. If
+    /// it contains neither, `-{i}` is appended to the file name.
+    #[arg(long = "batch", value_name = "INPUT_SETS")]
+    pub batch: Option<PathBuf>,
+
+    /// Writes the files the compilation depended on to the given path
+    ///
+    /// Lists all sources, images, data files, fonts, and package files that
+    /// were read, so that build systems like make and ninja know when to
+    /// recompile. Only written if compilation succeeds. In batch mode, a single
+    /// file lists the outputs of all input sets and everything any of them
+    /// read.
+    #[arg(long = "deps", value_name = "PATH")]
+    pub deps: Option<PathBuf>,
+
+    /// The format of the dependency file
+    #[arg(long = "deps-format", default_value_t = DepsFormat::Make)]
+    pub deps_format: DepsFormat,
+
+    /// Writes a JSON report of the compilation to the given path
+    ///
+    /// The report lists all diagnostics, the files that were read and
+    /// written, the page count, and the duration of the compilation. In batch
+    /// mode, the file contains an array with one report per input set.
+    #[arg(long = "report", value_name = "OUTPUT_JSON")]
+    pub report: Option<PathBuf>,
+
     /// Produces performance timings of the compilation process (experimental)
     ///
     /// The resulting JSON file can be loaded into a tracing tool such as
@@ -166,6 +205,11 @@ pub struct SharedArgs {
     )]
     pub font_paths: Vec<PathBuf>,
 
+    /// Caches the results of the font search in this directory so that they
+    /// can be reused across runs
+    #[clap(long = "cache-path", env = "TYPST_CACHE_PATH", value_name = "DIR")]
+    pub cache_path: Option<PathBuf>,
+
     /// The format to emit diagnostics in
     #[clap(
         long,
@@ -173,6 +217,15 @@ pub struct SharedArgs {
         value_parser = clap::value_parser!(DiagnosticFormat)
     )]
     pub diagnostic_format: DiagnosticFormat,
+
+    /// Requires all packages to be pinned in the project's `typst.lock`
+    #[clap(long = "locked", env = "TYPST_LOCKED")]
+    pub locked: bool,
+
+    /// Pins newly used packages in the project's `typst.lock`, creating it if
+    /// necessary
+    #[clap(long = "update-lock", conflicts_with = "locked")]
+    pub update_lock: bool,
 }
 
 /// An input that is either stdin or a real path.
@@ -223,16 +276,103 @@ pub struct FontsCommand {
     )]
     pub font_paths: Vec<PathBuf>,
 
+    /// Caches the results of the font search in this directory so that they
+    /// can be reused across runs
+    #[clap(long = "cache-path", env = "TYPST_CACHE_PATH", value_name = "DIR")]
+    pub cache_path: Option<PathBuf>,
+
     /// Also lists style variants of each font family
     #[arg(long)]
     pub variants: bool,
 }
 
+/// Copies all package dependencies of a project into its vendor directory
+#[derive(Debug, Clone, Parser)]
+pub struct VendorCommand {
+    /// The project root, defaults to the current directory
+    ///
+    /// All Typst files in the project are scanned for package imports. The
+    /// packages and their transitive dependencies are copied into the
+    /// `vendor` directory within the root. Packages from `@preview` are
+    /// additionally pinned in `typst.lock`.
+    #[clap(env = "TYPST_ROOT", value_name = "DIR")]
+    pub root: Option<PathBuf>,
+}
+
+/// Manages local packages and the package cache
+#[derive(Debug, Clone, Subcommand)]
+pub enum PackageCommand {
+    /// Lists all packages in the local package directories
+    List(PackageListCommand),
+
+    /// Removes packages from the download cache
+    Clean(PackageCleanCommand),
+
+    /// Links a package directory into a local namespace
+    Link(PackageLinkCommand),
+
+    /// Checks that a package directory is ready for publication
+    Check(PackageCheckCommand),
+}
+
+/// Lists all packages in the local package directories
+#[derive(Debug, Clone, Parser)]
+pub struct PackageListCommand {
+    /// Only lists packages from this namespace, e.g. `local` or `preview`
+    #[clap(long = "namespace")]
+    pub namespace: Option<String>,
+
+    /// Also prints where each package is stored
+    #[arg(long)]
+    pub paths: bool,
+}
+
+/// Removes packages from the download cache
+#[derive(Debug, Clone, Parser)]
+pub struct PackageCleanCommand {
+    /// The package to remove, e.g. `@preview/example` or `@preview/example:0.1.0`
+    ///
+    /// If a version is given, just that version is removed. Otherwise, all but
+    /// the latest cached version of the package are removed. If the package
+    /// is omitted, this applies to all cached packages.
+    pub package: Option<String>,
+
+    /// Removes all matching versions, including the latest one
+    #[arg(long)]
+    pub all: bool,
+}
+
+/// Links a package directory into a local namespace
+#[derive(Debug, Clone, Parser)]
+pub struct PackageLinkCommand {
+    /// The package directory containing a `typst.toml`, defaults to the
+    /// current directory
+    pub dir: Option<PathBuf>,
+
+    /// The namespace to link the package into
+    #[clap(long = "namespace", default_value = "local")]
+    pub namespace: String,
+
+    /// Replaces an existing package with the same name and version
+    #[arg(long)]
+    pub force: bool,
+}
+
+/// Checks that a package directory is ready for publication
+#[derive(Debug, Clone, Parser)]
+pub struct PackageCheckCommand {
+    /// The package directory containing a `typst.toml`, defaults to the
+    /// current directory
+    pub dir: Option<PathBuf>,
+}
+
 /// Which format to use for diagnostics.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, ValueEnum)]
 pub enum DiagnosticFormat {
     Human,
     Short,
+    /// One JSON object per line, for consumption by other tools.
+    Json,
 }
 
 impl Display for DiagnosticFormat {
@@ -244,6 +384,24 @@ impl Display for DiagnosticFormat {
     }
 }
 
+/// Which format to use for the dependency file.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, ValueEnum)]
+pub enum DepsFormat {
+    /// A Makefile rule, as understood by make and ninja.
+    Make,
+    /// A JSON object with the read inputs and written outputs.
+    Json,
+}
+
+impl Display for DepsFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.to_possible_value()
+            .expect("no values are skipped")
+            .get_name()
+            .fmt(f)
+    }
+}
+
 /// Update the CLI using a pre-compiled binary from a Typst GitHub release.
 #[derive(Debug, Clone, Parser)]
 pub struct UpdateCommand {
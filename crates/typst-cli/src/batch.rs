@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use ecow::{eco_format, EcoVec};
+use parking_lot::Mutex;
+use typst::diag::{bail, StrResult};
+use typst::eval::Tracer;
+use typst::foundations::{Dict, IntoValue};
+use typst::World;
+
+use crate::args::CompileCommand;
+use crate::compile::{export, print_diagnostics};
+use crate::deps::write_deps;
+use crate::report::CompileReport;
+use crate::world::SystemWorld;
+use crate::{print_error, set_failed};
+
+/// The inputs for one document of a batch, in order.
+pub type InputSet = Vec<(String, String)>;
+
+/// Read the input sets for a batch compilation from a JSON or CSV file.
+pub fn read_input_sets(path: &Path) -> StrResult<Vec<InputSet>> {
+    match path.extension() {
+        Some(ext) if ext.eq_ignore_ascii_case("json") => read_json(path),
+        Some(ext) if ext.eq_ignore_ascii_case("csv") => read_csv(path),
+        _ => bail!(
+            "could not infer format of input sets at {} (expected JSON or CSV)",
+            path.display()
+        ),
+    }
+}
+
+/// Read input sets from a JSON array of objects.
+fn read_json(path: &Path) -> StrResult<Vec<InputSet>> {
+    let data =
+        fs::read(path).map_err(|err| eco_format!("failed to read input sets ({err})"))?;
+    let objects: Vec<serde_json::Map<String, serde_json::Value>> =
+        serde_json::from_slice(&data).map_err(|err| {
+            eco_format!("failed to parse input sets (expected array of objects, {err})")
+        })?;
+
+    Ok(objects
+        .into_iter()
+        .map(|object| {
+            object
+                .into_iter()
+                .map(|(key, value)| match value {
+                    serde_json::Value::String(string) => (key, string),
+                    other => (key, other.to_string()),
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// Read input sets from a CSV file with a header row.
+fn read_csv(path: &Path) -> StrResult<Vec<InputSet>> {
+    let mut reader = csv::Reader::from_path(path)
+        .map_err(|err| eco_format!("failed to read input sets ({err})"))?;
+    let headers = reader
+        .headers()
+        .map_err(|err| eco_format!("failed to parse input sets ({err})"))?
+        .clone();
+
+    reader
+        .records()
+        .enumerate()
+        .map(|(i, record)| {
+            let record = record
+                .map_err(|err| eco_format!("failed to parse input sets ({err})"))?;
+            if record.len() != headers.len() {
+                bail!(
+                    "failed to parse input sets (input set {} has {} fields, \
+                     but the header has {})",
+                    i + 1,
+                    record.len(),
+                    headers.len(),
+                );
+            }
+            Ok(headers
+                .iter()
+                .zip(record.iter())
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect())
+        })
+        .collect()
+}
+
+/// Compile the document once for each input set, reusing the world and thus
+/// the loaded fonts and files as well as the memoization cache.
+///
+/// Compilation happens in order, while the outputs are written in parallel.
+pub fn compile_batch(
+    world: &mut SystemWorld,
+    command: &CompileCommand,
+    sets: Vec<InputSet>,
+) -> StrResult<()> {
+    // Check if main file can be read and opened.
+    world.source(world.main()).map_err(|err| err.to_string())?;
+
+    let jobs = prepare_jobs(command, sets)?;
+    let diagnostic_format = command.common.diagnostic_format;
+    let exports = Mutex::new(Vec::<(usize, StrResult<Vec<PathBuf>>, Duration)>::new());
+    let mut dependencies = vec![];
+    let mut reports = vec![];
+    let mut succeeded = true;
+
+    rayon::scope(|scope| {
+        for (i, (inputs, output)) in jobs.into_iter().enumerate() {
+            let index = i + 1;
+            let dict: Dict = inputs
+                .iter()
+                .map(|(k, v)| (k.as_str().into(), v.as_str().into_value()))
+                .collect();
+
+            world.set_inputs(dict);
+            world.reset();
+
+            let mut job = command.clone();
+            job.output = Some(output);
+            job.batch = None;
+            job.open = None;
+
+            let start = Instant::now();
+            let mut tracer = Tracer::new();
+            let result = typst::compile(world, &mut tracer);
+            let warnings = tracer.warnings();
+
+            let (pages, errors) = match result {
+                Ok(document) => {
+                    print_diagnostics(world, &[], &warnings, diagnostic_format).map_err(
+                        |err| eco_format!("failed to print diagnostics ({err})"),
+                    )?;
+
+                    let pages = document.pages.len();
+                    let exports = &exports;
+                    scope.spawn(move |_| {
+                        let written = export(&document, &job, None);
+                        exports.lock().push((i, written, start.elapsed()));
+                    });
+
+                    (Some(pages), EcoVec::new())
+                }
+
+                Err(errors) => {
+                    set_failed();
+                    succeeded = false;
+                    print_error(&format!("failed to compile input set {index}"))
+                        .map_err(|err| eco_format!("failed to print error ({err})"))?;
+                    print_diagnostics(world, &errors, &warnings, diagnostic_format)
+                        .map_err(|err| {
+                            eco_format!("failed to print diagnostics ({err})")
+                        })?;
+
+                    (None, errors)
+                }
+            };
+
+            // The outputs, success, and duration of a successful compilation
+            // are filled in once its export finished.
+            if command.report.is_some() {
+                reports.push(CompileReport::new(
+                    world,
+                    start.elapsed(),
+                    pages,
+                    vec![],
+                    &errors,
+                    &warnings,
+                ));
+            }
+
+            if command.deps.is_some() {
+                dependencies.extend(world.dependencies());
+            }
+
+            // Evict memoized results that are no longer reused.
+            comemo::evict(10);
+        }
+
+        StrResult::Ok(())
+    })?;
+
+    let mut exports = exports.into_inner();
+    exports.sort_by_key(|&(i, ..)| i);
+
+    let mut outputs = vec![];
+    for (i, written, duration) in exports {
+        if let Some(report) = reports.get_mut(i) {
+            report.duration = duration.as_secs_f64();
+        }
+
+        match written {
+            Ok(paths) => {
+                if let Some(report) = reports.get_mut(i) {
+                    report.outputs = paths.clone();
+                }
+                outputs.extend(paths);
+            }
+            Err(err) => {
+                set_failed();
+                succeeded = false;
+                if let Some(report) = reports.get_mut(i) {
+                    report.success = false;
+                }
+                print_error(&format!("input set {}: {err}", i + 1))
+                    .map_err(|err| eco_format!("failed to print error ({err})"))?;
+            }
+        }
+    }
+
+    // All outputs depend on the files read by any of the compilations.
+    if let (Some(path), true) = (&command.deps, succeeded) {
+        write_deps(path, command.deps_format, dependencies, &outputs)?;
+    }
+
+    if let Some(path) = &command.report {
+        CompileReport::write_all(&reports, path)?;
+    }
+
+    // Pin packages that were used for the first time, if requested.
+    world.packages().save()
+}
+
+/// Determine the inputs and the output path of each input set.
+///
+/// Fails if two input sets would be written to the same path, since one would
+/// silently overwrite the other.
+fn prepare_jobs(
+    command: &CompileCommand,
+    sets: Vec<InputSet>,
+) -> StrResult<Vec<(InputSet, PathBuf)>> {
+    let template = command.output();
+    let mut seen = HashMap::new();
+    let mut jobs = Vec::with_capacity(sets.len());
+
+    for (i, set) in sets.into_iter().enumerate() {
+        let index = i + 1;
+
+        // The set's inputs take precedence over the shared ones.
+        let mut inputs = command.common.inputs.clone();
+        for (key, value) in set {
+            inputs.retain(|(k, _)| *k != key);
+            inputs.push((key, value));
+        }
+
+        let output = batch_output(&template, index, &inputs)?;
+        if let Some(first) = seen.insert(output.clone(), index) {
+            bail!(
+                "input sets {first} and {index} would both be written to {}",
+                output.display()
+            );
+        }
+
+        jobs.push((inputs, output));
+    }
+
+    Ok(jobs)
+}
+
+/// Determine the output path for one input set.
+///
+/// Replaces `{i}` with the set's one-based index and `{key}` with the value of
+/// the input `key`. If the template contains no such placeholder, `-{i}` is
+/// appended to the file name.
+///
+/// Values that could make the path escape the template's directory are
+/// rejected.
+fn batch_output(template: &Path, index: usize, inputs: &InputSet) -> StrResult<PathBuf> {
+    let placeholder = |key: &str| format!("{{{key}}}");
+
+    let mut string = template.to_string_lossy().into_owned();
+    if !string.contains("{i}")
+        && !inputs.iter().any(|(key, _)| string.contains(&placeholder(key)))
+    {
+        let stem = template.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match template.extension() {
+            Some(ext) => format!("{stem}-{{i}}.{}", ext.to_string_lossy()),
+            None => format!("{stem}-{{i}}"),
+        };
+        string = template.with_file_name(name).to_string_lossy().into_owned();
+    }
+
+    string = string.replace("{i}", &index.to_string());
+    for (key, value) in inputs {
+        let placeholder = placeholder(key);
+        if !string.contains(&placeholder) {
+            continue;
+        }
+
+        if value.is_empty()
+            || value == "."
+            || value == ".."
+            || value.contains(['/', '\\', '\0'])
+        {
+            bail!(
+                "input `{key}` of input set {index} cannot be used in the output path \
+                 (expected a file name, found {value:?})"
+            );
+        }
+
+        string = string.replace(&placeholder, value);
+    }
+
+    Ok(PathBuf::from(string))
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[track_caller]
+    fn test(template: &str, inputs: &[(&str, &str)], expected: Option<&str>) {
+        let inputs: InputSet = inputs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        let output = batch_output(Path::new(template), 2, &inputs).ok();
+        assert_eq!(output, expected.map(PathBuf::from));
+    }
+
+    #[test]
+    fn test_batch_output() {
+        test("out/doc.pdf", &[("name", "a")], Some("out/doc-2.pdf"));
+        test("out/{i}.png", &[], Some("out/2.png"));
+        test("out/{name}-{i}.pdf", &[("name", "a b")], Some("out/a b-2.pdf"));
+        test("out/doc.pdf", &[("name", "../x")], Some("out/doc-2.pdf"));
+    }
+
+    #[test]
+    fn test_batch_output_rejects_paths() {
+        test("out/{name}.pdf", &[("name", "../x")], None);
+        test("out/{name}.pdf", &[("name", "..")], None);
+        test("out/{name}.pdf", &[("name", "a/b")], None);
+        test("out/{name}.pdf", &[("name", "a\\b")], None);
+        test("out/{name}.pdf", &[("name", "")], None);
+    }
+
+    #[test]
+    fn test_batch_rejects_duplicate_outputs() {
+        let command =
+            CompileCommand::try_parse_from(["compile", "main.typ", "out/{name}.pdf"])
+                .unwrap();
+        let set = |name: &str| vec![("name".to_string(), name.to_string())];
+        assert!(prepare_jobs(&command, vec![set("a"), set("b")]).is_ok());
+        assert!(prepare_jobs(&command, vec![set("a"), set("b"), set("a")]).is_err());
+    }
+}
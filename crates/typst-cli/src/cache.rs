@@ -0,0 +1,62 @@
+use std::fs;
+use std::hash::Hash;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tempfile::NamedTempFile;
+
+/// A persistent on-disk cache that speeds up repeated runs of the CLI.
+///
+/// The cache is opt-in and strictly best-effort: Entries that are missing,
+/// unreadable, or stale are silently recomputed and failures to write them
+/// are ignored. Entries are stored per Typst version so that changes to the
+/// cached data structures never lead to misinterpreted entries.
+pub struct DiskCache {
+    /// The directory that holds the entries of this Typst version.
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    /// Use the cache in the given directory.
+    pub fn new(dir: &Path) -> Self {
+        Self { dir: dir.join(crate::typst_version()) }
+    }
+
+    /// Load the entry with the given name.
+    pub fn load<T: DeserializeOwned>(&self, name: &str) -> Option<T> {
+        let file = fs::File::open(self.path(name)).ok()?;
+        serde_json::from_reader(BufReader::new(file)).ok()
+    }
+
+    /// Store an entry under the given name, replacing an existing one.
+    ///
+    /// The entry is written atomically, so that concurrent runs never observe
+    /// a partially written entry.
+    pub fn store<T: Serialize>(&self, name: &str, value: &T) {
+        self.write(name, value);
+    }
+
+    /// Write an entry through a temporary file.
+    fn write<T: Serialize>(&self, name: &str, value: &T) -> Option<()> {
+        fs::create_dir_all(&self.dir).ok()?;
+        let mut file = NamedTempFile::new_in(&self.dir).ok()?;
+        let mut writer = BufWriter::new(&mut file);
+        serde_json::to_writer(&mut writer, value).ok()?;
+        writer.flush().ok()?;
+        drop(writer);
+        file.persist(self.path(name)).ok()?;
+        Some(())
+    }
+
+    /// The path of the entry with the given name.
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(name).with_extension("json")
+    }
+}
+
+/// Computes the key under which a value's derived data is cached.
+pub fn key<T: Hash + ?Sized>(value: &T) -> String {
+    format!("{:032x}", typst::util::hash128(value))
+}
@@ -1,10 +1,11 @@
 use std::fs;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 
 use chrono::{Datelike, Timelike};
 use codespan_reporting::diagnostic::{Diagnostic, Label};
 use codespan_reporting::term;
-use ecow::{eco_format, EcoString};
+use ecow::{eco_format, EcoString, EcoVec};
 use parking_lot::RwLock;
 use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use typst::diag::{bail, At, Severity, SourceDiagnostic, StrResult};
@@ -17,6 +18,8 @@ use typst::visualize::Color;
 use typst::{World, WorldExt};
 
 use crate::args::{CompileCommand, DiagnosticFormat, Input, OutputFormat};
+use crate::deps::write_deps;
+use crate::report::{CompileReport, JsonDiagnostic};
 use crate::timings::Timer;
 use crate::watch::Status;
 use crate::world::SystemWorld;
@@ -65,7 +68,14 @@ impl CompileCommand {
 pub fn compile(mut timer: Timer, mut command: CompileCommand) -> StrResult<()> {
     let mut world =
         SystemWorld::new(&command.common).map_err(|err| eco_format!("{err}"))?;
-    timer.record(&mut world, |world| compile_once(world, &mut command, false))??;
+    if let Some(path) = command.batch.clone() {
+        let sets = crate::batch::read_input_sets(&path)?;
+        timer.record(&mut world, |world| {
+            crate::batch::compile_batch(world, &command, sets)
+        })??;
+    } else {
+        timer.record(&mut world, |world| compile_once(world, &mut command, false))??;
+    }
     Ok(())
 }
 
@@ -93,6 +103,11 @@ pub fn compile_once(
         print_diagnostics(world, &errors, &[], command.common.diagnostic_format)
             .map_err(|err| eco_format!("failed to print diagnostics ({err})"))?;
 
+        if let Some(path) = &command.report {
+            CompileReport::new(world, start.elapsed(), None, vec![], &errors, &[])
+                .write(path)?;
+        }
+
         return Ok(());
     }
 
@@ -100,10 +115,15 @@ pub fn compile_once(
     let result = typst::compile(world, &mut tracer);
     let warnings = tracer.warnings();
 
-    match result {
+    let (pages, outputs, errors) = match result {
         // Export the PDF / PNG.
         Ok(document) => {
-            export(world, &document, command, watching)?;
+            let outputs =
+                export(&document, command, watching.then(|| world.export_cache()))?;
+            if let Some(path) = &command.deps {
+                let inputs: Vec<_> = world.dependencies().collect();
+                write_deps(path, command.deps_format, inputs, &outputs)?;
+            }
             let duration = start.elapsed();
 
             if watching {
@@ -120,6 +140,8 @@ pub fn compile_once(
             if let Some(open) = command.open.take() {
                 open_file(open.as_deref(), &command.output())?;
             }
+
+            (Some(document.pages.len()), outputs, EcoVec::new())
         }
 
         // Print diagnostics.
@@ -137,37 +159,49 @@ pub fn compile_once(
                 command.common.diagnostic_format,
             )
             .map_err(|err| eco_format!("failed to print diagnostics ({err})"))?;
+
+            (None, vec![], errors)
         }
+    };
+
+    // Pin packages that were used for the first time, if requested.
+    world.packages().save()?;
+
+    if let Some(path) = &command.report {
+        CompileReport::new(world, start.elapsed(), pages, outputs, &errors, &warnings)
+            .write(path)?;
     }
 
     Ok(())
 }
 
 /// Export into the target format.
-fn export(
-    world: &mut SystemWorld,
+///
+/// If an export cache is given, pages that did not change since the last
+/// export are skipped. Returns the paths of all output files.
+pub fn export(
     document: &Document,
     command: &CompileCommand,
-    watching: bool,
-) -> StrResult<()> {
+    cache: Option<&ExportCache>,
+) -> StrResult<Vec<PathBuf>> {
     match command.output_format()? {
         OutputFormat::Png => {
-            export_image(world, document, command, watching, ImageExportFormat::Png)
+            export_image(document, command, cache, ImageExportFormat::Png)
         }
         OutputFormat::Svg => {
-            export_image(world, document, command, watching, ImageExportFormat::Svg)
+            export_image(document, command, cache, ImageExportFormat::Svg)
         }
         OutputFormat::Pdf => export_pdf(document, command),
     }
 }
 
 /// Export to a PDF.
-fn export_pdf(document: &Document, command: &CompileCommand) -> StrResult<()> {
+fn export_pdf(document: &Document, command: &CompileCommand) -> StrResult<Vec<PathBuf>> {
     let buffer = typst_pdf::pdf(document, Smart::Auto, now());
     let output = command.output();
-    fs::write(output, buffer)
+    fs::write(&output, buffer)
         .map_err(|err| eco_format!("failed to write PDF file ({err})"))?;
-    Ok(())
+    Ok(vec![output])
 }
 
 /// Get the current date and time in UTC.
@@ -191,12 +225,11 @@ enum ImageExportFormat {
 
 /// Export to one or multiple PNGs.
 fn export_image(
-    world: &mut SystemWorld,
     document: &Document,
     command: &CompileCommand,
-    watching: bool,
+    cache: Option<&ExportCache>,
     fmt: ImageExportFormat,
-) -> StrResult<()> {
+) -> StrResult<Vec<PathBuf>> {
     // Determine whether we have a `{n}` numbering.
     let output = command.output();
     let string = output.to_str().unwrap_or_default();
@@ -210,9 +243,6 @@ fn export_image(
     // 999 pages.
     let width = 1 + document.pages.len().checked_ilog10().unwrap_or(0) as usize;
 
-    let cache = world.export_cache();
-
-    // The results are collected in a `Vec<()>` which does not allocate.
     document
         .pages
         .par_iter()
@@ -226,11 +256,12 @@ fn export_image(
                 output.as_path()
             };
 
-            // If we are not watching, don't use the cache.
+            // If we are not watching, there is no cache.
             // If the frame is in the cache, skip it.
             // If the file does not exist, always create it.
-            if watching && cache.is_cached(i, &page.frame) && path.exists() {
-                return Ok(());
+            if cache.is_some_and(|cache| cache.is_cached(i, &page.frame)) && path.exists()
+            {
+                return Ok(path.to_path_buf());
             }
 
             match fmt {
@@ -251,11 +282,9 @@ fn export_image(
                 }
             }
 
-            Ok(())
+            Ok(path.to_path_buf())
         })
-        .collect::<Result<Vec<()>, EcoString>>()?;
-
-    Ok(())
+        .collect::<Result<Vec<PathBuf>, EcoString>>()
 }
 
 /// Caches exported files so that we can avoid re-exporting them if they haven't
@@ -311,6 +340,16 @@ pub fn print_diagnostics(
     warnings: &[SourceDiagnostic],
     diagnostic_format: DiagnosticFormat,
 ) -> Result<(), codespan_reporting::files::Error> {
+    if diagnostic_format == DiagnosticFormat::Json {
+        let mut out = terminal::out();
+        for diagnostic in warnings.iter().chain(errors) {
+            let json = serde_json::to_string(&JsonDiagnostic::new(world, diagnostic))
+                .map_err(io::Error::from)?;
+            writeln!(out, "{json}")?;
+        }
+        return Ok(());
+    }
+
     let mut config = term::Config { tab_width: 2, ..Default::default() };
     if diagnostic_format == DiagnosticFormat::Short {
         config.display_style = term::DisplayStyle::Short;
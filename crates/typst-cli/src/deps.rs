@@ -0,0 +1,126 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ecow::eco_format;
+use serde::Serialize;
+use typst::diag::StrResult;
+
+use crate::args::DepsFormat;
+
+/// The files a compilation read and wrote, in JSON form.
+#[derive(Debug, Clone, Serialize)]
+struct JsonDeps<'a> {
+    /// The files that were read.
+    inputs: &'a [PathBuf],
+    /// The files that were written.
+    outputs: &'a [PathBuf],
+}
+
+/// Write a dependency file listing the given inputs and outputs to `path`.
+///
+/// Only inputs that exist are listed, as build systems typically fail on
+/// prerequisites that they cannot find.
+pub fn write_deps(
+    path: &Path,
+    format: DepsFormat,
+    inputs: impl IntoIterator<Item = PathBuf>,
+    outputs: &[PathBuf],
+) -> StrResult<()> {
+    let mut inputs: Vec<PathBuf> =
+        inputs.into_iter().filter(|path| path.exists()).collect();
+    inputs.sort();
+    inputs.dedup();
+
+    let content = match format {
+        DepsFormat::Make => make_deps(&inputs, outputs),
+        DepsFormat::Json => {
+            serde_json::to_string_pretty(&JsonDeps { inputs: &inputs, outputs })
+                .map_err(|err| eco_format!("failed to serialize dependencies ({err})"))?
+        }
+    };
+
+    fs::write(path, content)
+        .map_err(|err| eco_format!("failed to write dependency file ({err})"))
+}
+
+/// Format dependencies as a Makefile rule, as understood by make and ninja.
+///
+/// Like with `gcc -MP`, an empty rule is added for each input so that deleting
+/// an input doesn't break the build.
+fn make_deps(inputs: &[PathBuf], outputs: &[PathBuf]) -> String {
+    let mut out = String::new();
+    let targets: Vec<_> = outputs.iter().map(|path| escape(path)).collect();
+    out.push_str(&targets.join(" "));
+    out.push(':');
+    for input in inputs {
+        out.push_str(" \\\n  ");
+        out.push_str(&escape(input));
+    }
+    out.push('\n');
+
+    for input in inputs {
+        out.push('\n');
+        out.push_str(&escape(input));
+        out.push_str(":\n");
+    }
+
+    out
+}
+
+/// Escape a path for use in a Makefile rule.
+///
+/// Besides spaces and comments, colons and backslashes are escaped so that
+/// Windows paths like `C:\doc.typ` don't break the rule.
+fn escape(path: &Path) -> String {
+    let mut out = String::new();
+    for c in path.to_string_lossy().chars() {
+        match c {
+            ' ' | '#' | ':' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '$' => out.push_str("$$"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn test(path: &str, expected: &str) {
+        assert_eq!(escape(Path::new(path)), expected);
+    }
+
+    #[test]
+    fn test_escape_plain() {
+        test("main.typ", "main.typ");
+        test("/home/user/doc.pdf", "/home/user/doc.pdf");
+    }
+
+    #[test]
+    fn test_escape_special() {
+        test("my doc.typ", "my\\ doc.typ");
+        test("#1.typ", "\\#1.typ");
+        test("price$.typ", "price$$.typ");
+    }
+
+    #[test]
+    fn test_escape_windows() {
+        test("C:\\Users\\doc.typ", "C\\:\\\\Users\\\\doc.typ");
+        test("a:b", "a\\:b");
+    }
+
+    #[test]
+    fn test_make_deps() {
+        let inputs = [PathBuf::from("a b.typ"), PathBuf::from("c.png")];
+        let outputs = [PathBuf::from("out.pdf")];
+        assert_eq!(
+            make_deps(&inputs, &outputs),
+            "out.pdf: \\\n  a\\ b.typ \\\n  c.png\n\na\\ b.typ:\n\nc.png:\n",
+        );
+    }
+}
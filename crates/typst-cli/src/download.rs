@@ -25,9 +25,18 @@ static CERT: Lazy<Option<Certificate>> = Lazy::new(|| {
 });
 
 /// Download binary data and display its progress.
+///
+/// The progress is not displayed if the terminal output must stay
+/// machine-readable.
 #[allow(clippy::result_large_err)]
 pub fn download_with_progress(url: &str) -> Result<Vec<u8>, ureq::Error> {
     let response = download(url)?;
+    if terminal::json() {
+        let mut data = vec![];
+        response.into_reader().read_to_end(&mut data)?;
+        return Ok(data);
+    }
+
     Ok(RemoteReader::from_response(response).download()?)
 }
 
@@ -1,18 +1,27 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::OnceLock;
+use std::time::SystemTime;
 
 use fontdb::{Database, Source};
+use serde::{Deserialize, Serialize};
 use typst::diag::StrResult;
 use typst::text::{Font, FontBook, FontInfo, FontVariant};
 use typst_timing::TimingScope;
 
 use crate::args::FontsCommand;
+use crate::cache::{self, DiskCache};
+
+/// The name of the cache entry that holds the result of the font search.
+const FONT_CACHE: &str = "fonts";
 
 /// Execute a font listing command.
 pub fn fonts(command: &FontsCommand) -> StrResult<()> {
+    let cache = command.cache_path.as_deref().map(DiskCache::new);
     let mut searcher = FontSearcher::new();
-    searcher.search(&command.font_paths);
+    searcher.search(&command.font_paths, cache.as_ref());
 
     for (name, infos) in searcher.book.families() {
         println!("{name}");
@@ -44,11 +53,14 @@ pub struct FontSlot {
     index: u32,
     /// The lazily loaded font.
     font: OnceLock<Option<Font>>,
+    /// Whether the font was requested in the ongoing compilation.
+    accessed: AtomicBool,
 }
 
 impl FontSlot {
     /// Get the font for this slot.
     pub fn get(&self) -> Option<Font> {
+        self.accessed.store(true, Ordering::Relaxed);
         self.font
             .get_or_init(|| {
                 let _scope = TimingScope::new("load font", None);
@@ -57,6 +69,22 @@ impl FontSlot {
             })
             .clone()
     }
+
+    /// The path of the font file, if it is not embedded in the binary.
+    pub fn path(&self) -> Option<&Path> {
+        (!self.path.as_os_str().is_empty()).then_some(self.path.as_path())
+    }
+
+    /// Whether the font was requested in the ongoing compilation.
+    pub fn accessed(&self) -> bool {
+        self.accessed.load(Ordering::Relaxed)
+    }
+
+    /// Marks the font as not yet requested in preparation of the next
+    /// compilation.
+    pub fn reset(&mut self) {
+        *self.accessed.get_mut() = false;
+    }
 }
 
 impl FontSearcher {
@@ -66,35 +94,25 @@ impl FontSearcher {
     }
 
     /// Search everything that is available.
-    pub fn search(&mut self, font_paths: &[PathBuf]) {
-        let mut db = Database::new();
-
-        // Font paths have highest priority.
-        for path in font_paths {
-            db.load_fonts_dir(path);
-        }
-
-        // System fonts have second priority.
-        db.load_system_fonts();
+    ///
+    /// Extracting the metadata of a font requires parsing it in full. With a
+    /// cache, this only happens for fonts that are new or were modified since
+    /// the last search. If nothing changed at all, the search is skipped
+    /// entirely.
+    pub fn search(&mut self, font_paths: &[PathBuf], cache: Option<&DiskCache>) {
+        let faces = match cache {
+            Some(cache) => cached_faces(font_paths, cache),
+            None => scan_faces(font_paths, &HashMap::new()),
+        };
 
-        for face in db.faces() {
-            let path = match &face.source {
-                Source::File(path) | Source::SharedFile(path, _) => path,
-                // We never add binary sources to the database, so there
-                // shouln't be any.
-                Source::Binary(_) => continue,
-            };
-
-            let info = db
-                .with_face_data(face.id, FontInfo::new)
-                .expect("database must contain this font");
-
-            if let Some(info) = info {
+        for face in faces {
+            if let Some(info) = face.info {
                 self.book.push(info);
                 self.fonts.push(FontSlot {
-                    path: path.clone(),
+                    path: face.path,
                     index: face.index,
                     font: OnceLock::new(),
+                    accessed: AtomicBool::new(false),
                 });
             }
         }
@@ -115,8 +133,251 @@ impl FontSearcher {
                     path: PathBuf::new(),
                     index: i as u32,
                     font: OnceLock::from(Some(font)),
+                    accessed: AtomicBool::new(false),
                 });
             }
         }
     }
 }
+
+/// The key under which the metadata of a font face is cached.
+///
+/// The key includes the file's size and modification time, so that the entry
+/// is invalidated when the font is replaced.
+fn font_key(path: &Path, index: u32) -> Option<String> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?;
+    Some(cache::key(&(path, index, metadata.len(), modified)))
+}
+
+/// The cached result of a font search.
+#[derive(PartialEq, Serialize, Deserialize)]
+struct FontIndex {
+    /// The font paths that were searched in addition to the system's fonts.
+    font_paths: Vec<PathBuf>,
+    /// The modification times of the files and directories that determine
+    /// which fonts are found. If any of them changes, the system's fonts must
+    /// be scanned again.
+    stamps: BTreeMap<PathBuf, Option<SystemTime>>,
+    /// The discovered font faces, in order of priority.
+    faces: Vec<FontFace>,
+}
+
+impl FontIndex {
+    /// Whether the fonts on the system are still the same as when the index
+    /// was created.
+    fn is_fresh(&self) -> bool {
+        let mut stamps = stamp_roots(&self.font_paths);
+        stamp_parents(&self.faces, &mut stamps);
+        stamps == self.stamps
+            && self.faces.iter().all(|face| {
+                face.key.is_some() && face.key == font_key(&face.path, face.index)
+            })
+    }
+}
+
+/// A font face discovered by a search.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+struct FontFace {
+    /// The path of the font file.
+    path: PathBuf,
+    /// The index of the face in its collection.
+    index: u32,
+    /// The key under which the face's metadata is cached, if the font file's
+    /// metadata could be read.
+    key: Option<String>,
+    /// The face's metadata or `None` if the face is unusable.
+    info: Option<FontInfo>,
+}
+
+/// Find the font faces, reusing the results of the last search where
+/// possible.
+fn cached_faces(font_paths: &[PathBuf], cache: &DiskCache) -> Vec<FontFace> {
+    let cached: Option<FontIndex> = cache.load(FONT_CACHE);
+    if cached
+        .as_ref()
+        .is_some_and(|index| index.font_paths == font_paths && index.is_fresh())
+    {
+        return cached.unwrap().faces;
+    }
+
+    // Take the stamps before scanning, so that changes made during the scan
+    // invalidate the index on the next run.
+    let mut stamps = stamp_roots(font_paths);
+
+    let known = cached
+        .iter()
+        .flat_map(|index| &index.faces)
+        .filter_map(|face| Some((face.key.clone()?, face.info.clone())))
+        .collect();
+    let faces = scan_faces(font_paths, &known);
+
+    stamp_parents(&faces, &mut stamps);
+
+    let index = FontIndex { font_paths: font_paths.to_vec(), stamps, faces };
+
+    // Only write the cache if fonts were added, modified, or removed.
+    if cached.as_ref() != Some(&index) {
+        cache.store(FONT_CACHE, &index);
+    }
+
+    index.faces
+}
+
+/// Find the font faces in the font paths and on the system.
+///
+/// The metadata of faces whose key is among the known ones is not extracted
+/// again.
+fn scan_faces(
+    font_paths: &[PathBuf],
+    known: &HashMap<String, Option<FontInfo>>,
+) -> Vec<FontFace> {
+    let mut db = Database::new();
+
+    // Font paths have highest priority.
+    for path in font_paths {
+        db.load_fonts_dir(path);
+    }
+
+    // System fonts have second priority.
+    db.load_system_fonts();
+
+    let mut faces = vec![];
+    for face in db.faces() {
+        let path = match &face.source {
+            Source::File(path) | Source::SharedFile(path, _) => path,
+            // We never add binary sources to the database, so there
+            // shouln't be any.
+            Source::Binary(_) => continue,
+        };
+
+        let key = font_key(path, face.index);
+        let info = match key.as_ref().and_then(|key| known.get(key)) {
+            Some(info) => info.clone(),
+            None => db
+                .with_face_data(face.id, FontInfo::new)
+                .expect("database must contain this font"),
+        };
+
+        faces.push(FontFace { path: path.clone(), index: face.index, key, info });
+    }
+
+    faces
+}
+
+/// The well-known directories that contain the system's fonts or the
+/// configuration that determines where they are searched for.
+fn system_font_roots() -> Vec<PathBuf> {
+    let mut roots = vec![];
+
+    #[cfg(target_os = "windows")]
+    {
+        if let Some(dir) = std::env::var_os("WINDIR") {
+            roots.push(Path::new(&dir).join("Fonts"));
+        }
+        if let Some(dir) = dirs::data_local_dir() {
+            roots.push(dir.join("Microsoft").join("Windows").join("Fonts"));
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        roots.push("/Library/Fonts".into());
+        roots.push("/Network/Library/Fonts".into());
+        roots.push("/System/Library/Fonts".into());
+        if let Some(dir) = dirs::home_dir() {
+            roots.push(dir.join("Library").join("Fonts"));
+        }
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        roots.push("/etc/fonts".into());
+        roots.push("/usr/share/fonts".into());
+        roots.push("/usr/local/share/fonts".into());
+        if let Some(dir) = dirs::home_dir() {
+            roots.push(dir.join(".fonts"));
+        }
+        if let Some(dir) = dirs::data_dir() {
+            roots.push(dir.join("fonts"));
+        }
+        if let Some(dir) = dirs::config_dir() {
+            roots.push(dir.join("fontconfig"));
+        }
+    }
+
+    roots
+}
+
+/// Record the modification times of everything in the font paths and the
+/// system's well-known font directories.
+fn stamp_roots(font_paths: &[PathBuf]) -> BTreeMap<PathBuf, Option<SystemTime>> {
+    let mut stamps = BTreeMap::new();
+    for root in font_paths.iter().cloned().chain(system_font_roots()) {
+        stamp_tree(&root, &mut stamps);
+    }
+    stamps
+}
+
+/// Record the modification times of the directories that contain the faces.
+///
+/// Fonts can also live outside of the well-known directories, for instance in
+/// ones that are configured through fontconfig. A font added next to them
+/// changes their modification time.
+fn stamp_parents(faces: &[FontFace], stamps: &mut BTreeMap<PathBuf, Option<SystemTime>>) {
+    for face in faces {
+        if let Some(dir) = face.path.parent() {
+            stamps.entry(dir.into()).or_insert_with(|| modified(dir));
+        }
+    }
+}
+
+/// Record the modification times of a file or directory and everything below
+/// it.
+fn stamp_tree(path: &Path, stamps: &mut BTreeMap<PathBuf, Option<SystemTime>>) {
+    stamps.insert(path.into(), modified(path));
+    let Ok(entries) = fs::read_dir(path) else { return };
+    for entry in entries.flatten() {
+        // Don't follow symlinks to avoid running in circles.
+        if entry.file_type().is_ok_and(|ty| ty.is_dir()) {
+            stamp_tree(&entry.path(), stamps);
+        } else {
+            let path = entry.path();
+            let time = modified(&path);
+            stamps.insert(path, time);
+        }
+    }
+}
+
+/// The modification time of a file or directory, if it exists.
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_font_index_freshness() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("font.ttf");
+        fs::write(&path, b"font").unwrap();
+
+        let font_paths = vec![dir.path().to_path_buf()];
+        let faces = vec![FontFace {
+            key: font_key(&path, 0),
+            path: path.clone(),
+            index: 0,
+            info: None,
+        }];
+        let mut stamps = stamp_roots(&font_paths);
+        stamp_parents(&faces, &mut stamps);
+        let index = FontIndex { font_paths, stamps, faces };
+        assert!(index.is_fresh());
+
+        // Adding a directory, which may contain fonts, invalidates the index.
+        fs::create_dir(dir.path().join("more")).unwrap();
+        assert!(!index.is_fresh());
+    }
+}
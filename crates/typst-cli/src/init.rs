@@ -4,10 +4,8 @@ use std::path::Path;
 use codespan_reporting::term::termcolor::{Color, ColorSpec, WriteColor};
 use ecow::eco_format;
 use fs_extra::dir::CopyOptions;
-use typst::diag::{bail, FileError, StrResult};
-use typst::syntax::package::{
-    PackageManifest, PackageSpec, TemplateInfo, VersionlessPackageSpec,
-};
+use typst::diag::{bail, StrResult};
+use typst::syntax::package::{PackageSpec, TemplateInfo, VersionlessPackageSpec};
 
 use crate::args::InitCommand;
 
@@ -25,10 +23,10 @@ pub fn init(command: &InitCommand) -> StrResult<()> {
     })?;
 
     // Find or download the package.
-    let package_path = crate::package::prepare_package(&spec)?;
+    let package_path = crate::package::prepare_package(&spec, None)?;
 
     // Parse the manifest.
-    let manifest = parse_manifest(&package_path)?;
+    let manifest = crate::package::parse_manifest(&package_path)?;
     manifest.validate(&spec)?;
 
     // Ensure that it is indeed a template.
@@ -48,20 +46,6 @@ pub fn init(command: &InitCommand) -> StrResult<()> {
     Ok(())
 }
 
-/// Parses the manifest of the package located at `package_path`.
-fn parse_manifest(package_path: &Path) -> StrResult<PackageManifest> {
-    let toml_path = package_path.join("typst.toml");
-    let string = std::fs::read_to_string(&toml_path).map_err(|err| {
-        eco_format!(
-            "failed to read package manifest ({})",
-            FileError::from_io(err, &toml_path)
-        )
-    })?;
-
-    toml::from_str(&string)
-        .map_err(|err| eco_format!("package manifest is malformed ({})", err.message()))
-}
-
 /// Creates the project directory with the template's contents and returns the
 /// path at which it was created.
 fn scaffold_project(
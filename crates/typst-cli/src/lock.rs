@@ -0,0 +1,298 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use ecow::{eco_format, EcoString};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use typst::diag::{bail, PackageError, PackageResult, StrResult};
+use typst::syntax::package::{PackageSpec, PackageVersion};
+
+use crate::package::prepare_package;
+
+/// The name of the lockfile within the project root.
+pub const LOCKFILE_NAME: &str = "typst.lock";
+
+/// The name of the directory within the project root that holds vendored
+/// packages.
+pub const VENDOR_DIR_NAME: &str = "vendor";
+
+/// The current version of the lockfile format.
+const LOCKFILE_VERSION: u32 = 1;
+
+/// The contents of a `typst.lock` file.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Lockfile {
+    /// The version of the lockfile format.
+    pub version: u32,
+    /// The pinned packages, sorted by namespace, name, and version.
+    #[serde(default, rename = "package")]
+    pub packages: Vec<LockedPackage>,
+}
+
+/// A package pinned in the lockfile.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LockedPackage {
+    /// The namespace the package lives in.
+    pub namespace: EcoString,
+    /// The name of the package within its namespace.
+    pub name: EcoString,
+    /// The exact version of the package.
+    pub version: PackageVersion,
+    /// The content hash of the package's files.
+    pub hash: EcoString,
+}
+
+impl LockedPackage {
+    /// Whether this entry pins the given package.
+    fn matches(&self, spec: &PackageSpec) -> bool {
+        self.namespace == spec.namespace
+            && self.name == spec.name
+            && self.version == spec.version
+    }
+}
+
+impl Lockfile {
+    /// Create an empty lockfile.
+    pub fn new() -> Self {
+        Self { version: LOCKFILE_VERSION, packages: vec![] }
+    }
+
+    /// Read the lockfile at the given path, if it exists.
+    pub fn read(path: &Path) -> StrResult<Option<Self>> {
+        let string = match fs::read_to_string(path) {
+            Ok(string) => string,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => bail!("failed to read lockfile ({err})"),
+        };
+
+        let lockfile: Self = toml::from_str(&string)
+            .map_err(|err| eco_format!("lockfile is malformed ({})", err.message()))?;
+
+        if lockfile.version != LOCKFILE_VERSION {
+            bail!(
+                "lockfile has unsupported version {} (expected {LOCKFILE_VERSION})",
+                lockfile.version
+            );
+        }
+
+        Ok(Some(lockfile))
+    }
+
+    /// Write the lockfile to the given path.
+    pub fn write(&self, path: &Path) -> StrResult<()> {
+        let body = toml::to_string(self)
+            .map_err(|err| eco_format!("failed to serialize lockfile ({err})"))?;
+        let string = format!(
+            "# This file is automatically generated by Typst.\n\
+             # It is not intended for manual editing.\n{body}"
+        );
+        fs::write(path, string)
+            .map_err(|err| eco_format!("failed to write lockfile ({err})"))
+    }
+
+    /// Find the entry for a package.
+    pub fn get(&self, spec: &PackageSpec) -> Option<&LockedPackage> {
+        self.packages.iter().find(|package| package.matches(spec))
+    }
+
+    /// Pin a package with the given hash, replacing an existing entry.
+    pub fn insert(&mut self, spec: &PackageSpec, hash: EcoString) {
+        self.packages.retain(|package| !package.matches(spec));
+        self.packages.push(LockedPackage {
+            namespace: spec.namespace.clone(),
+            name: spec.name.clone(),
+            version: spec.version,
+            hash,
+        });
+        self.packages.sort_by(|a, b| {
+            (&a.namespace, &a.name, a.version).cmp(&(&b.namespace, &b.name, b.version))
+        });
+    }
+}
+
+/// Resolves the packages of a project, honoring its lockfile and vendor
+/// directory.
+pub struct PackageLock {
+    /// The path of the project's lockfile.
+    path: PathBuf,
+    /// The project's vendor directory.
+    vendor: PathBuf,
+    /// Whether packages that are not yet pinned are rejected.
+    locked: bool,
+    /// Whether newly pinned packages are written back to the lockfile.
+    update: bool,
+    /// The current contents of the lockfile and whether they changed since
+    /// they were last written.
+    file: Mutex<(Lockfile, bool)>,
+    /// The packages that were already verified in this session.
+    verified: Mutex<HashSet<PackageSpec>>,
+}
+
+impl PackageLock {
+    /// Load the lockfile state of the project at `root`.
+    pub fn new(root: &Path, locked: bool, update: bool) -> StrResult<Self> {
+        let path = root.join(LOCKFILE_NAME);
+        let existing = Lockfile::read(&path)?;
+        if locked && existing.is_none() {
+            bail!(
+                "cannot compile with `--locked` because there is no lockfile \
+                 (searched at {})",
+                path.display()
+            );
+        }
+
+        let file = existing.unwrap_or_else(Lockfile::new);
+
+        Ok(Self {
+            path,
+            vendor: root.join(VENDOR_DIR_NAME),
+            locked,
+            update,
+            file: Mutex::new((file, false)),
+            verified: Mutex::new(HashSet::new()),
+        })
+    }
+
+    /// Make a package available and verify it against the lockfile.
+    ///
+    /// Vendored packages take precedence over the local data and cache
+    /// directories. Only packages from the registry are pinned, since local
+    /// packages are expected to change.
+    pub fn resolve(&self, spec: &PackageSpec) -> PackageResult<PathBuf> {
+        let vendored = vendored_dir(&self.vendor, spec);
+        let dir = if vendored.exists() {
+            vendored
+        } else {
+            let expected = self.file.lock().0.get(spec).map(|p| p.hash.clone());
+            prepare_package(spec, expected.as_deref())?
+        };
+
+        if is_pinned(spec) {
+            self.verify(spec, &dir)?;
+        }
+
+        Ok(dir)
+    }
+
+    /// Check the package's contents against its pinned hash or pin it if it is
+    /// not yet in the lockfile.
+    fn verify(&self, spec: &PackageSpec, dir: &Path) -> PackageResult<()> {
+        if self.verified.lock().contains(spec) {
+            return Ok(());
+        }
+
+        let hash = hash_package(dir)?;
+        let mut file = self.file.lock();
+        match file.0.get(spec) {
+            Some(locked) if locked.hash != hash => {
+                return Err(PackageError::Other(Some(eco_format!(
+                    "contents of {spec} do not match the lockfile \
+                     (expected {}, found {hash})",
+                    locked.hash,
+                ))));
+            }
+            Some(_) => {}
+            None if self.locked => {
+                return Err(PackageError::Other(Some(eco_format!(
+                    "{spec} is not pinned in the lockfile, \
+                     but `--locked` was passed"
+                ))));
+            }
+            None => {
+                file.0.insert(spec, hash);
+                file.1 = true;
+            }
+        }
+
+        self.verified.lock().insert(spec.clone());
+        Ok(())
+    }
+
+    /// Write the lockfile to disk if new packages were pinned and updating it
+    /// was requested.
+    ///
+    /// The lockfile is created if it doesn't exist yet. Without `--update-lock`,
+    /// new pins are only checked within the current session.
+    pub fn save(&self) -> StrResult<()> {
+        let mut file = self.file.lock();
+        if self.update && file.1 {
+            file.0.write(&self.path)?;
+            file.1 = false;
+        }
+        Ok(())
+    }
+}
+
+/// Whether a package is pinned in the lockfile.
+///
+/// This is only the case for packages from the `@preview` registry. Packages
+/// in other namespaces live in the local data directory and are edited in
+/// place, so pinning their contents would only get in the way.
+pub fn is_pinned(spec: &PackageSpec) -> bool {
+    spec.namespace == "preview"
+}
+
+/// The directory in which a vendored copy of the package lives, given the
+/// project's vendor directory.
+pub fn vendored_dir(vendor: &Path, spec: &PackageSpec) -> PathBuf {
+    vendor
+        .join(spec.namespace.as_str())
+        .join(spec.name.as_str())
+        .join(spec.version.to_string())
+}
+
+/// Compute the content hash of a package directory.
+///
+/// The hash is a SHA-256 digest of the relative paths and contents of all
+/// files in the directory, in sorted order, so that it is independent of the platform and
+/// the file system.
+pub fn hash_package(dir: &Path) -> PackageResult<EcoString> {
+    let mut files = vec![];
+    collect_files(dir, dir, &mut files).map_err(|err| {
+        PackageError::Other(Some(eco_format!("failed to hash package ({err})")))
+    })?;
+    files.sort();
+
+    let mut state = Sha256::new();
+    for (relative, path) in files {
+        let data = fs::read(&path).map_err(|err| {
+            PackageError::Other(Some(eco_format!("failed to hash package ({err})")))
+        })?;
+        state.update(relative.as_bytes());
+        state.update([0]);
+        state.update((data.len() as u64).to_le_bytes());
+        state.update(&data);
+    }
+
+    let mut hash = EcoString::from("sha256-");
+    for byte in state.finalize() {
+        hash.push_str(&eco_format!("{byte:02x}"));
+    }
+
+    Ok(hash)
+}
+
+/// Recursively collect all files below `dir` along with their `/`-separated
+/// paths relative to `base`.
+fn collect_files(
+    base: &Path,
+    dir: &Path,
+    files: &mut Vec<(String, PathBuf)>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(base, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(base) {
+            let components: Vec<_> = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect();
+            files.push((components.join("/"), path));
+        }
+    }
+    Ok(())
+}
@@ -1,14 +1,20 @@
 mod args;
+mod batch;
+mod cache;
 mod compile;
+mod deps;
 mod download;
 mod fonts;
 mod init;
+mod lock;
 mod package;
 mod query;
+mod report;
 mod terminal;
 mod timings;
 #[cfg(feature = "self-update")]
 mod update;
+mod vendor;
 mod watch;
 mod world;
 
@@ -22,6 +28,7 @@ use codespan_reporting::term::termcolor::WriteColor;
 use once_cell::sync::Lazy;
 
 use crate::args::{CliArguments, Command};
+use crate::report::JsonDiagnostic;
 use crate::timings::Timer;
 
 thread_local! {
@@ -42,6 +49,8 @@ fn main() -> ExitCode {
         Command::Init(command) => crate::init::init(command),
         Command::Query(command) => crate::query::query(command),
         Command::Fonts(command) => crate::fonts::fonts(command),
+        Command::Vendor(command) => crate::vendor::vendor(command),
+        Command::Package(command) => crate::package::package(command),
         Command::Update(command) => crate::update::update(command),
     };
 
@@ -65,6 +74,12 @@ fn typst_version() -> &'static str {
 
 /// Print an application-level error (independent from a source file).
 fn print_error(msg: &str) -> io::Result<()> {
+    if terminal::json() {
+        let json = serde_json::to_string(&JsonDiagnostic::error(msg.into()))
+            .map_err(io::Error::from)?;
+        return writeln!(terminal::out(), "{json}");
+    }
+
     let styles = term::Styles::default();
 
     let mut output = terminal::out();
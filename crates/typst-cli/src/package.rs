@@ -1,24 +1,51 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 
 use codespan_reporting::term::{self, termcolor};
-use ecow::eco_format;
+use ecow::{eco_format, EcoString};
 use termcolor::WriteColor;
-use typst::diag::{bail, PackageError, PackageResult, StrResult};
+use typst::diag::{bail, FileError, PackageError, PackageResult, StrResult};
 use typst::syntax::package::{
-    PackageInfo, PackageSpec, PackageVersion, VersionlessPackageSpec,
+    PackageInfo, PackageManifest, PackageSpec, PackageVersion, VersionlessPackageSpec,
 };
+use typst::syntax::VirtualPath;
 
+use crate::args::{
+    PackageCheckCommand, PackageCleanCommand, PackageCommand, PackageLinkCommand,
+    PackageListCommand,
+};
 use crate::download::{download, download_with_progress};
-use crate::terminal;
+use crate::lock::hash_package;
+use crate::{print_error, terminal};
 
 const HOST: &str = "https://packages.typst.org";
 
+/// The directory below the data and cache directories in which packages are
+/// stored.
+const PACKAGES_SUBDIR: &str = "typst/packages";
+
+/// Execute a package management command.
+pub fn package(command: &PackageCommand) -> StrResult<()> {
+    match command {
+        PackageCommand::List(command) => list(command),
+        PackageCommand::Clean(command) => clean(command),
+        PackageCommand::Link(command) => link(command),
+        PackageCommand::Check(command) => check(command),
+    }
+}
+
 /// Make a package available in the on-disk cache.
-pub fn prepare_package(spec: &PackageSpec) -> PackageResult<PathBuf> {
+///
+/// If an `expected` content hash is given, a freshly downloaded package is
+/// verified against it and discarded if it doesn't match.
+pub fn prepare_package(
+    spec: &PackageSpec,
+    expected: Option<&str>,
+) -> PackageResult<PathBuf> {
     let subdir =
-        format!("typst/packages/{}/{}/{}", spec.namespace, spec.name, spec.version);
+        format!("{PACKAGES_SUBDIR}/{}/{}/{}", spec.namespace, spec.name, spec.version);
 
     if let Some(data_dir) = dirs::data_dir() {
         let dir = data_dir.join(&subdir);
@@ -36,6 +63,9 @@ pub fn prepare_package(spec: &PackageSpec) -> PackageResult<PathBuf> {
         // Download from network if it doesn't exist yet.
         if spec.namespace == "preview" {
             download_package(spec, &dir)?;
+            if let Some(expected) = expected {
+                verify_download(spec, &dir, expected)?;
+            }
             if dir.exists() {
                 return Ok(dir);
             }
@@ -62,7 +92,7 @@ pub fn determine_latest_version(
         // For other namespaces, search locally. We only search in the data
         // directory and not the cache directory, because the latter is not
         // intended for storage of local packages.
-        let subdir = format!("typst/packages/{}/{}", spec.namespace, spec.name);
+        let subdir = format!("{PACKAGES_SUBDIR}/{}/{}", spec.namespace, spec.name);
         dirs::data_dir()
             .into_iter()
             .flat_map(|dir| std::fs::read_dir(dir.join(&subdir)).ok())
@@ -83,7 +113,7 @@ fn download_package(spec: &PackageSpec, package_dir: &Path) -> PackageResult<()>
 
     let url = format!("{HOST}/preview/{}-{}.tar.gz", spec.name, spec.version);
 
-    print_downloading(spec).unwrap();
+    print_status("downloading", spec).map_err(|err| PackageError::Other(Some(err)))?;
 
     let data = match download_with_progress(&url) {
         Ok(data) => data,
@@ -100,6 +130,24 @@ fn download_package(spec: &PackageSpec, package_dir: &Path) -> PackageResult<()>
     })
 }
 
+/// Check a freshly downloaded package against its expected content hash,
+/// removing it again if it doesn't match.
+fn verify_download(
+    spec: &PackageSpec,
+    package_dir: &Path,
+    expected: &str,
+) -> PackageResult<()> {
+    let hash = hash_package(package_dir)?;
+    if hash != expected {
+        fs::remove_dir_all(package_dir).ok();
+        return Err(PackageError::Other(Some(eco_format!(
+            "downloaded archive of {spec} does not match the lockfile \
+             (expected {expected}, found {hash})"
+        ))));
+    }
+    Ok(())
+}
+
 /// Download the `@preview` package index.
 fn download_index() -> StrResult<Vec<PackageInfo>> {
     let url = format!("{HOST}/preview/index.json");
@@ -114,14 +162,476 @@ fn download_index() -> StrResult<Vec<PackageInfo>> {
     }
 }
 
-/// Print that a package downloading is happening.
-fn print_downloading(spec: &PackageSpec) -> io::Result<()> {
+/// Parses the manifest of the package located at `package_path`.
+pub fn parse_manifest(package_path: &Path) -> StrResult<PackageManifest> {
+    let string = read_manifest(package_path)?;
+    toml::from_str(&string)
+        .map_err(|err| eco_format!("package manifest is malformed ({})", err.message()))
+}
+
+/// Reads the raw manifest of the package located at `package_path`.
+fn read_manifest(package_path: &Path) -> StrResult<String> {
+    let toml_path = package_path.join("typst.toml");
+    fs::read_to_string(&toml_path).map_err(|err| {
+        eco_format!(
+            "failed to read package manifest ({})",
+            FileError::from_io(err, &toml_path)
+        )
+    })
+}
+
+/// Lists all packages available in the local data and cache directories.
+///
+/// A package that is stored in both directories is only listed once. The
+/// descriptions are taken from the packages' manifests.
+pub fn local_packages() -> Vec<(PackageSpec, Option<EcoString>)> {
+    let mut packages: Vec<_> = [dirs::data_dir(), dirs::cache_dir()]
+        .into_iter()
+        .flatten()
+        .flat_map(|dir| stored_packages(&dir))
+        .map(|(spec, path)| {
+            let description = parse_manifest(&path)
+                .ok()
+                .and_then(|manifest| manifest.package.description);
+            (spec, description)
+        })
+        .collect();
+
+    packages.sort_by(|(a, _), (b, _)| {
+        (&a.namespace, &a.name, a.version).cmp(&(&b.namespace, &b.name, b.version))
+    });
+    packages.dedup_by(|(a, _), (b, _)| a == b);
+    packages
+}
+
+/// Lists the packages stored below the given data or cache directory.
+fn stored_packages(dir: &Path) -> Vec<(PackageSpec, PathBuf)> {
+    fn subdirs(path: &Path) -> impl Iterator<Item = (String, PathBuf)> {
+        fs::read_dir(path)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .filter_map(|path| Some((path.file_name()?.to_str()?.to_string(), path)))
+    }
+
+    let mut packages: Vec<(PackageSpec, PathBuf)> = vec![];
+    for (namespace, namespace_dir) in subdirs(&dir.join(PACKAGES_SUBDIR)) {
+        for (name, name_dir) in subdirs(&namespace_dir) {
+            for (version, version_dir) in subdirs(&name_dir) {
+                if let Ok(spec) = format!("@{namespace}/{name}:{version}").parse() {
+                    packages.push((spec, version_dir));
+                }
+            }
+        }
+    }
+
+    packages.sort_by(|(a, _), (b, _)| {
+        (&a.namespace, &a.name, a.version).cmp(&(&b.namespace, &b.name, b.version))
+    });
+    packages
+}
+
+/// Execute a package listing command.
+fn list(command: &PackageListCommand) -> StrResult<()> {
+    write_list(command).map_err(|err| eco_format!("failed to print packages ({err})"))
+}
+
+/// Write the locally available packages to the terminal.
+fn write_list(command: &PackageListCommand) -> io::Result<()> {
+    let mut out = terminal::out();
+    let dirs = [("local", dirs::data_dir()), ("cached", dirs::cache_dir())];
+    for (kind, dir) in dirs {
+        let Some(dir) = dir else { continue };
+        for (spec, path) in stored_packages(&dir) {
+            if command.namespace.as_deref().is_some_and(|ns| spec.namespace != ns) {
+                continue;
+            }
+
+            if command.paths {
+                writeln!(out, "{spec} ({kind}, at {})", path.display())?;
+            } else {
+                writeln!(out, "{spec} ({kind})")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Execute a cache cleaning command.
+fn clean(command: &PackageCleanCommand) -> StrResult<()> {
+    let Some(cache_dir) = dirs::cache_dir() else {
+        bail!("failed to locate the cache directory");
+    };
+
+    // Either an exact version or all versions of a package are affected.
+    let (filter, exact) = match command.package.as_deref() {
+        Some(raw) => match raw.parse::<PackageSpec>() {
+            Ok(spec) => (
+                Some(VersionlessPackageSpec {
+                    namespace: spec.namespace.clone(),
+                    name: spec.name.clone(),
+                }),
+                Some(spec.version),
+            ),
+            Err(err) => {
+                let spec: VersionlessPackageSpec = raw.parse().map_err(|_| err)?;
+                (Some(spec), None)
+            }
+        },
+        None => (None, None),
+    };
+
+    // Group the cached versions by package.
+    let mut groups: BTreeMap<_, Vec<(PackageSpec, PathBuf)>> = BTreeMap::new();
+    for (spec, path) in stored_packages(&cache_dir) {
+        if filter
+            .as_ref()
+            .is_some_and(|f| f.namespace != spec.namespace || f.name != spec.name)
+        {
+            continue;
+        }
+        groups
+            .entry((spec.namespace.clone(), spec.name.clone()))
+            .or_default()
+            .push((spec, path));
+    }
+
+    let mut removed = 0;
+    for versions in groups.into_values() {
+        let latest = versions.iter().map(|(spec, _)| spec.version).max();
+        for (spec, path) in versions {
+            let remove = match exact {
+                Some(version) => spec.version == version,
+                None => command.all || Some(spec.version) != latest,
+            };
+
+            if remove {
+                fs::remove_dir_all(&path).map_err(|err| {
+                    eco_format!(
+                        "failed to remove {spec} ({})",
+                        FileError::from_io(err, &path)
+                    )
+                })?;
+
+                // Clean up the package's directory if it is now empty.
+                if let Some(parent) = path.parent() {
+                    fs::remove_dir(parent).ok();
+                }
+
+                print_status("removed", &spec)?;
+                removed += 1;
+            }
+        }
+    }
+
+    if removed == 0 {
+        if let (Some(filter), Some(version)) = (filter, exact) {
+            bail!("package {} is not in the cache", filter.at(version));
+        }
+    }
+
+    Ok(())
+}
+
+/// Execute a package linking command.
+fn link(command: &PackageLinkCommand) -> StrResult<()> {
+    let dir = command.dir.as_deref().unwrap_or(Path::new("."));
+    let dir = dir.canonicalize().map_err(|err| {
+        eco_format!(
+            "failed to locate package directory ({})",
+            FileError::from_io(err, dir)
+        )
+    })?;
+
+    let manifest = parse_manifest(&dir)?;
+    let spec: PackageSpec = format!(
+        "@{}/{}:{}",
+        command.namespace, manifest.package.name, manifest.package.version
+    )
+    .parse()?;
+
+    if spec.namespace == "preview" {
+        bail!("cannot link into the `preview` namespace, which is reserved for published packages");
+    }
+
+    let Some(data_dir) = dirs::data_dir() else {
+        bail!("failed to locate the data directory");
+    };
+
+    let target = data_dir
+        .join(PACKAGES_SUBDIR)
+        .join(spec.namespace.as_str())
+        .join(spec.name.as_str())
+        .join(spec.version.to_string());
+    if let Ok(metadata) = fs::symlink_metadata(&target) {
+        if !command.force {
+            bail!(
+                "package {spec} already exists (at {}), \
+                 pass `--force` to replace it",
+                target.display()
+            );
+        }
+
+        let result = if metadata.is_symlink() {
+            remove_symlink(&target)
+        } else {
+            fs::remove_dir_all(&target)
+        };
+        result.map_err(|err| {
+            eco_format!("failed to replace {spec} ({})", FileError::from_io(err, &target))
+        })?;
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|err| {
+            eco_format!(
+                "failed to create package directory ({})",
+                FileError::from_io(err, parent)
+            )
+        })?;
+    }
+
+    symlink_dir(&dir, &target).map_err(|err| {
+        eco_format!("failed to link {spec} ({})", FileError::from_io(err, &target))
+    })?;
+
+    print_status("linked", &spec)?;
+    Ok(())
+}
+
+/// Create a symbolic link to a directory.
+fn symlink_dir(original: &Path, link: &Path) -> io::Result<()> {
+    #[cfg(windows)]
+    return std::os::windows::fs::symlink_dir(original, link);
+    #[cfg(not(windows))]
+    return std::os::unix::fs::symlink(original, link);
+}
+
+/// Remove a symbolic link to a directory.
+fn remove_symlink(link: &Path) -> io::Result<()> {
+    #[cfg(windows)]
+    return fs::remove_dir(link);
+    #[cfg(not(windows))]
+    return fs::remove_file(link);
+}
+
+/// Execute a package checking command.
+fn check(command: &PackageCheckCommand) -> StrResult<()> {
+    let dir = command.dir.as_deref().unwrap_or(Path::new("."));
+    let raw: toml::Table = toml::from_str(&read_manifest(dir)?).map_err(|err| {
+        eco_format!("package manifest is malformed ({})", err.message())
+    })?;
+    let manifest = parse_manifest(dir)?;
+
+    let spec: PackageSpec =
+        format!("@preview/{}:{}", manifest.package.name, manifest.package.version)
+            .parse()?;
+    manifest.validate(&spec)?;
+
+    let mut problems: Vec<EcoString> = vec![];
+    let field = |table: &str, key: &str| raw.get(table).and_then(|t| t.get(key));
+
+    // Check the fields that are required for publication, but not for
+    // compilation.
+    for key in ["authors", "license", "description"] {
+        if field("package", key).is_none() {
+            problems.push(eco_format!("package manifest is missing the `{key}` field"));
+        }
+    }
+
+    // Collect the exclusion patterns.
+    let excludes: Vec<&str> = match field("package", "exclude") {
+        None => vec![],
+        Some(toml::Value::Array(array)) => {
+            array.iter().filter_map(|value| value.as_str()).collect()
+        }
+        Some(_) => {
+            problems.push("`exclude` field must be an array of strings".into());
+            vec![]
+        }
+    };
+
+    let mut require = |what: &str, path: &str, source: bool| {
+        if let Err(problem) = check_file(dir, &excludes, what, path, source) {
+            problems.push(problem);
+        }
+    };
+
+    require("entrypoint", &manifest.package.entrypoint, true);
+
+    // Check the template layout.
+    if let Some(template) = &manifest.template {
+        require("template directory", &template.path, false);
+        let entrypoint = format!(
+            "{}/{}",
+            template.path.trim_end_matches('/'),
+            template.entrypoint.trim_start_matches('/')
+        );
+        require("template entrypoint", &entrypoint, true);
+        let thumbnail = field("template", "thumbnail").and_then(|value| value.as_str());
+        require("template thumbnail", thumbnail.unwrap_or_default(), false);
+    }
+
+    if !problems.is_empty() {
+        for problem in &problems {
+            print_error(problem)
+                .map_err(|err| eco_format!("failed to print error ({err})"))?;
+        }
+        bail!(
+            "package {spec} is not ready for publication ({} problems)",
+            problems.len()
+        );
+    }
+
+    print_status("checked", &spec)?;
+    Ok(())
+}
+
+/// Check that a file referenced by the manifest exists, is not excluded, and,
+/// if it is a Typst source file, parses without errors.
+fn check_file(
+    dir: &Path,
+    excludes: &[&str],
+    what: &str,
+    path: &str,
+    source: bool,
+) -> StrResult<()> {
+    if path.is_empty() {
+        bail!("{what} is not specified in the package manifest");
+    }
+
+    let Some(resolved) = VirtualPath::new(path).resolve(dir) else {
+        bail!("{what} `{path}` is outside of the package");
+    };
+
+    if !resolved.exists() {
+        bail!("{what} `{path}` does not exist");
+    }
+
+    let relative = path.trim_start_matches('/');
+    if excludes.iter().any(|pattern| glob_excludes(pattern, relative)) {
+        bail!("{what} `{path}` is excluded from the package");
+    }
+
+    if source {
+        let text = fs::read_to_string(&resolved).map_err(|err| {
+            eco_format!(
+                "failed to read {what} `{path}` ({})",
+                FileError::from_io(err, &resolved)
+            )
+        })?;
+
+        if typst::syntax::parse(&text).erroneous() {
+            bail!("{what} `{path}` contains syntax errors");
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether an exclusion pattern excludes the given `/`-separated path relative
+/// to the package root, either directly or through one of its parent
+/// directories.
+///
+/// Supports the wildcards `*` and `?` within a path segment and `**` across
+/// segments. Patterns starting with `/` or `./` are anchored at the package
+/// root and other patterns without a slash match in any directory.
+fn glob_excludes(pattern: &str, path: &str) -> bool {
+    let pattern = match pattern.strip_prefix('/').or_else(|| pattern.strip_prefix("./")) {
+        Some(rooted) => rooted.to_string(),
+        None if !pattern.trim_end_matches('/').contains('/') => format!("**/{pattern}"),
+        None => pattern.to_string(),
+    };
+
+    let pattern: Vec<&str> = pattern.trim_end_matches('/').split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    (1..=path.len()).any(|len| glob_segments(&pattern, &path[..len]))
+}
+
+/// Matches a pattern against a path, both split into segments.
+fn glob_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|i| glob_segments(rest, &path[i..])),
+        Some((first, rest)) => {
+            !path.is_empty()
+                && glob_segment(first.as_bytes(), path[0].as_bytes())
+                && glob_segments(rest, &path[1..])
+        }
+    }
+}
+
+/// Matches a single pattern segment against a single path segment.
+fn glob_segment(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len()).any(|i| glob_segment(rest, &text[i..])),
+        Some((b'?', rest)) => !text.is_empty() && glob_segment(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && glob_segment(rest, &text[1..]),
+    }
+}
+
+/// Print the outcome of a package operation.
+fn print_status(action: &str, spec: &PackageSpec) -> StrResult<()> {
+    write_status(action, spec)
+        .map_err(|err| eco_format!("failed to print status ({err})"))
+}
+
+/// Write the outcome of a package operation to the terminal.
+fn write_status(action: &str, spec: &PackageSpec) -> io::Result<()> {
+    if terminal::json() {
+        return Ok(());
+    }
+
     let styles = term::Styles::default();
 
     let mut out = terminal::out();
     out.set_color(&styles.header_help)?;
-    write!(out, "downloading")?;
+    write!(out, "{action}")?;
 
     out.reset()?;
     writeln!(out, " {spec}")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn test(pattern: &str, path: &str, excluded: bool) {
+        assert_eq!(glob_excludes(pattern, path), excluded, "{pattern} on {path}");
+    }
+
+    #[test]
+    fn test_glob_single_star() {
+        test("*.pdf", "out.pdf", true);
+        test("*.pdf", "docs/out.pdf", true);
+        test("*.pdf", "out.pdf.typ", false);
+        test("main*", "main.typ", true);
+        test("?.typ", "a.typ", true);
+        test("?.typ", "ab.typ", false);
+        test("lib.typ", "lib.typst", false);
+    }
+
+    #[test]
+    fn test_glob_double_star() {
+        test("examples/**/*.png", "examples/a/b/c.png", true);
+        test("examples/**/*.png", "examples/c.png", true);
+        test("examples/**/*.png", "examples/c.jpg", false);
+        test("**/tests", "src/tests/main.typ", true);
+        test("docs/**", "docs/a/b.typ", true);
+    }
+
+    #[test]
+    fn test_glob_anchored() {
+        test("/docs", "docs/manual.typ", true);
+        test("/docs", "src/docs/manual.typ", false);
+        test("./docs", "src/docs/manual.typ", false);
+        test("docs/", "src/docs/manual.typ", true);
+        test("examples/*.png", "other/examples/a.png", false);
+        test("src/lib.typ", "src/lib.typ", true);
+    }
+}
@@ -25,6 +25,9 @@ pub fn query(command: &QueryCommand) -> StrResult<()> {
     let result = typst::compile(&world, &mut tracer);
     let warnings = tracer.warnings();
 
+    // Pin packages that were used for the first time, if requested.
+    world.packages().save()?;
+
     match result {
         // Retrieve and print query results.
         Ok(document) => {
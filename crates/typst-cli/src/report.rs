@@ -0,0 +1,205 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use ecow::{eco_format, EcoString};
+use serde::Serialize;
+use typst::diag::{Severity, SourceDiagnostic, StrResult};
+use typst::syntax::Span;
+use typst::World;
+
+use crate::world::SystemWorld;
+
+/// A diagnostic in machine-readable form.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonDiagnostic {
+    /// Either `error` or `warning`.
+    pub severity: &'static str,
+    /// The diagnostic message.
+    pub message: EcoString,
+    /// Where the diagnostic occurred, if it is attached to a file.
+    pub location: Option<JsonLocation>,
+    /// Hints on how to fix the problem.
+    pub hints: Vec<EcoString>,
+    /// The chain of calls, show rules, and imports that led to the problem,
+    /// innermost first.
+    pub trace: Vec<JsonTracepoint>,
+    /// Fixes that can be applied automatically.
+    pub suggestions: Vec<JsonSuggestion>,
+}
+
+/// A step in the trace of a [`JsonDiagnostic`].
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonTracepoint {
+    /// A description of the step.
+    pub message: EcoString,
+    /// Where the step occurred, if it is attached to a file.
+    pub location: Option<JsonLocation>,
+}
+
+/// A fix in a [`JsonDiagnostic`].
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonSuggestion {
+    /// A description of the fix.
+    pub message: EcoString,
+    /// The range to replace, if it is attached to a file.
+    pub location: Option<JsonLocation>,
+    /// The text to replace the range with.
+    pub replacement: EcoString,
+}
+
+/// A range in a file.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonLocation {
+    /// The file's name, as shown in human-readable diagnostics.
+    pub file: String,
+    /// The start of the range.
+    pub start: JsonPosition,
+    /// The end of the range.
+    pub end: JsonPosition,
+}
+
+/// A position in a file.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonPosition {
+    /// The zero-based byte offset.
+    pub byte: usize,
+    /// The one-based line number.
+    pub line: usize,
+    /// The one-based column number, counted in characters.
+    pub column: usize,
+}
+
+impl JsonDiagnostic {
+    /// Convert a diagnostic into its machine-readable form.
+    pub fn new(world: &SystemWorld, diagnostic: &SourceDiagnostic) -> Self {
+        Self {
+            severity: match diagnostic.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            },
+            message: diagnostic.message.clone(),
+            location: JsonLocation::new(world, diagnostic.span),
+            hints: diagnostic.hints.iter().cloned().collect(),
+            trace: diagnostic
+                .trace
+                .iter()
+                .map(|point| JsonTracepoint {
+                    message: eco_format!("{}", point.v),
+                    location: JsonLocation::new(world, point.span),
+                })
+                .collect(),
+            suggestions: diagnostic
+                .suggestions
+                .iter()
+                .map(|suggestion| JsonSuggestion {
+                    message: suggestion.message.clone(),
+                    location: JsonLocation::new(world, suggestion.span),
+                    replacement: suggestion.replacement.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl JsonDiagnostic {
+    /// An application-level error that is not attached to a source file.
+    pub fn error(message: EcoString) -> Self {
+        Self {
+            severity: "error",
+            message,
+            location: None,
+            hints: vec![],
+            trace: vec![],
+            suggestions: vec![],
+        }
+    }
+}
+
+impl JsonLocation {
+    /// Resolve the location of a span, if it is attached to a source file.
+    fn new(world: &SystemWorld, span: Span) -> Option<Self> {
+        let id = span.id()?;
+        let source = world.source(id).ok()?;
+        let range = source.range(span)?;
+        let position = |byte| {
+            Some(JsonPosition {
+                byte,
+                line: source.byte_to_line(byte)? + 1,
+                column: source.byte_to_column(byte)? + 1,
+            })
+        };
+
+        Some(Self {
+            file: codespan_reporting::files::Files::name(world, id).ok()?,
+            start: position(range.start)?,
+            end: position(range.end)?,
+        })
+    }
+}
+
+/// A machine-readable summary of a compilation.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompileReport {
+    /// Whether the compilation and export succeeded.
+    pub success: bool,
+    /// How long the compilation and export took, in seconds.
+    pub duration: f64,
+    /// The number of pages, if the compilation succeeded.
+    pub pages: Option<usize>,
+    /// The files written by the export.
+    pub outputs: Vec<PathBuf>,
+    /// All files the compilation read.
+    pub dependencies: Vec<PathBuf>,
+    /// All errors and warnings.
+    pub diagnostics: Vec<JsonDiagnostic>,
+}
+
+impl CompileReport {
+    /// Summarize the last compilation in the given world.
+    pub fn new(
+        world: &mut SystemWorld,
+        duration: Duration,
+        pages: Option<usize>,
+        outputs: Vec<PathBuf>,
+        errors: &[SourceDiagnostic],
+        warnings: &[SourceDiagnostic],
+    ) -> Self {
+        let diagnostics = warnings
+            .iter()
+            .chain(errors)
+            .map(|diagnostic| JsonDiagnostic::new(world, diagnostic))
+            .collect();
+
+        let mut dependencies: Vec<_> = world.dependencies().collect();
+        dependencies.sort();
+
+        Self {
+            success: errors.is_empty(),
+            duration: duration.as_secs_f64(),
+            pages,
+            outputs,
+            dependencies,
+            diagnostics,
+        }
+    }
+
+    /// Write the report as JSON to the given path.
+    pub fn write(&self, path: &Path) -> StrResult<()> {
+        write_json(path, self)
+    }
+
+    /// Write the reports of a batch compilation as a JSON array to the given
+    /// path.
+    pub fn write_all(reports: &[Self], path: &Path) -> StrResult<()> {
+        write_json(path, reports)
+    }
+}
+
+/// Write one or multiple reports as JSON to the given path.
+fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> StrResult<()> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|err| eco_format!("failed to serialize compile report ({err})"))?;
+    fs::write(path, json)
+        .map_err(|err| eco_format!("failed to write compile report ({err})"))
+}
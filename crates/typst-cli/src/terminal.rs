@@ -4,6 +4,7 @@ use codespan_reporting::term::termcolor;
 use once_cell::sync::Lazy;
 use termcolor::{ColorChoice, WriteColor};
 
+use crate::args::{Command, DiagnosticFormat};
 use crate::ARGS;
 
 /// Returns a handle to the optionally colored terminal output.
@@ -12,6 +13,19 @@ pub fn out() -> TermOut {
     TermOut { inner: &OUTPUT }
 }
 
+/// Whether diagnostics are emitted as JSON.
+///
+/// If so, everything written to the terminal must be JSON, so that the output
+/// stays machine-readable.
+pub fn json() -> bool {
+    let common = match &ARGS.command {
+        Command::Compile(command) | Command::Watch(command) => &command.common,
+        Command::Query(command) => &command.common,
+        _ => return false,
+    };
+    common.diagnostic_format == DiagnosticFormat::Json
+}
+
 /// The stuff that has to be shared between instances of [`TermOut`].
 struct TermOutInner {
     stream: termcolor::StandardStream,
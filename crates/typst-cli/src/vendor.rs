@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use codespan_reporting::term::{self, termcolor};
+use ecow::eco_format;
+use fs_extra::dir::CopyOptions;
+use termcolor::WriteColor;
+use typst::diag::{bail, StrResult};
+use typst::syntax::package::PackageSpec;
+use typst::syntax::{ast, SyntaxNode};
+
+use crate::args::VendorCommand;
+use crate::lock::{
+    hash_package, is_pinned, vendored_dir, Lockfile, LOCKFILE_NAME, VENDOR_DIR_NAME,
+};
+use crate::package::prepare_package;
+use crate::terminal;
+
+/// Execute a vendoring command.
+pub fn vendor(command: &VendorCommand) -> StrResult<()> {
+    let root = command.root.as_deref().unwrap_or(Path::new("."));
+    let root = root.canonicalize().map_err(|err| {
+        eco_format!("root directory not found (searched at {}, {err})", root.display())
+    })?;
+
+    let vendor = root.join(VENDOR_DIR_NAME);
+    let lock_path = root.join(LOCKFILE_NAME);
+    let mut lockfile = Lockfile::read(&lock_path)?.unwrap_or_else(Lockfile::new);
+
+    // Find the packages imported by the project itself.
+    let mut queue = vec![];
+    scan_dir(&root, Some(&vendor), &mut queue)
+        .map_err(|err| eco_format!("failed to scan project ({err})"))?;
+
+    // Vendor them and, transitively, the packages they import.
+    let mut done = HashSet::new();
+    while let Some(spec) = queue.pop() {
+        if !done.insert(spec.clone()) {
+            continue;
+        }
+
+        let target = vendored_dir(&vendor, &spec);
+        let expected = lockfile.get(&spec).map(|p| p.hash.clone());
+        if !target.exists() {
+            let source = prepare_package(&spec, expected.as_deref())?;
+            copy_package(&source, &target)?;
+            print_vendored(&spec)
+                .map_err(|err| eco_format!("failed to print vendored package ({err})"))?;
+        }
+
+        if is_pinned(&spec) {
+            let hash = hash_package(&target)?;
+            if let Some(expected) = expected {
+                if expected != hash {
+                    bail!(
+                        "contents of {spec} do not match the lockfile \
+                         (expected {expected}, found {hash})"
+                    );
+                }
+            }
+            lockfile.insert(&spec, hash);
+        }
+
+        scan_dir(&target, None, &mut queue)
+            .map_err(|err| eco_format!("failed to scan package {spec} ({err})"))?;
+    }
+
+    lockfile.write(&lock_path)
+}
+
+/// Copy a package from the local package directories into the vendor
+/// directory.
+fn copy_package(source: &Path, target: &Path) -> StrResult<()> {
+    fs::create_dir_all(target)
+        .map_err(|err| eco_format!("failed to create vendor directory ({err})"))?;
+
+    fs_extra::dir::copy(source, target, &CopyOptions::new().content_only(true)).map_err(
+        |err| {
+            fs::remove_dir_all(target).ok();
+            eco_format!("failed to copy package ({err})")
+        },
+    )?;
+
+    Ok(())
+}
+
+/// Collect the package specifications referenced in all Typst files below
+/// `dir`, skipping the `exclude` directory.
+///
+/// Any string literal that is a valid package specification counts as a
+/// reference, so that imports through variables are found as well.
+fn scan_dir(
+    dir: &Path,
+    exclude: Option<&Path>,
+    specs: &mut Vec<PackageSpec>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if Some(path.as_path()) == exclude {
+            continue;
+        }
+
+        if path.is_dir() {
+            scan_dir(&path, exclude, specs)?;
+        } else if path.extension().is_some_and(|ext| ext == "typ") {
+            let Ok(text) = fs::read_to_string(&path) else { continue };
+            scan_node(&typst::syntax::parse(&text), specs);
+        }
+    }
+    Ok(())
+}
+
+/// Collect the package specifications referenced in a syntax tree.
+fn scan_node(node: &SyntaxNode, specs: &mut Vec<PackageSpec>) {
+    if let Some(string) = node.cast::<ast::Str>() {
+        let string = string.get();
+        if string.starts_with('@') {
+            if let Ok(spec) = string.parse() {
+                specs.push(spec);
+            }
+        }
+    }
+
+    for child in node.children() {
+        scan_node(child, specs);
+    }
+}
+
+/// Print that a package was vendored.
+fn print_vendored(spec: &PackageSpec) -> io::Result<()> {
+    let styles = term::Styles::default();
+
+    let mut out = terminal::out();
+    out.set_color(&styles.header_help)?;
+    write!(out, "vendored")?;
+
+    out.reset()?;
+    writeln!(out, " {spec}")
+}
@@ -10,9 +10,9 @@ use codespan_reporting::term::{self, termcolor};
 use ecow::eco_format;
 use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher as _};
 use same_file::is_same_file;
-use typst::diag::StrResult;
+use typst::diag::{bail, StrResult};
 
-use crate::args::{CompileCommand, Input};
+use crate::args::{CompileCommand, DiagnosticFormat, Input};
 use crate::compile::compile_once;
 use crate::timings::Timer;
 use crate::world::{SystemWorld, WorldCreationError};
@@ -20,6 +20,10 @@ use crate::{print_error, terminal};
 
 /// Execute a watching compilation command.
 pub fn watch(mut timer: Timer, mut command: CompileCommand) -> StrResult<()> {
+    if command.batch.is_some() {
+        bail!("batch compilation is not supported in watch mode");
+    }
+
     // Create a file system watcher.
     let mut watcher = Watcher::new(command.output())?;
 
@@ -263,6 +267,11 @@ pub enum Status {
 impl Status {
     /// Clear the terminal and render the status message.
     pub fn print(&self, command: &CompileCommand) -> io::Result<()> {
+        // Keep the output machine-readable.
+        if command.common.diagnostic_format == DiagnosticFormat::Json {
+            return Ok(());
+        }
+
         let output = command.output();
         let timestamp = chrono::offset::Local::now().format("%H:%M:%S");
         let color = self.color();
@@ -11,14 +11,18 @@ use once_cell::sync::Lazy;
 use parking_lot::Mutex;
 use typst::diag::{FileError, FileResult};
 use typst::foundations::{Bytes, Datetime, Dict, IntoValue};
+use typst::syntax::package::PackageSpec;
 use typst::syntax::{FileId, Source, VirtualPath};
 use typst::text::{Font, FontBook};
 use typst::{Library, World};
 use typst_timing::{timed, TimingScope};
 
 use crate::args::{Input, SharedArgs};
+use crate::cache::DiskCache;
 use crate::compile::ExportCache;
 use crate::fonts::{FontSearcher, FontSlot};
+use crate::lock::PackageLock;
+use crate::package::local_packages;
 
 /// Static `FileId` allocated for stdin.
 /// This is to ensure that a file is read in the correct way.
@@ -31,6 +35,8 @@ pub struct SystemWorld {
     workdir: Option<PathBuf>,
     /// The root relative to which absolute paths are resolved.
     root: PathBuf,
+    /// Resolves packages in accordance with the project's lockfile.
+    packages: PackageLock,
     /// The input path.
     main: FileId,
     /// Typst's standard library.
@@ -44,6 +50,12 @@ pub struct SystemWorld {
     /// The current datetime if requested. This is stored here to ensure it is
     /// always the same within one compilation. Reset between compilations.
     now: OnceLock<DateTime<Local>>,
+    /// The packages in the local data and cache directories, discovered on
+    /// first use.
+    local_packages: OnceLock<Vec<(PackageSpec, Option<EcoString>)>>,
+    /// The files in the project, discovered on first use. Reset between
+    /// compilations.
+    files: OnceLock<Vec<FileId>>,
     /// The export cache, used for caching output files in `typst watch`
     /// sessions.
     export_cache: ExportCache,
@@ -101,18 +113,25 @@ impl SystemWorld {
             Library::builder().with_inputs(inputs).build()
         };
 
+        let packages = PackageLock::new(&root, command.locked, command.update_lock)
+            .map_err(WorldCreationError::Lockfile)?;
+
+        let cache = command.cache_path.as_deref().map(DiskCache::new);
         let mut searcher = FontSearcher::new();
-        searcher.search(&command.font_paths);
+        searcher.search(&command.font_paths, cache.as_ref());
 
         Ok(Self {
             workdir: std::env::current_dir().ok(),
             root,
+            packages,
             main,
             library: Prehashed::new(library),
             book: Prehashed::new(searcher.book),
             fonts: searcher.fonts,
             slots: Mutex::new(HashMap::new()),
             now: OnceLock::new(),
+            local_packages: OnceLock::new(),
+            files: OnceLock::new(),
             export_cache: ExportCache::new(),
         })
     }
@@ -132,13 +151,44 @@ impl SystemWorld {
         self.workdir.as_deref().unwrap_or(Path::new("."))
     }
 
+    /// Replace the inputs available through `sys.inputs`.
+    ///
+    /// Everything else, in particular the fonts, the loaded files, and the
+    /// library's limits and styles, is kept.
+    pub fn set_inputs(&mut self, inputs: Dict) {
+        let library = Library::builder()
+            .with_inputs(inputs)
+            .with_limits(self.library.limits)
+            .build();
+        let styles = self.library.styles.clone();
+        self.library = Prehashed::new(Library { styles, ..library });
+    }
+
     /// Return all paths the last compilation depended on.
+    ///
+    /// This includes the files of the fonts that were used, but not fonts that
+    /// are embedded in the binary.
     pub fn dependencies(&mut self) -> impl Iterator<Item = PathBuf> + '_ {
-        self.slots
+        let files = self
+            .slots
             .get_mut()
             .values()
             .filter(|slot| slot.accessed())
-            .filter_map(|slot| system_path(&self.root, slot.id).ok())
+            .filter_map(|slot| system_path(&self.root, &self.packages, slot.id).ok());
+
+        let fonts = self
+            .fonts
+            .iter()
+            .filter(|slot| slot.accessed())
+            .filter_map(|slot| slot.path())
+            .map(Path::to_path_buf);
+
+        files.chain(fonts)
+    }
+
+    /// Gets access to the project's package lock.
+    pub fn packages(&self) -> &PackageLock {
+        &self.packages
     }
 
     /// Reset the compilation state in preparation of a new compilation.
@@ -146,7 +196,11 @@ impl SystemWorld {
         for slot in self.slots.get_mut().values_mut() {
             slot.reset();
         }
+        for slot in &mut self.fonts {
+            slot.reset();
+        }
         self.now.take();
+        self.files.take();
     }
 
     /// Lookup a source file by id.
@@ -175,11 +229,11 @@ impl World for SystemWorld {
     }
 
     fn source(&self, id: FileId) -> FileResult<Source> {
-        self.slot(id, |slot| slot.source(&self.root))
+        self.slot(id, |slot| slot.source(&self.root, &self.packages))
     }
 
     fn file(&self, id: FileId) -> FileResult<Bytes> {
-        self.slot(id, |slot| slot.file(&self.root))
+        self.slot(id, |slot| slot.file(&self.root, &self.packages))
     }
 
     fn font(&self, index: usize) -> Option<Font> {
@@ -200,6 +254,65 @@ impl World for SystemWorld {
             naive.day().try_into().ok()?,
         )
     }
+
+    fn packages(&self) -> &[(PackageSpec, Option<EcoString>)] {
+        self.local_packages.get_or_init(local_packages)
+    }
+
+    fn files(&self) -> Vec<FileId> {
+        self.files
+            .get_or_init(|| {
+                let mut files = vec![];
+                let mut budget = MAX_PROJECT_ENTRIES;
+                project_files(&self.root, &self.root, 0, &mut budget, &mut files);
+                files.sort_by(|a, b| {
+                    a.vpath().as_rooted_path().cmp(b.vpath().as_rooted_path())
+                });
+                files
+            })
+            .clone()
+    }
+}
+
+/// How deep [`project_files`] descends into the project's directories.
+const MAX_PROJECT_DEPTH: usize = 8;
+
+/// How many directory entries [`project_files`] visits at most.
+const MAX_PROJECT_ENTRIES: usize = 10_000;
+
+/// Collect the files below a directory in the project, skipping hidden files
+/// and directories.
+///
+/// The walk is bounded by [`MAX_PROJECT_DEPTH`] and [`MAX_PROJECT_ENTRIES`] so
+/// that a root with a huge directory tree doesn't stall autocompletion.
+fn project_files(
+    root: &Path,
+    dir: &Path,
+    depth: usize,
+    budget: &mut usize,
+    files: &mut Vec<FileId>,
+) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.filter_map(Result::ok) {
+        if *budget == 0 {
+            return;
+        }
+        *budget -= 1;
+
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+
+        let Ok(kind) = entry.file_type() else { continue };
+        if kind.is_dir() {
+            if depth < MAX_PROJECT_DEPTH {
+                project_files(root, &path, depth + 1, budget, files);
+            }
+        } else if let Some(vpath) = VirtualPath::within_root(&path, root) {
+            files.push(FileId::new(None, vpath));
+        }
+    }
 }
 
 impl SystemWorld {
@@ -244,9 +357,13 @@ impl FileSlot {
     }
 
     /// Retrieve the source for this file.
-    fn source(&mut self, project_root: &Path) -> FileResult<Source> {
+    fn source(
+        &mut self,
+        project_root: &Path,
+        packages: &PackageLock,
+    ) -> FileResult<Source> {
         self.source.get_or_init(
-            || read(self.id, project_root),
+            || read(self.id, project_root, packages),
             |data, prev| {
                 let name = if prev.is_some() { "reparsing file" } else { "parsing file" };
                 let _scope = TimingScope::new(name, None);
@@ -262,9 +379,11 @@ impl FileSlot {
     }
 
     /// Retrieve the file's bytes.
-    fn file(&mut self, project_root: &Path) -> FileResult<Bytes> {
-        self.file
-            .get_or_init(|| read(self.id, project_root), |data, _| Ok(data.into()))
+    fn file(&mut self, project_root: &Path, packages: &PackageLock) -> FileResult<Bytes> {
+        self.file.get_or_init(
+            || read(self.id, project_root, packages),
+            |data, _| Ok(data.into()),
+        )
     }
 }
 
@@ -329,13 +448,17 @@ impl<T: Clone> SlotCell<T> {
 
 /// Resolves the path of a file id on the system, downloading a package if
 /// necessary.
-fn system_path(project_root: &Path, id: FileId) -> FileResult<PathBuf> {
+fn system_path(
+    project_root: &Path,
+    packages: &PackageLock,
+    id: FileId,
+) -> FileResult<PathBuf> {
     // Determine the root path relative to which the file path
     // will be resolved.
     let buf;
     let mut root = project_root;
     if let Some(spec) = id.package() {
-        buf = crate::package::prepare_package(spec)?;
+        buf = packages.resolve(spec)?;
         root = &buf;
     }
 
@@ -348,11 +471,11 @@ fn system_path(project_root: &Path, id: FileId) -> FileResult<PathBuf> {
 ///
 /// If the ID represents stdin it will read from standard input,
 /// otherwise it gets the file path of the ID and reads the file from disk.
-fn read(id: FileId, project_root: &Path) -> FileResult<Vec<u8>> {
+fn read(id: FileId, project_root: &Path, packages: &PackageLock) -> FileResult<Vec<u8>> {
     if id == *STDIN_ID {
         read_from_stdin()
     } else {
-        read_from_disk(&system_path(project_root, id)?)
+        read_from_disk(&system_path(project_root, packages, id)?)
     }
 }
 
@@ -393,6 +516,8 @@ pub enum WorldCreationError {
     InputOutsideRoot,
     /// The root directory does not appear to exist.
     RootNotFound(PathBuf),
+    /// The project's lockfile could not be loaded.
+    Lockfile(EcoString),
     /// Another type of I/O error.
     Io(io::Error),
 }
@@ -409,6 +534,7 @@ impl fmt::Display for WorldCreationError {
             WorldCreationError::RootNotFound(path) => {
                 write!(f, "root directory not found (searched at {})", path.display())
             }
+            WorldCreationError::Lockfile(err) => write!(f, "{err}"),
             WorldCreationError::Io(err) => write!(f, "{err}"),
         }
     }
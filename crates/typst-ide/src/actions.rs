@@ -0,0 +1,547 @@
+use std::ops::Range;
+
+use ecow::{eco_format, EcoString};
+use typst::diag::SourceDiagnostic;
+use typst::foundations::Value;
+use typst::syntax::ast::{self, AstNode};
+use typst::syntax::{
+    is_id_continue, is_id_start, LinkedNode, Source, SyntaxKind, SyntaxNode,
+};
+use typst::util::closest;
+use typst::World;
+
+use crate::definition::{descendants, ident_definition, is_scoped, scope_definition};
+
+/// An action that fixes a problem or refactors code in a source file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CodeAction {
+    /// A short description of the action, e.g. "Extract to variable".
+    pub title: EcoString,
+    /// What kind of action this is.
+    pub kind: CodeActionKind,
+    /// The text edits to apply, as byte ranges in the source file and
+    /// replacements.
+    pub edits: Vec<(Range<usize>, EcoString)>,
+    /// Whether the replacements are described with snippet syntax like
+    /// `${name}`.
+    pub snippet: bool,
+}
+
+/// A kind of [`CodeAction`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum CodeActionKind {
+    /// A fix for a problem.
+    QuickFix,
+    /// A refactoring that extracts code, e.g. into a variable.
+    Extract,
+    /// A refactoring that rewrites code, e.g. by wrapping it in a call.
+    Rewrite,
+}
+
+/// Find the actions that apply to a range in a source file.
+///
+/// The `diagnostics` (from a previous compilation) provide the suggested fixes
+/// for the problems that overlap with the range. Other fixes, like the one for
+/// an unclosed delimiter, are derived from the syntax.
+pub fn code_actions(
+    world: &dyn World,
+    source: &Source,
+    range: Range<usize>,
+    diagnostics: &[SourceDiagnostic],
+) -> Vec<CodeAction> {
+    let mut actions = vec![];
+
+    for diagnostic in diagnostics {
+        if diagnostic.span.id() != Some(source.id()) {
+            continue;
+        }
+
+        let Some(diagnostic_range) = source.range(diagnostic.span) else { continue };
+        if diagnostic_range.start > range.end || diagnostic_range.end < range.start {
+            continue;
+        }
+
+        for suggestion in &diagnostic.suggestions {
+            if suggestion.span.id() != Some(source.id()) {
+                continue;
+            }
+
+            if let Some(replaced) = source.range(suggestion.span) {
+                actions.push(quick_fix(
+                    capitalize(&suggestion.message),
+                    replaced,
+                    suggestion.replacement.clone(),
+                ));
+            }
+        }
+    }
+
+    let root = LinkedNode::new(source.root());
+    descendants(&root, &mut |node| {
+        let node_range = node.range();
+        if node_range.start > range.end || node_range.end < range.start {
+            return;
+        }
+
+        if let Some(action) = close_delimiter(node) {
+            actions.push(action);
+        }
+
+        if node.kind() == SyntaxKind::Text {
+            actions.extend(add_hash(world, node));
+        }
+
+        if let Some(action) = fix_font_family(world, node) {
+            actions.push(action);
+        }
+    });
+
+    actions.extend(extract_variable(world, source, range.clone()));
+    actions.extend(wrap_in_call(source, range));
+    actions
+}
+
+/// Close an unclosed delimiter, string, or label.
+fn close_delimiter(node: &LinkedNode) -> Option<CodeAction> {
+    if node.kind() != SyntaxKind::Error {
+        return None;
+    }
+
+    let error = node.get().errors().into_iter().next()?;
+    let (closing, at) = match error.message.as_str() {
+        "unclosed delimiter" => {
+            let closing = match node.text().as_str() {
+                "(" => ")",
+                "[" => "]",
+                "{" => "}",
+                "$" => "$",
+                _ => return None,
+            };
+
+            // Close the group after its last non-trivia child.
+            let end = node
+                .parent()?
+                .children()
+                .filter(|child| !child.kind().is_trivia())
+                .last()
+                .map_or(node.range().end, |child| child.range().end);
+            (closing, end)
+        }
+        "unclosed string" => ("\"", node.range().end),
+        "unclosed label" => (">", node.range().end),
+        _ => return None,
+    };
+
+    Some(quick_fix(eco_format!("Insert `{closing}`"), at..at, closing.into()))
+}
+
+/// Turn what looks like a function call in text, like `rect(..)`, into an
+/// actual call.
+fn add_hash(world: &dyn World, node: &LinkedNode) -> Vec<CodeAction> {
+    if node.parent_kind() != Some(SyntaxKind::Markup) {
+        return vec![];
+    }
+
+    let text = node.text();
+    let mut actions = vec![];
+    for (i, c) in text.char_indices() {
+        if !is_id_start(c) || text[..i].chars().next_back().is_some_and(is_id_continue) {
+            continue;
+        }
+
+        let len = text[i..].find(|c: char| !is_id_continue(c)).unwrap_or(text.len() - i);
+        let name = &text[i..i + len];
+        if !text[i + len..].starts_with(['(', '[']) {
+            continue;
+        }
+
+        let callable = match scope_definition(world, node, name) {
+            Some(_) => true,
+            None => matches!(
+                world.library().global.scope().get(name),
+                Some(Value::Func(_) | Value::Type(_))
+            ),
+        };
+
+        if callable {
+            let at = node.offset() + i;
+            actions.push(quick_fix(
+                eco_format!("Add `#` to call `{name}`"),
+                at..at,
+                "#".into(),
+            ));
+        }
+    }
+
+    actions
+}
+
+/// Replace an unknown font family with the most similar known one.
+fn fix_font_family(world: &dyn World, node: &LinkedNode) -> Option<CodeAction> {
+    let string = node.cast::<ast::Str>()?;
+    let mut parent = node.parent()?;
+    if parent.kind() == SyntaxKind::Array {
+        parent = parent.parent()?;
+    }
+
+    let named = parent.cast::<ast::Named>()?;
+    if named.name().as_str() != "font" {
+        return None;
+    }
+
+    let family = string.get();
+    let book = world.book();
+    if book.select_family(&family.to_lowercase()).next().is_some() {
+        return None;
+    }
+
+    let families: Vec<(String, &str)> =
+        book.families().map(|(name, _)| (name.to_lowercase(), name)).collect();
+    let lowered = family.to_lowercase();
+    let best = closest(&lowered, families.iter().map(|(lower, _)| lower.as_str()))?;
+    let (_, name) = families.iter().find(|(lower, _)| lower == best)?;
+
+    Some(quick_fix(
+        eco_format!("Replace with `{name}`"),
+        node.range(),
+        eco_format!("\"{name}\""),
+    ))
+}
+
+/// Extract the selected expression into a variable that is defined before the
+/// statement it is part of.
+fn extract_variable(
+    world: &dyn World,
+    source: &Source,
+    range: Range<usize>,
+) -> Option<CodeAction> {
+    let root = LinkedNode::new(source.root());
+    let node = selected_expr(&root, source, range)?;
+    let stmt = code_stmt(&node)?;
+
+    if !extractable(&node) {
+        return None;
+    }
+
+    // In markup, the definition goes before the hash of the statement.
+    let in_markup = stmt.parent_kind() == Some(SyntaxKind::Markup);
+    let at = if in_markup { stmt.prev_leaf()?.offset() } else { stmt.offset() };
+
+    // Everything the expression refers to must already be defined where the
+    // variable is.
+    let mut movable = true;
+    descendants(&node, &mut |child| {
+        if matches!(child.kind(), SyntaxKind::Ident) && is_scoped(child) {
+            if let Some(def) = ident_definition(world, child) {
+                movable &= def.id != source.id()
+                    || def.range.start < at
+                    || node.range().contains(&def.range.start);
+            }
+        }
+    });
+
+    if !movable {
+        return None;
+    }
+
+    let name = (1..)
+        .map(|i| if i == 1 { EcoString::from("value") } else { eco_format!("value{i}") })
+        .find(|name| {
+            scope_definition(world, &stmt, name).is_none()
+                && scope_definition(world, &node, name).is_none()
+                && world.library().global.scope().get(name).is_none()
+        })?;
+
+    let line_start = source.line_to_byte(source.byte_to_line(at)?)?;
+    let indent = source.get(line_start..at)?;
+    let separator = if indent.chars().all(char::is_whitespace) {
+        eco_format!("\n{indent}")
+    } else if in_markup {
+        ";".into()
+    } else {
+        "; ".into()
+    };
+
+    let hash = if in_markup { "#" } else { "" };
+    let expr = node.get().clone().into_text();
+    let definition = eco_format!("{hash}let {name} = {expr}{separator}");
+
+    let edits = if at == node.offset() {
+        vec![(node.range(), eco_format!("{definition}{name}"))]
+    } else {
+        vec![(at..at, definition), (node.range(), name)]
+    };
+
+    Some(CodeAction {
+        title: "Extract to variable".into(),
+        kind: CodeActionKind::Extract,
+        edits,
+        snippet: false,
+    })
+}
+
+/// Wrap the selected expression or markup in a function call.
+fn wrap_in_call(source: &Source, range: Range<usize>) -> Option<CodeAction> {
+    let root = LinkedNode::new(source.root());
+    let trimmed = trim(source, range)?;
+    let text = escape_snippet(source.get(trimmed.clone())?);
+
+    let replacement = match selected_expr(&root, source, trimmed.clone()) {
+        Some(node) if code_stmt(&node).is_some() => eco_format!("${{function}}({text})"),
+        _ => {
+            let leaf = root.leaf_at(trimmed.start + 1)?;
+            let mut container = leaf.parent()?.clone();
+            while !matches!(container.kind(), SyntaxKind::Markup | SyntaxKind::Math) {
+                container = container.parent()?.clone();
+            }
+
+            // The selection must consist of whole children.
+            let children: Vec<_> = container.children().collect();
+            if !children.iter().any(|child| child.offset() == trimmed.start)
+                || !children.iter().any(|child| child.range().end == trimmed.end)
+            {
+                return None;
+            }
+
+            match container.kind() {
+                SyntaxKind::Markup => eco_format!("#${{function}}[{text}]"),
+                _ => eco_format!("${{function}}({text})"),
+            }
+        }
+    };
+
+    Some(CodeAction {
+        title: "Wrap in function call".into(),
+        kind: CodeActionKind::Rewrite,
+        edits: vec![(trimmed, replacement)],
+        snippet: true,
+    })
+}
+
+/// Find the outermost expression that spans exactly the selected range,
+/// ignoring surrounding whitespace.
+fn selected_expr<'a>(
+    root: &LinkedNode<'a>,
+    source: &Source,
+    range: Range<usize>,
+) -> Option<LinkedNode<'a>> {
+    let range = trim(source, range)?;
+    let mut node = root.leaf_at(range.start + 1)?;
+    let mut found = None;
+    while range.start <= node.offset() && node.range().end <= range.end {
+        if node.range() == range && node.is::<ast::Expr>() {
+            found = Some(node.clone());
+        }
+
+        match node.parent() {
+            Some(parent) => node = parent.clone(),
+            None => break,
+        }
+    }
+    found
+}
+
+/// Find the statement in code or the embedded expression in markup that
+/// contains a node in code.
+fn code_stmt<'a>(node: &LinkedNode<'a>) -> Option<LinkedNode<'a>> {
+    let mut stmt = node.clone();
+    loop {
+        match stmt.parent_kind()? {
+            SyntaxKind::Code => return Some(stmt),
+            SyntaxKind::Markup => {
+                let embedded = stmt.prev_leaf()?.kind() == SyntaxKind::Hash;
+                return embedded.then_some(stmt);
+            }
+            SyntaxKind::Math | SyntaxKind::Equation => return None,
+            _ => stmt = stmt.parent()?.clone(),
+        }
+    }
+}
+
+/// Whether an expression can be moved into a variable without changing what
+/// the code means.
+fn extractable(node: &LinkedNode) -> bool {
+    let Some(expr) = node.cast::<ast::Expr>() else { return false };
+    if matches!(
+        expr,
+        ast::Expr::Let(_)
+            | ast::Expr::DestructAssign(_)
+            | ast::Expr::Set(_)
+            | ast::Expr::Show(_)
+            | ast::Expr::Import(_)
+            | ast::Expr::Break(_)
+            | ast::Expr::Continue(_)
+            | ast::Expr::Return(_)
+    ) {
+        return false;
+    }
+
+    if let ast::Expr::Binary(binary) = expr {
+        if binary.op().assoc() == ast::Assoc::Right {
+            return false;
+        }
+    }
+
+    let Some(parent) = node.parent() else { return false };
+    let is = |other: &SyntaxNode| other.span() == node.span();
+    match parent.kind() {
+        SyntaxKind::Params
+        | SyntaxKind::Destructuring
+        | SyntaxKind::ImportItems
+        | SyntaxKind::RenamedImportItem => false,
+        SyntaxKind::Named => node.index() > 0,
+        SyntaxKind::LetBinding => parent
+            .cast::<ast::LetBinding>()
+            .and_then(|binding| binding.init())
+            .is_some_and(|init| is(init.to_untyped())),
+        SyntaxKind::ForLoop => parent
+            .cast::<ast::ForLoop>()
+            .is_some_and(|for_loop| !is(for_loop.pattern().to_untyped())),
+        SyntaxKind::Binary => parent.cast::<ast::Binary>().is_some_and(|binary| {
+            binary.op().assoc() != ast::Assoc::Right || !is(binary.lhs().to_untyped())
+        }),
+        // Methods may mutate their target in place.
+        SyntaxKind::FieldAccess => {
+            node.index() == 0
+                && (parent.parent_kind() != Some(SyntaxKind::FuncCall)
+                    || !matches!(expr, ast::Expr::Ident(_) | ast::Expr::FieldAccess(_)))
+        }
+        _ => true,
+    }
+}
+
+/// Shrink a range so that it does not start or end with whitespace.
+fn trim(source: &Source, range: Range<usize>) -> Option<Range<usize>> {
+    let text = source.get(range.clone())?;
+    let start = range.start + (text.len() - text.trim_start().len());
+    let end = range.end - (text.len() - text.trim_end().len());
+    (start < end).then_some(start..end)
+}
+
+/// Escape text for use in a snippet.
+fn escape_snippet(text: &str) -> EcoString {
+    let mut escaped = EcoString::new();
+    for c in text.chars() {
+        if matches!(c, '$' | '}' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Create a quick fix with a single edit.
+fn quick_fix(
+    title: EcoString,
+    range: Range<usize>,
+    replacement: EcoString,
+) -> CodeAction {
+    CodeAction {
+        title,
+        kind: CodeActionKind::QuickFix,
+        edits: vec![(range, replacement)],
+        snippet: false,
+    }
+}
+
+/// Capitalize the first letter of a message.
+fn capitalize(message: &str) -> EcoString {
+    let mut chars = message.chars();
+    match chars.next() {
+        Some(first) => {
+            let mut capitalized: EcoString = first.to_uppercase().collect();
+            capitalized.push_str(chars.as_str());
+            capitalized
+        }
+        None => EcoString::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use typst::eval::Tracer;
+    use typst::World;
+
+    use super::{code_actions, CodeAction, CodeActionKind};
+    use crate::tests::TestWorld;
+
+    /// Find the action with the given title for a range in a text.
+    #[track_caller]
+    fn action(text: &str, range: Range<usize>, title: &str) -> Option<CodeAction> {
+        let world = TestWorld::new(text);
+        let diagnostics = match typst::compile(&world, &mut Tracer::new()) {
+            Ok(_) => vec![],
+            Err(errors) => errors.to_vec(),
+        };
+
+        code_actions(&world, &world.main(), range, &diagnostics)
+            .into_iter()
+            .find(|action| action.title == title)
+    }
+
+    #[track_caller]
+    fn test(
+        text: &str,
+        range: Range<usize>,
+        title: &str,
+        edits: &[(Range<usize>, &str)],
+    ) {
+        let action = action(text, range, title).expect("action not found");
+        let edits: Vec<_> = edits
+            .iter()
+            .map(|(range, text)| (range.clone(), (*text).into()))
+            .collect();
+        assert_eq!(action.edits, edits);
+    }
+
+    #[test]
+    fn test_action_suggestion() {
+        test("#pow(2, 3)", 1..1, "Replace with `calc.pow`", &[(1..4, "calc.pow")]);
+    }
+
+    #[test]
+    fn test_action_close_delimiter() {
+        test("#f(1", 0..4, "Insert `)`", &[(4..4, ")")]);
+        test("#\"abc", 0..5, "Insert `\"`", &[(5..5, "\"")]);
+    }
+
+    #[test]
+    fn test_action_add_hash() {
+        test("Call rect(width: 1pt)", 5..5, "Add `#` to call `rect`", &[(5..5, "#")]);
+        assert_eq!(action("Call foo(x)", 5..5, "Add `#` to call `foo`"), None);
+    }
+
+    #[test]
+    fn test_action_font_family() {
+        test(
+            "#set text(font: \"Linux Libertne\")",
+            16..16,
+            "Replace with `Linux Libertine`",
+            &[(16..32, "\"Linux Libertine\"")],
+        );
+    }
+
+    #[test]
+    fn test_action_extract_variable() {
+        let text = "#rect(width: 1pt + 2pt)";
+        test(
+            text,
+            13..22,
+            "Extract to variable",
+            &[(0..0, "#let value = 1pt + 2pt\n"), (13..22, "value")],
+        );
+
+        let extract = action(text, 13..22, "Extract to variable").unwrap();
+        assert_eq!(extract.kind, CodeActionKind::Extract);
+        assert_eq!(action(text, 6..11, "Extract to variable"), None);
+    }
+
+    #[test]
+    fn test_action_wrap_in_call() {
+        let wrap = action("Hello *world*", 6..13, "Wrap in function call").unwrap();
+        assert_eq!(wrap.edits, [(6..13, "#${function}[*world*]".into())]);
+        assert!(wrap.snippet);
+    }
+}
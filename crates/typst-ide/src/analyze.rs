@@ -5,7 +5,7 @@ use typst::eval::{Tracer, Vm};
 use typst::foundations::{Context, Label, Scopes, Styles, Value};
 use typst::introspection::{Introspector, Locator};
 use typst::model::{BibliographyElem, Document};
-use typst::syntax::{ast, LinkedNode, Span, SyntaxKind};
+use typst::syntax::{ast, LinkedNode, Source, Span, SyntaxKind};
 use typst::World;
 
 /// Try to determine a set of possible values for an expression.
@@ -50,6 +50,8 @@ pub fn analyze_expr(
 
 /// Try to load a module from the current source file.
 pub fn analyze_import(world: &dyn World, source: &LinkedNode) -> Option<Value> {
+    // Use the span of the source for resolving relative paths.
+    let span = source.span();
     let (source, _) = analyze_expr(world, source).into_iter().next()?;
     if source.scope().is_some() {
         return Some(source);
@@ -73,7 +75,7 @@ pub fn analyze_import(world: &dyn World, source: &LinkedNode) -> Option<Value> {
         Scopes::new(Some(world.library())),
         Span::detached(),
     );
-    typst::eval::import(&mut vm, source, Span::detached(), true)
+    typst::eval::import(&mut vm, source, span, true)
         .ok()
         .map(Value::Module)
 }
@@ -112,3 +114,48 @@ pub fn analyze_labels(document: &Document) -> (Vec<(Label, Option<EcoString>)>,
 
     (output, split)
 }
+
+/// Find all source files of the project.
+///
+/// Starts at the main file and follows imports and includes of local files.
+/// Packages are not part of the project, but their files can be included
+/// with `packages`.
+pub fn analyze_sources(world: &dyn World, packages: bool) -> Vec<Source> {
+    let mut sources = vec![];
+    let mut pending = vec![world.main().id()];
+    while let Some(id) = pending.pop() {
+        if sources.iter().any(|source: &Source| source.id() == id) {
+            continue;
+        }
+
+        let Ok(source) = world.source(id) else { continue };
+        collect_paths(&LinkedNode::new(source.root()), &mut |path, node| {
+            if !path.starts_with('@') {
+                pending.push(id.join(path));
+            } else if packages {
+                if let Some(Value::Module(module)) = analyze_import(world, node) {
+                    pending.extend(module.file());
+                }
+            }
+        });
+
+        sources.push(source);
+    }
+
+    sources
+}
+
+/// Find the string paths of all imports and includes in a syntax tree.
+fn collect_paths(node: &LinkedNode, f: &mut impl FnMut(&str, &LinkedNode)) {
+    if matches!(node.kind(), SyntaxKind::ModuleImport | SyntaxKind::ModuleInclude) {
+        if let Some(source) = node.children().find(|child| child.is::<ast::Expr>()) {
+            if let Some(ast::Expr::Str(path)) = source.cast::<ast::Expr>() {
+                f(&path.get(), &source);
+            }
+        }
+    }
+
+    for child in node.children() {
+        collect_paths(&child, f);
+    }
+}
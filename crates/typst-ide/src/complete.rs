@@ -1,5 +1,6 @@
 use std::cmp::Reverse;
 use std::collections::{BTreeSet, HashSet};
+use std::ffi::OsStr;
 
 use ecow::{eco_format, EcoString};
 use if_chain::if_chain;
@@ -9,8 +10,9 @@ use typst::foundations::{
     NoneValue, Repr, Scope, StyleChain, Styles, Type, Value,
 };
 use typst::model::Document;
+use typst::syntax::ast::{self, AstNode};
 use typst::syntax::{
-    ast, is_id_continue, is_id_start, is_ident, LinkedNode, Source, SyntaxKind,
+    is_id_continue, is_id_start, is_ident, LinkedNode, Source, SyntaxKind,
 };
 use typst::text::RawElem;
 use typst::visualize::Color;
@@ -18,6 +20,7 @@ use typst::World;
 use unscanny::Scanner;
 
 use crate::analyze::{analyze_expr, analyze_import, analyze_labels};
+use crate::signature::resolve_callee;
 use crate::{plain_docs_sentence, summarize_font_family};
 
 /// Autocomplete a cursor position in a source file.
@@ -44,6 +47,7 @@ pub fn autocomplete(
         || complete_field_accesses(&mut ctx)
         || complete_open_labels(&mut ctx)
         || complete_imports(&mut ctx)
+        || complete_paths(&mut ctx)
         || complete_rules(&mut ctx)
         || complete_params(&mut ctx)
         || complete_markup(&mut ctx)
@@ -85,6 +89,8 @@ pub enum CompletionKind {
     Constant,
     /// A symbol.
     Symbol(char),
+    /// A path to a file.
+    Path,
 }
 
 /// Complete in comments. Or rather, don't!
@@ -492,9 +498,23 @@ fn complete_imports(ctx: &mut CompletionContext) -> bool {
         let value = str.get();
         if value.starts_with('@');
         then {
-            let all_versions = value.contains(':');
             ctx.from = ctx.leaf.offset();
-            ctx.package_completions(all_versions);
+            ctx.package_completions(&value);
+            return true;
+        }
+    }
+
+    // In an import or include path for a file:
+    // "#import "|", "#include "chapters/|".
+    if_chain! {
+        if matches!(
+            ctx.leaf.parent_kind(),
+            Some(SyntaxKind::ModuleImport | SyntaxKind::ModuleInclude)
+        );
+        if ctx.leaf.kind() == SyntaxKind::Str;
+        then {
+            ctx.from = ctx.leaf.offset();
+            ctx.file_completions(&["typ"]);
             return true;
         }
     }
@@ -554,6 +574,78 @@ fn import_item_completions<'a>(
     }
 }
 
+/// Complete file paths in arguments that expect one.
+fn complete_paths(ctx: &mut CompletionContext) -> bool {
+    // In a string argument to a function that loads a file:
+    // "image("|")", "bibliography("refs.bib", style: "|")".
+    let Some(extensions) = path_argument_extensions(ctx) else { return false };
+
+    ctx.from = ctx.leaf.offset();
+    ctx.file_completions(extensions);
+    true
+}
+
+/// The file extensions accepted by the string argument the cursor is in, if
+/// it is an argument that expects a file path.
+fn path_argument_extensions(ctx: &CompletionContext) -> Option<&'static [&'static str]> {
+    if ctx.leaf.kind() != SyntaxKind::Str {
+        return None;
+    }
+
+    let parent = ctx.leaf.parent()?;
+    let named = parent.get().cast::<ast::Named>();
+    let parent = match named {
+        Some(_) => parent.parent()?,
+        None => parent,
+    };
+
+    let args = parent.get().cast::<ast::Args>()?;
+    let callee = match parent.parent()?.get().cast::<ast::Expr>()? {
+        ast::Expr::FuncCall(call) => call.callee(),
+        ast::Expr::Set(set) => set.target(),
+        _ => return None,
+    };
+
+    let func = resolve_global_callee(ctx, callee)?;
+    let param = match named {
+        Some(named) => func.param(&named.name())?,
+        None => {
+            // Find the positional parameter by counting the positional
+            // arguments before the string.
+            let index = args
+                .items()
+                .take_while(|arg| arg.span() != ctx.leaf.span())
+                .filter(|arg| matches!(arg, ast::Arg::Pos(_)))
+                .count();
+            func.params()?.iter().filter(|param| param.positional).nth(index)?
+        }
+    };
+
+    path_extensions(func.name()?, param.name)
+}
+
+/// The file extensions accepted by a parameter that expects a path, if any.
+///
+/// An empty list means that files of any type are accepted.
+fn path_extensions(func: &str, param: &str) -> Option<&'static [&'static str]> {
+    Some(match (func, param) {
+        ("image", "path") => &["png", "jpg", "jpeg", "gif", "svg", "svgz"],
+        ("read", "path") => &[],
+        ("csv", "path") => &["csv"],
+        ("json", "path") => &["json"],
+        ("toml", "path") => &["toml"],
+        ("yaml", "path") => &["yaml", "yml"],
+        ("xml", "path") => &["xml"],
+        ("cbor", "path") => &["cbor"],
+        ("plugin", "path") => &["wasm"],
+        ("bibliography", "path") => &["bib", "yaml", "yml"],
+        ("bibliography" | "cite", "style") => &["csl"],
+        ("raw", "syntaxes") => &["sublime-syntax"],
+        ("raw", "theme") => &["tmtheme"],
+        _ => return None,
+    })
+}
+
 /// Complete set and show rules.
 fn complete_rules(ctx: &mut CompletionContext) -> bool {
     // We don't want to complete directly behind the keyword.
@@ -653,7 +745,7 @@ fn show_rule_recipe_completions(ctx: &mut CompletionContext) {
 /// Complete call and set rule parameters.
 fn complete_params(ctx: &mut CompletionContext) -> bool {
     // Ensure that we are in a function call or set rule's argument list.
-    let (callee, set, args) = if_chain! {
+    let (callee, set, args, call) = if_chain! {
         if let Some(parent) = ctx.leaf.parent();
         if let Some(parent) = match parent.kind() {
             SyntaxKind::Named => parent.parent(),
@@ -669,7 +761,7 @@ fn complete_params(ctx: &mut CompletionContext) -> bool {
             _ => None,
         };
         then {
-            (callee, set, args)
+            (callee, set, args, grand.clone())
         } else {
             return false;
         }
@@ -695,7 +787,9 @@ fn complete_params(ctx: &mut CompletionContext) -> bool {
                 ctx.from = ctx.cursor.min(next.offset());
             }
 
-            named_param_value_completions(ctx, callee, &param);
+            if let Some(callee) = call.find(callee.span()) {
+                named_param_value_completions(ctx, &callee, &param);
+            }
             return true;
         }
     }
@@ -767,18 +861,26 @@ fn param_completions<'a>(
 /// Add completions for the values of a named function parameter.
 fn named_param_value_completions<'a>(
     ctx: &mut CompletionContext<'a>,
-    callee: ast::Expr<'a>,
+    callee: &LinkedNode<'a>,
     name: &str,
 ) {
-    let Some(func) = resolve_global_callee(ctx, callee) else { return };
-    let Some(param) = func.param(name) else { return };
-    if !param.named {
-        return;
-    }
+    let Some(expr) = callee.get().cast::<ast::Expr>() else { return };
+    if let Some(func) = resolve_global_callee(ctx, expr) {
+        let Some(param) = func.param(name) else { return };
+        if !param.named {
+            return;
+        }
 
-    ctx.cast_completions(&param.input);
-    if name == "font" {
-        ctx.font_completions();
+        ctx.cast_completions(&param.input);
+        if name == "font" {
+            ctx.font_completions();
+        }
+    } else {
+        // User-defined functions may annotate the types of their parameters.
+        let Some((func, _)) = resolve_callee(ctx.world, callee) else { return };
+        let Some(types) = func.param_types(name) else { return };
+        let types = types.iter().copied().map(CastInfo::Type).collect();
+        ctx.cast_completions(&CastInfo::Union(types));
     }
 
     if ctx.before.ends_with(':') {
@@ -912,6 +1014,12 @@ fn code_completions(ctx: &mut CompletionContext, hash: bool) {
         "Defines a function.",
     );
 
+    ctx.snippet_completion(
+        "private let binding",
+        "private let ${name} = ${value}",
+        "Saves a value in a variable that is not part of the module.",
+    );
+
     ctx.snippet_completion(
         "if conditional",
         "if ${1 < 2} {\n\t${}\n}",
@@ -972,6 +1080,12 @@ fn code_completions(ctx: &mut CompletionContext, hash: bool) {
         "Imports variables from another file.",
     );
 
+    ctx.snippet_completion(
+        "private import (file)",
+        "private import \"${file}.typ\": ${items}",
+        "Imports variables from another file without passing them on.",
+    );
+
     ctx.snippet_completion(
         "include (file)",
         "include \"${file}.typ\"",
@@ -1096,12 +1210,30 @@ impl<'a> CompletionContext<'a> {
     }
 
     /// Add completions for all available packages.
-    fn package_completions(&mut self, all_versions: bool) {
-        let mut packages: Vec<_> = self.world.packages().iter().collect();
+    ///
+    /// Once the typed package name is followed by a colon, all versions of
+    /// that package are suggested. Otherwise, only the latest version of each
+    /// package is.
+    fn package_completions(&mut self, typed: &str) {
+        let versioned = typed.split_once(':').map(|(name, _)| name);
+        let mut packages: Vec<_> = self
+            .world
+            .packages()
+            .iter()
+            .filter(|(spec, _)| {
+                versioned.map_or(true, |name| {
+                    name.strip_prefix('@')
+                        .and_then(|name| name.split_once('/'))
+                        .is_some_and(|(namespace, name)| {
+                            spec.namespace == namespace && spec.name == name
+                        })
+                })
+            })
+            .collect();
         packages.sort_by_key(|(spec, _)| {
             (&spec.namespace, &spec.name, Reverse(spec.version))
         });
-        if !all_versions {
+        if versioned.is_none() {
             packages.dedup_by_key(|(spec, _)| (&spec.namespace, &spec.name));
         }
         for (package, description) in packages {
@@ -1114,6 +1246,50 @@ impl<'a> CompletionContext<'a> {
         }
     }
 
+    /// Add completions for the files in the project that have one of the
+    /// given extensions, relative to the current file.
+    ///
+    /// An empty list of extensions accepts files of any type.
+    fn file_completions(&mut self, extensions: &[&str]) {
+        let Some(base) = self.leaf.span().id() else { return };
+        let Some(dir) = base.vpath().as_rooted_path().parent() else { return };
+
+        let mut paths: Vec<String> = self
+            .world
+            .files()
+            .into_iter()
+            .filter(|&id| id != base && id.package() == base.package())
+            .filter(|id| {
+                let ext = id
+                    .vpath()
+                    .as_rooted_path()
+                    .extension()
+                    .and_then(OsStr::to_str)
+                    .unwrap_or_default()
+                    .to_lowercase();
+                extensions.is_empty() || extensions.contains(&ext.as_str())
+            })
+            .filter_map(|id| pathdiff::diff_paths(id.vpath().as_rooted_path(), dir))
+            .map(|path| path.to_string_lossy().replace('\\', "/"))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let label = path.as_str().repr();
+            let apply = self
+                .after
+                .starts_with('"')
+                .then(|| label.strip_suffix('"').map(EcoString::from))
+                .flatten();
+            self.completions.push(Completion {
+                kind: CompletionKind::Path,
+                label,
+                apply,
+                detail: None,
+            });
+        }
+    }
+
     /// Add completions for raw block tags.
     fn raw_completions(&mut self) {
         for (name, mut tags) in RawElem::languages() {
@@ -1227,7 +1403,7 @@ impl<'a> CompletionContext<'a> {
     }
 
     /// Add completions for a castable.
-    fn cast_completions(&mut self, cast: &'a CastInfo) {
+    fn cast_completions(&mut self, cast: &CastInfo) {
         // Prevent duplicate completions from appearing.
         if !self.seen_casts.insert(typst::util::hash128(cast)) {
             return;
@@ -1403,3 +1579,88 @@ impl<'a> CompletionContext<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use typst::World;
+
+    use super::autocomplete;
+    use crate::tests::TestWorld;
+
+    /// The labels and applied texts of the completions at a cursor position.
+    fn complete(world: &TestWorld, cursor: usize) -> Vec<(String, Option<String>)> {
+        let (_, completions) =
+            autocomplete(world, None, &world.main(), cursor, false).unwrap();
+        completions
+            .into_iter()
+            .map(|completion| (completion.label.into(), completion.apply.map(Into::into)))
+            .collect()
+    }
+
+    #[track_caller]
+    fn test(world: &TestWorld, cursor: usize, expected: &[(&str, &str)]) {
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|&(label, apply)| (label.into(), Some(apply.into())))
+            .collect();
+        assert_eq!(complete(world, cursor), expected);
+    }
+
+    #[test]
+    fn test_complete_paths() {
+        let world = TestWorld::new("#image(\"\")")
+            .with_file("img/a.png", b"")
+            .with_file("img/b.svg", b"")
+            .with_file("data.csv", b"");
+        test(
+            &world,
+            8,
+            &[("\"img/a.png\"", "\"img/a.png"), ("\"img/b.svg\"", "\"img/b.svg")],
+        );
+    }
+
+    #[test]
+    fn test_complete_import_paths() {
+        let world = TestWorld::new("#import \"\"")
+            .with_source("chapter.typ", "")
+            .with_source("sub/intro.typ", "")
+            .with_file("data.csv", b"");
+        test(
+            &world,
+            9,
+            &[
+                ("\"chapter.typ\"", "\"chapter.typ"),
+                ("\"sub/intro.typ\"", "\"sub/intro.typ"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_complete_package_versions() {
+        let world = TestWorld::new("#import \"@\"")
+            .with_package("@preview/foo:0.1.0")
+            .with_package("@preview/foo:0.2.0")
+            .with_package("@preview/bar:1.0.0");
+        test(
+            &world,
+            10,
+            &[
+                ("\"@preview/bar:1.0.0\"", "\"@preview/bar:1.0.0"),
+                ("\"@preview/foo:0.2.0\"", "\"@preview/foo:0.2.0"),
+            ],
+        );
+
+        let world = TestWorld::new("#import \"@preview/foo:\"")
+            .with_package("@preview/foo:0.1.0")
+            .with_package("@preview/foo:0.2.0")
+            .with_package("@preview/bar:1.0.0");
+        test(
+            &world,
+            22,
+            &[
+                ("\"@preview/foo:0.2.0\"", "\"@preview/foo:0.2.0"),
+                ("\"@preview/foo:0.1.0\"", "\"@preview/foo:0.1.0"),
+            ],
+        );
+    }
+}
@@ -0,0 +1,638 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
+
+use ecow::EcoString;
+use typst::foundations::{Label, Value};
+use typst::model::Document;
+use typst::syntax::ast::{self, AstNode};
+use typst::syntax::{FileId, LinkedNode, Source, Span, SyntaxKind};
+use typst::World;
+
+use crate::analyze::{analyze_expr, analyze_import, analyze_sources};
+
+/// The place where an identifier or label is defined.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Definition {
+    /// The defined name.
+    pub name: EcoString,
+    /// The file in which the name is defined.
+    pub id: FileId,
+    /// The byte range of the defining name in the file. Empty if the name
+    /// refers to a whole module file.
+    pub range: Range<usize>,
+}
+
+/// Find the definition of the identifier or label under the cursor.
+///
+/// Identifiers are resolved through the lexical scopes of the syntax tree and
+/// through imports. Definitions in the standard library have no source and
+/// thus yield `None`.
+///
+/// Labels are resolved through the `document` (from a previous compilation)
+/// if it is available. Otherwise, the project's sources are searched for a
+/// matching label in markup.
+pub fn definition(
+    world: &dyn World,
+    document: Option<&Document>,
+    source: &Source,
+    cursor: usize,
+) -> Option<Definition> {
+    let leaf = named_leaf_at(source, cursor)?;
+    match label_name(&leaf) {
+        Some(label) => label_definition(world, document, label),
+        None => Resolver::new(world).ident(&leaf),
+    }
+}
+
+/// Find all references to the identifier or label under the cursor across the
+/// project, including its definition.
+///
+/// The project consists of the main file and all local files it imports or
+/// includes, plus the given `source`. The returned ranges cover only the names
+/// themselves, i.e. without the `@` of references or the angle brackets of
+/// labels.
+pub fn references(
+    world: &dyn World,
+    source: &Source,
+    cursor: usize,
+) -> Vec<(FileId, Range<usize>)> {
+    let Some(leaf) = named_leaf_at(source, cursor) else { return vec![] };
+    let sources = project_sources(world, source);
+
+    if let Some(label) = label_name(&leaf) {
+        let mut output = vec![];
+        for source in &sources {
+            descendants(&LinkedNode::new(source.root()), &mut |node| {
+                if label_name(node) == Some(label) {
+                    output.push((source.id(), name_range(node)));
+                }
+            });
+        }
+        return output;
+    }
+
+    let resolver = Resolver::new(world);
+    match resolver.ident(&leaf) {
+        Some(target) => ident_references(&resolver, &sources, &target),
+        None => vec![],
+    }
+}
+
+/// The sources of the project, plus the given source if it is not part of
+/// the project.
+pub(crate) fn project_sources(world: &dyn World, source: &Source) -> Vec<Source> {
+    let mut sources = analyze_sources(world, false);
+    if sources.iter().all(|other| other.id() != source.id()) {
+        sources.push(source.clone());
+    }
+    sources
+}
+
+/// Find all identifiers in the given sources that resolve to a definition.
+pub(crate) fn ident_references(
+    resolver: &Resolver,
+    sources: &[Source],
+    target: &Definition,
+) -> Vec<(FileId, Range<usize>)> {
+    let mut output = vec![];
+    for source in sources {
+        // Names under which the definition was imported elsewhere.
+        let mut names = vec![target.name.clone()];
+        descendants(&LinkedNode::new(source.root()), &mut |node| {
+            if let Some(item) = node.cast::<ast::RenamedImportItem>() {
+                if item.original_name().as_str() == target.name {
+                    names.push(item.new_name().get().clone());
+                }
+            }
+        });
+
+        descendants(&LinkedNode::new(source.root()), &mut |node| {
+            if matches!(node.kind(), SyntaxKind::Ident | SyntaxKind::MathIdent)
+                && names.contains(node.text())
+                && resolver.ident(node).as_ref() == Some(target)
+            {
+                output.push((source.id(), node.range()));
+            }
+        });
+    }
+
+    output
+}
+
+/// Find the identifier, label, or reference at or directly after the cursor.
+pub(crate) fn named_leaf_at(source: &Source, cursor: usize) -> Option<LinkedNode> {
+    let root = LinkedNode::new(source.root());
+    [cursor, cursor + 1].into_iter().find_map(|cursor| {
+        let leaf = root.leaf_at(cursor)?;
+        matches!(
+            leaf.kind(),
+            SyntaxKind::Ident
+                | SyntaxKind::MathIdent
+                | SyntaxKind::Label
+                | SyntaxKind::RefMarker
+        )
+        .then_some(leaf)
+    })
+}
+
+/// The label a label or reference node refers to.
+pub(crate) fn label_name<'a>(node: &LinkedNode<'a>) -> Option<&'a str> {
+    match node.kind() {
+        SyntaxKind::Label => Some(node.get().cast::<ast::Label>()?.get()),
+        SyntaxKind::RefMarker => Some(node.get().text().trim_start_matches('@')),
+        _ => None,
+    }
+}
+
+/// The range of the name in an identifier, label, or reference node.
+pub(crate) fn name_range(node: &LinkedNode) -> Range<usize> {
+    let range = node.range();
+    match node.kind() {
+        SyntaxKind::Label => range.start + 1..range.end - 1,
+        SyntaxKind::RefMarker => range.start + 1..range.end,
+        _ => range,
+    }
+}
+
+/// Resolves identifiers to their definitions.
+///
+/// Caches the modules and values it analyzes, so that resolving many
+/// identifiers, for example when searching for references across a project,
+/// evaluates each import and expression only once.
+pub(crate) struct Resolver<'a> {
+    world: &'a dyn World,
+    /// The modules imported through import sources, keyed by their span.
+    modules: RefCell<HashMap<Span, Option<Value>>>,
+    /// The values of expressions, keyed by their span.
+    values: RefCell<HashMap<Span, Option<Value>>>,
+}
+
+impl<'a> Resolver<'a> {
+    /// Create a resolver without cached analyses.
+    pub fn new(world: &'a dyn World) -> Self {
+        Self {
+            world,
+            modules: RefCell::new(HashMap::new()),
+            values: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Find the definition of an identifier.
+    pub fn ident(&self, leaf: &LinkedNode) -> Option<Definition> {
+        let name = leaf.text().as_str();
+        let parent = leaf.parent()?;
+
+        if !is_scoped(leaf) {
+            return match parent.kind() {
+                // A field of a module: "utils.name".
+                SyntaxKind::FieldAccess => {
+                    let target = parent.children().next()?;
+                    let value = self.value(&target)?;
+                    self.module(&value, name)
+                }
+                // The original name of a renamed import: "import "a.typ": name as other".
+                SyntaxKind::RenamedImportItem => {
+                    let import = parent.parent()?.parent()?;
+                    self.import(import, None, name)
+                }
+                _ => None,
+            };
+        }
+
+        // The identifier is itself a binding.
+        let mut ancestor = parent;
+        loop {
+            if let Some(idents) = declared_idents(ancestor) {
+                if idents.iter().any(|ident| ident.span() == leaf.span()) {
+                    let import = ancestor.cast::<ast::ModuleImport>();
+                    if let Some(def) = import
+                        .and_then(|import| self.import(ancestor, Some(import), name))
+                    {
+                        return Some(def);
+                    }
+                    return Some(Definition {
+                        name: name.into(),
+                        id: leaf.span().id()?,
+                        range: leaf.range(),
+                    });
+                }
+                break;
+            }
+
+            match ancestor.parent() {
+                Some(parent) => ancestor = parent,
+                None => break,
+            }
+        }
+
+        self.scope(leaf, name)
+    }
+
+    /// Find the definition a name resolves to at the position of a node by
+    /// searching the enclosing scopes from the inside out.
+    pub fn scope(&self, node: &LinkedNode, name: &str) -> Option<Definition> {
+        let mut node = node.clone();
+        loop {
+            let mut sibling = node.prev_sibling();
+            while let Some(prev) = sibling {
+                if let Some(def) = self.binding(&prev, name) {
+                    return Some(def);
+                }
+                sibling = prev.prev_sibling();
+            }
+
+            let parent = node.parent()?.clone();
+            let in_scope = match parent.cast::<ast::Expr>() {
+                Some(ast::Expr::Closure(closure)) => closure.body().span() == node.span(),
+                Some(ast::Expr::For(for_loop)) => for_loop.body().span() == node.span(),
+                _ => false,
+            };
+
+            if in_scope {
+                let idents = declared_idents(&parent).unwrap_or_default();
+                if let Some(ident) = idents.iter().find(|ident| ident.as_str() == name) {
+                    return Some(Definition {
+                        name: name.into(),
+                        id: ident.span().id()?,
+                        range: parent.find(ident.span())?.range(),
+                    });
+                }
+            }
+
+            node = parent;
+        }
+    }
+
+    /// Find the definition of a name if the given let binding or import binds it.
+    fn binding(&self, node: &LinkedNode, name: &str) -> Option<Definition> {
+        if let Some(binding) = node.cast::<ast::LetBinding>() {
+            let ident = binding
+                .kind()
+                .bindings()
+                .into_iter()
+                .rev()
+                .find(|ident| ident.as_str() == name)?;
+            return Some(Definition {
+                name: name.into(),
+                id: ident.span().id()?,
+                range: node.find(ident.span())?.range(),
+            });
+        }
+
+        if let Some(import) = node.cast::<ast::ModuleImport>() {
+            // Fall back to the binding in the import itself if the module can't
+            // be analyzed.
+            let fallback = || {
+                let ident = declared_idents(node)?
+                    .into_iter()
+                    .find(|ident| ident.as_str() == name)?;
+                Some(Definition {
+                    name: name.into(),
+                    id: ident.span().id()?,
+                    range: node.find(ident.span())?.range(),
+                })
+            };
+
+            return self.import(node, Some(import), name).or_else(fallback);
+        }
+
+        None
+    }
+
+    /// Find the definition of a name that is bound by an import.
+    ///
+    /// If no `import` is given, the name is looked up as an original name in the
+    /// imported module.
+    fn import(
+        &self,
+        node: &LinkedNode,
+        import: Option<ast::ModuleImport>,
+        name: &str,
+    ) -> Option<Definition> {
+        let source = node.children().find(|child| child.is::<ast::Expr>())?;
+        let Some(import) = import else {
+            let value = self.module_value(&source)?;
+            return self.module(&value, name);
+        };
+
+        if import.new_name().is_some_and(|ident| ident.as_str() == name) {
+            let value = self.module_value(&source)?;
+            return file_definition(&value, name);
+        }
+
+        match import.imports() {
+            None => {
+                let value = self.module_value(&source)?;
+                if import.new_name().is_some() || value.name() != Some(name) {
+                    return None;
+                }
+                file_definition(&value, name)
+            }
+            Some(ast::Imports::Wildcard) => {
+                let value = self.module_value(&source)?;
+                self.module(&value, name)
+            }
+            Some(ast::Imports::Items(items)) => {
+                let item =
+                    items.iter().find(|item| item.bound_name().as_str() == name)?;
+                let value = self.module_value(&source)?;
+                self.module(&value, item.original_name().as_str())
+            }
+        }
+    }
+
+    /// Find the top-level definition of a name in a module evaluated from a file.
+    fn module(&self, value: &Value, name: &str) -> Option<Definition> {
+        let Value::Module(module) = value else { return None };
+        module.scope().get(name)?;
+
+        let source = self.world.source(module.file()?).ok()?;
+        let root = LinkedNode::new(source.root());
+        let children: Vec<_> = root.children().collect();
+        children.iter().rev().find_map(|child| self.binding(child, name))
+    }
+
+    /// The module imported through an import source.
+    fn module_value(&self, source: &LinkedNode) -> Option<Value> {
+        cached(&self.modules, source.span(), || analyze_import(self.world, source))
+    }
+
+    /// The value of an expression.
+    fn value(&self, node: &LinkedNode) -> Option<Value> {
+        cached(&self.values, node.span(), || {
+            analyze_expr(self.world, node)
+                .into_iter()
+                .next()
+                .map(|(value, _)| value)
+        })
+    }
+}
+
+/// Look up an analysis in a cache or perform and store it.
+fn cached(
+    cache: &RefCell<HashMap<Span, Option<Value>>>,
+    span: Span,
+    f: impl FnOnce() -> Option<Value>,
+) -> Option<Value> {
+    if span.is_detached() {
+        return f();
+    }
+
+    if let Some(value) = cache.borrow().get(&span) {
+        return value.clone();
+    }
+
+    let value = f();
+    cache.borrow_mut().insert(span, value.clone());
+    value
+}
+
+/// Find the definition of an identifier.
+pub(crate) fn ident_definition(
+    world: &dyn World,
+    leaf: &LinkedNode,
+) -> Option<Definition> {
+    Resolver::new(world).ident(leaf)
+}
+
+/// Find the definition a name resolves to at the position of a node.
+pub(crate) fn scope_definition(
+    world: &dyn World,
+    node: &LinkedNode,
+    name: &str,
+) -> Option<Definition> {
+    Resolver::new(world).scope(node, name)
+}
+
+/// Whether an identifier is resolved through the enclosing scopes.
+///
+/// This is not the case for fields, the names of named arguments and
+/// dictionary keys, and the original names of renamed imports.
+pub(crate) fn is_scoped(leaf: &LinkedNode) -> bool {
+    let Some(parent) = leaf.parent() else { return true };
+    match parent.kind() {
+        SyntaxKind::FieldAccess => leaf.index() == 0,
+        SyntaxKind::Named => {
+            leaf.index() > 0 || parent.parent_kind() == Some(SyntaxKind::Params)
+        }
+        SyntaxKind::RenamedImportItem => leaf.prev_sibling().is_some(),
+        _ => true,
+    }
+}
+
+/// The identifiers bound by a let binding, closure, for loop, or import.
+fn declared_idents<'a>(node: &LinkedNode<'a>) -> Option<Vec<ast::Ident<'a>>> {
+    Some(match node.get().cast::<ast::Expr>()? {
+        ast::Expr::Let(binding) => binding.kind().bindings(),
+        ast::Expr::Closure(closure) => {
+            let mut idents: Vec<_> = closure.name().into_iter().collect();
+            for param in closure.params().children() {
+                match param {
+                    ast::Param::Pos(pattern) => idents.extend(pattern.bindings()),
+                    ast::Param::Named(named) => idents.push(named.name()),
+                    ast::Param::Spread(spread) => idents.extend(spread.sink_ident()),
+                }
+            }
+            idents
+        }
+        ast::Expr::For(for_loop) => for_loop.pattern().bindings(),
+        ast::Expr::Import(import) => {
+            let mut idents: Vec<_> = import.new_name().into_iter().collect();
+            if let Some(ast::Imports::Items(items)) = import.imports() {
+                idents.extend(items.iter().map(|item| item.bound_name()));
+            }
+            idents
+        }
+        _ => return None,
+    })
+}
+
+/// A definition that refers to a whole module file.
+fn file_definition(value: &Value, name: &str) -> Option<Definition> {
+    let Value::Module(module) = value else { return None };
+    Some(Definition { name: name.into(), id: module.file()?, range: 0..0 })
+}
+
+/// Find the definition of a label.
+fn label_definition(
+    world: &dyn World,
+    document: Option<&Document>,
+    name: &str,
+) -> Option<Definition> {
+    if let Some(document) = document {
+        let label = Label::new(name);
+        for elem in document.introspector.all() {
+            if elem.label() != Some(label) {
+                continue;
+            }
+
+            let span = elem.span();
+            let Some(id) = span.id() else { continue };
+            let Ok(source) = world.source(id) else { continue };
+            let Some(range) = source.range(span) else { continue };
+
+            // Prefer the label's own syntax, which typically directly follows
+            // or ends the labelled element.
+            let mut best: Option<Range<usize>> = None;
+            descendants(&LinkedNode::new(source.root()), &mut |node| {
+                if node.kind() == SyntaxKind::Label
+                    && label_name(node) == Some(name)
+                    && node.offset() >= range.start
+                    && best.as_ref().map_or(true, |best| node.offset() < best.start)
+                {
+                    best = Some(name_range(node));
+                }
+            });
+
+            return Some(Definition {
+                name: name.into(),
+                id,
+                range: best.unwrap_or(range),
+            });
+        }
+    }
+
+    for source in analyze_sources(world, false) {
+        let mut found = None;
+        descendants(&LinkedNode::new(source.root()), &mut |node| {
+            if found.is_none()
+                && node.kind() == SyntaxKind::Label
+                && node.parent_kind() == Some(SyntaxKind::Markup)
+                && label_name(node) == Some(name)
+            {
+                found = Some(name_range(node));
+            }
+        });
+
+        if let Some(range) = found {
+            return Some(Definition { name: name.into(), id: source.id(), range });
+        }
+    }
+
+    None
+}
+
+/// Call `f` for a node and all its descendants in preorder.
+pub(crate) fn descendants<'a>(
+    node: &LinkedNode<'a>,
+    f: &mut impl FnMut(&LinkedNode<'a>),
+) {
+    f(node);
+    for child in node.children() {
+        descendants(&child, f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use typst::World;
+
+    use super::{definition, references};
+    use crate::tests::{id, TestWorld};
+
+    #[track_caller]
+    fn test_definition(world: &TestWorld, cursor: usize, expected: (&str, Range<usize>)) {
+        let def =
+            definition(world, None, &world.main(), cursor).expect("no definition found");
+        assert_eq!((def.id, def.range), (id(expected.0), expected.1));
+    }
+
+    #[track_caller]
+    fn test_references(
+        world: &TestWorld,
+        cursor: usize,
+        expected: &[(&str, Range<usize>)],
+    ) {
+        let found = references(world, &world.main(), cursor);
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(path, range)| (id(path), range.clone()))
+            .collect();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_definition_local() {
+        let world = TestWorld::new("#let x = 1\n#x");
+        test_definition(&world, 12, ("main.typ", 5..6));
+
+        let world = TestWorld::new("#let f(a, b: 2) = a + b");
+        test_definition(&world, 22, ("main.typ", 10..11));
+        test_definition(&world, 18, ("main.typ", 7..8));
+    }
+
+    #[test]
+    fn test_definition_shadowed() {
+        let world = TestWorld::new("#let x = 1\n#let x = x + 1\n#x");
+        test_definition(&world, 20, ("main.typ", 5..6));
+        test_definition(&world, 27, ("main.typ", 16..17));
+    }
+
+    #[test]
+    fn test_definition_import() {
+        let world =
+            TestWorld::new("#import \"a.typ\": y\n#y").with_source("a.typ", "#let y = 1");
+        test_definition(&world, 20, ("a.typ", 5..6));
+        test_definition(&world, 17, ("a.typ", 5..6));
+
+        let world =
+            TestWorld::new("#import \"a.typ\"\n#a.y").with_source("a.typ", "#let y = 1");
+        test_definition(&world, 19, ("a.typ", 5..6));
+        test_definition(&world, 17, ("a.typ", 0..0));
+    }
+
+    #[test]
+    fn test_definition_label() {
+        let world = TestWorld::new("= Intro <intro>\nSee @intro.");
+        test_definition(&world, 21, ("main.typ", 9..14));
+    }
+
+    #[test]
+    fn test_definition_library() {
+        let world = TestWorld::new("#rect()");
+        assert_eq!(definition(&world, None, &world.main(), 1), None);
+    }
+
+    #[test]
+    fn test_references_local() {
+        let world = TestWorld::new("#let x = 1\n#(x + x)");
+        test_references(
+            &world,
+            5,
+            &[("main.typ", 5..6), ("main.typ", 13..14), ("main.typ", 17..18)],
+        );
+
+        let world = TestWorld::new("#let x = 1\n#let x = x + 1\n#x");
+        test_references(&world, 5, &[("main.typ", 5..6), ("main.typ", 20..21)]);
+        test_references(&world, 27, &[("main.typ", 16..17), ("main.typ", 27..28)]);
+    }
+
+    #[test]
+    fn test_references_import() {
+        let world = TestWorld::new("#import \"a.typ\": y\n#y")
+            .with_source("a.typ", "#let y = 1\n#y");
+        test_references(
+            &world,
+            20,
+            &[
+                ("main.typ", 17..18),
+                ("main.typ", 20..21),
+                ("a.typ", 5..6),
+                ("a.typ", 12..13),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_references_label() {
+        let world = TestWorld::new("= Intro <intro>\nSee @intro and @intro.");
+        test_references(
+            &world,
+            9,
+            &[("main.typ", 9..14), ("main.typ", 21..26), ("main.typ", 32..37)],
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use std::cmp::Reverse;
+use std::ops::Range;
+
+use typst::syntax::{ast, LinkedNode, Source, SyntaxKind};
+
+/// A region of a source file that can be folded.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct FoldingRange {
+    /// The byte range of the region.
+    pub range: Range<usize>,
+    /// What kind of region this is.
+    pub kind: FoldingKind,
+}
+
+/// A kind of [`FoldingRange`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum FoldingKind {
+    /// A heading's section, up to the next heading of the same or a lower
+    /// level.
+    Section,
+    /// A delimited piece of syntax like a code or content block, an argument
+    /// list or a raw block.
+    Region,
+    /// A block comment or a run of line comments.
+    Comment,
+}
+
+/// Find the foldable regions of a source file.
+///
+/// Only regions that span multiple lines are returned. They are sorted by
+/// their start and, for the same start, outer regions come first.
+pub fn folding_ranges(source: &Source) -> Vec<FoldingRange> {
+    let root = LinkedNode::new(source.root());
+    let mut ranges = vec![];
+    collect(&root, &mut ranges);
+
+    let line = |byte| source.byte_to_line(byte);
+    ranges.retain(|folding| line(folding.range.start) != line(folding.range.end));
+    ranges.sort_by_key(|folding| (folding.range.start, Reverse(folding.range.end)));
+    ranges
+}
+
+/// Collect the foldable regions within a node.
+fn collect(node: &LinkedNode, ranges: &mut Vec<FoldingRange>) {
+    if node.kind() == SyntaxKind::Markup {
+        sections(node, ranges);
+    }
+
+    let mut comments: Option<Range<usize>> = None;
+    for child in node.children() {
+        match child.kind() {
+            SyntaxKind::LineComment => {
+                let range = child.range();
+                match &mut comments {
+                    Some(run) => run.end = range.end,
+                    None => comments = Some(range),
+                }
+                continue;
+            }
+            // A blank line ends a run of line comments.
+            SyntaxKind::Space
+                if child.text().chars().filter(|&c| c == '\n').count() < 2 =>
+            {
+                continue;
+            }
+            _ => {}
+        }
+
+        if let Some(range) = comments.take() {
+            ranges.push(FoldingRange { range, kind: FoldingKind::Comment });
+        }
+
+        if let Some(kind) = folding_kind(&child) {
+            ranges.push(FoldingRange { range: child.range(), kind });
+        }
+
+        collect(&child, ranges);
+    }
+
+    if let Some(range) = comments {
+        ranges.push(FoldingRange { range, kind: FoldingKind::Comment });
+    }
+}
+
+/// The kind of region a node forms by itself, if it is foldable.
+fn folding_kind(node: &LinkedNode) -> Option<FoldingKind> {
+    match node.kind() {
+        SyntaxKind::CodeBlock
+        | SyntaxKind::ContentBlock
+        | SyntaxKind::Args
+        | SyntaxKind::Params
+        | SyntaxKind::Array
+        | SyntaxKind::Dict
+        | SyntaxKind::Raw
+        | SyntaxKind::Equation => Some(FoldingKind::Region),
+        SyntaxKind::BlockComment => Some(FoldingKind::Comment),
+        _ => None,
+    }
+}
+
+/// Collect the sections of the headings in a markup node.
+///
+/// A section ends at the last non-whitespace content before the next heading
+/// of the same or a lower level, or before the end of the markup.
+fn sections(markup: &LinkedNode, ranges: &mut Vec<FoldingRange>) {
+    let mut open: Vec<(usize, usize)> = vec![];
+    let mut end = markup.offset();
+    for child in markup.children() {
+        if let Some(heading) = child.cast::<ast::Heading>() {
+            let depth = heading.depth().get();
+            while open.last().is_some_and(|&(level, _)| level >= depth) {
+                let (_, start) = open.pop().unwrap();
+                let range = start..end;
+                ranges.push(FoldingRange { range, kind: FoldingKind::Section });
+            }
+            open.push((depth, child.offset()));
+        }
+
+        if !matches!(child.kind(), SyntaxKind::Space | SyntaxKind::Parbreak) {
+            end = child.range().end;
+        }
+    }
+
+    for (_, start) in open.into_iter().rev() {
+        ranges.push(FoldingRange { range: start..end, kind: FoldingKind::Section });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::syntax::Source;
+
+    use super::{folding_ranges, FoldingKind, FoldingRange};
+
+    #[test]
+    fn test_folding_ranges() {
+        let source = Source::detached(
+            "= A\ntext\n== B\nmore\n\n= C\n#{\n  1\n}\n// a\n// b\n\n// c",
+        );
+        let fold = |range, kind| FoldingRange { range, kind };
+        assert_eq!(
+            folding_ranges(&source),
+            [
+                fold(0..18, FoldingKind::Section),
+                fold(9..18, FoldingKind::Section),
+                fold(20..48, FoldingKind::Section),
+                fold(25..32, FoldingKind::Region),
+                fold(33..42, FoldingKind::Comment),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_folding_single_line() {
+        let source = Source::detached("#f(1, 2) /* x */");
+        assert_eq!(folding_ranges(&source), []);
+    }
+}
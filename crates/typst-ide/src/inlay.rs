@@ -0,0 +1,470 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use comemo::Track;
+use ecow::{eco_format, EcoString};
+use typst::eval::{eval_string, EvalMode, Tracer};
+use typst::foundations::{Repr, Scope, StyleChain, Value};
+use typst::layout::{Abs, Length};
+use typst::syntax::ast::{self, AstNode};
+use typst::syntax::{LinkedNode, Source, SyntaxKind};
+use typst::text::TextElem;
+use typst::util::{round_2, Numeric};
+use typst::World;
+
+use crate::definition::{descendants, ident_definition, is_scoped};
+use crate::semantic::static_value;
+
+/// An annotation that is shown inline with the source code.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct InlayHint {
+    /// The byte offset at which the hint is shown.
+    pub position: usize,
+    /// The text of the hint.
+    pub label: EcoString,
+    /// What kind of hint this is.
+    pub kind: InlayHintKind,
+    /// Whether the hint should be separated from the code before it.
+    pub padding_left: bool,
+    /// Whether the hint should be separated from the code after it.
+    pub padding_right: bool,
+}
+
+/// A kind of [`InlayHint`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum InlayHintKind {
+    /// The name of the parameter a positional argument is for.
+    Parameter,
+    /// The value of an expression.
+    Value,
+}
+
+/// The maximum length of a value's representation in a hint.
+const MAX_VALUE_LEN: usize = 40;
+
+/// Compute the inlay hints for a range of a source file.
+///
+/// Produces hints with the parameter names of positional arguments, the
+/// resolved sizes of font-relative lengths, and the values of simple `let`
+/// bindings at the top level of the file.
+///
+/// Parameter names are only shown for calls whose callee can be determined
+/// without evaluating code, i.e. calls of the standard library and of
+/// functions defined with `let` in the project. A `let` binding is simple if
+/// it can be evaluated on its own or from the values of other simple bindings.
+pub fn inlay_hints(
+    world: &dyn World,
+    source: &Source,
+    range: Range<usize>,
+) -> Vec<InlayHint> {
+    let root = LinkedNode::new(source.root());
+    let mut hints = vec![];
+
+    descendants(&root, &mut |node| {
+        let node_range = node.range();
+        if node_range.end < range.start || node_range.start > range.end {
+            return;
+        }
+
+        match node.kind() {
+            SyntaxKind::FuncCall | SyntaxKind::SetRule => {
+                param_hints(world, node, &mut hints);
+            }
+            SyntaxKind::Numeric => hints.extend(length_hint(world, node)),
+            _ => {}
+        }
+    });
+
+    hints.extend(
+        value_hints(world, &root)
+            .into_iter()
+            .filter(|hint| range.contains(&hint.position) || range.end == hint.position),
+    );
+
+    hints.sort_by_key(|hint| hint.position);
+    hints
+}
+
+/// Annotate the positional arguments of a call or set rule with the names of
+/// their parameters.
+fn param_hints(world: &dyn World, node: &LinkedNode, hints: &mut Vec<InlayHint>) {
+    let Some(callee) = node.children().find(|child| child.is::<ast::Expr>()) else {
+        return;
+    };
+
+    let Some(args) = node.children().find(|child| child.kind() == SyntaxKind::Args)
+    else {
+        return;
+    };
+
+    let Some(params) = positional_params(world, &callee) else { return };
+
+    // A single parameter is obvious from the function's name.
+    if params.len() < 2 && !params.iter().any(|(_, variadic)| *variadic) {
+        return;
+    }
+
+    let close = args
+        .children()
+        .find(|child| child.kind() == SyntaxKind::RightParen)
+        .map(|child| child.offset());
+
+    let mut index = 0;
+    for child in args.children() {
+        match child.cast::<ast::Arg>() {
+            Some(ast::Arg::Pos(expr)) => {
+                let Some((name, _)) = params
+                    .get(index)
+                    .or_else(|| params.last().filter(|(_, variadic)| *variadic))
+                else {
+                    return;
+                };
+                index += 1;
+
+                // Trailing content blocks are not annotated.
+                if close.is_some_and(|close| child.offset() > close) {
+                    continue;
+                }
+
+                // An identifier with the parameter's name speaks for itself.
+                if let ast::Expr::Ident(ident) = expr {
+                    if ident.as_str() == name.as_str() {
+                        continue;
+                    }
+                }
+
+                hints.push(InlayHint {
+                    position: child.offset(),
+                    label: eco_format!("{name}:"),
+                    kind: InlayHintKind::Parameter,
+                    padding_left: false,
+                    padding_right: true,
+                });
+            }
+            // The positions after a spread argument are unknown.
+            Some(ast::Arg::Spread(_)) => return,
+            _ => {}
+        }
+    }
+}
+
+/// Determine the names of the positional parameters of a callee and whether
+/// they are variadic.
+fn positional_params(
+    world: &dyn World,
+    callee: &LinkedNode,
+) -> Option<Vec<(EcoString, bool)>> {
+    if let Some(value) = static_value(world, callee) {
+        let func = match value {
+            Value::Func(func) => func,
+            Value::Type(ty) => ty.constructor().ok()?,
+            _ => return None,
+        };
+
+        let params = func.params()?;
+        return Some(
+            params
+                .iter()
+                .filter(|param| param.positional)
+                .map(|param| (param.name.into(), param.variadic))
+                .collect(),
+        );
+    }
+
+    // A closure defined with `let` in the project.
+    let leaf = match callee.kind() {
+        SyntaxKind::Ident => callee.clone(),
+        _ => return None,
+    };
+
+    let def = ident_definition(world, &leaf)?;
+    let source = world.source(def.id).ok()?;
+    let binding = LinkedNode::new(source.root()).leaf_at(def.range.end)?;
+    let parent = binding.parent()?;
+    let closure = match parent.cast::<ast::Closure>() {
+        Some(closure) => closure,
+        None => match parent.cast::<ast::LetBinding>()?.init()? {
+            ast::Expr::Closure(closure) => closure,
+            _ => return None,
+        },
+    };
+
+    Some(
+        closure
+            .params()
+            .children()
+            .filter_map(|param| match param {
+                ast::Param::Pos(pattern) => {
+                    Some((pattern.to_untyped().clone().into_text(), false))
+                }
+                ast::Param::Spread(spread) => {
+                    let name = spread.sink_ident()?.get().clone();
+                    Some((name, true))
+                }
+                ast::Param::Named(_) => None,
+            })
+            .collect(),
+    )
+}
+
+/// Show the absolute size of a font-relative length.
+fn length_hint(world: &dyn World, node: &LinkedNode) -> Option<InlayHint> {
+    let ast::Expr::Numeric(numeric) = node.cast::<ast::Expr>()? else { return None };
+    let (_, ast::Unit::Em) = numeric.get() else { return None };
+    let Value::Length(length) = Value::numeric(numeric.get()) else { return None };
+
+    let size = inspected_size(world, node, length).or_else(|| static_size(node))?;
+    let resolved = length.abs + length.em.at(size);
+
+    Some(InlayHint {
+        position: node.range().end,
+        label: eco_format!("= {}pt", round_2(resolved.to_pt())),
+        kind: InlayHintKind::Value,
+        padding_left: true,
+        padding_right: false,
+    })
+}
+
+/// Determine the font size for a length from the styles the tracer inspected.
+///
+/// Styles are only known for lengths in contextual expressions and show rules,
+/// so no compilation is attempted for other lengths.
+fn inspected_size(world: &dyn World, node: &LinkedNode, length: Length) -> Option<Abs> {
+    let mut ancestor = node.parent();
+    while let Some(parent) = ancestor {
+        if matches!(parent.kind(), SyntaxKind::Contextual | SyntaxKind::ShowRule) {
+            break;
+        }
+        ancestor = parent.parent();
+    }
+    ancestor?;
+
+    let mut tracer = Tracer::new();
+    tracer.inspect(node.span());
+    typst::compile(world, &mut tracer).ok();
+
+    let sizes: Vec<Abs> = tracer
+        .values()
+        .into_iter()
+        .filter(|(value, _)| *value == Value::Length(length))
+        .filter_map(|(_, styles)| Some(TextElem::size_in(StyleChain::new(&styles?))))
+        .collect();
+
+    // Only show a size if the length resolves to the same size everywhere.
+    let first = *sizes.first()?;
+    sizes.iter().all(|&size| size == first).then_some(first)
+}
+
+/// Determine the font size for a length from the closest preceding
+/// `set text(size: ..)` rule with an absolute size, or use the default size.
+fn static_size(node: &LinkedNode) -> Option<Abs> {
+    let mut node = node.clone();
+    loop {
+        let mut sibling = node.prev_sibling();
+        while let Some(prev) = sibling {
+            if let Some(set) = prev.cast::<ast::SetRule>() {
+                if let Some(size) = set_text_size(set) {
+                    return size;
+                }
+            }
+            sibling = prev.prev_sibling();
+        }
+
+        match node.parent() {
+            Some(parent) => node = parent.clone(),
+            None => return Some(TextElem::size_in(StyleChain::default())),
+        }
+    }
+}
+
+/// The size set by a `set text(size: ..)` rule.
+///
+/// Returns `Some(None)` if the rule sets a size that is not an absolute
+/// length literal.
+fn set_text_size(set: ast::SetRule) -> Option<Option<Abs>> {
+    let ast::Expr::Ident(target) = set.target() else { return None };
+    if target.as_str() != "text" || set.condition().is_some() {
+        return None;
+    }
+
+    let value = set.args().items().find_map(|arg| match arg {
+        ast::Arg::Named(named) if named.name().as_str() == "size" => Some(named.expr()),
+        _ => None,
+    })?;
+
+    Some(match value {
+        ast::Expr::Numeric(numeric) => match Value::numeric(numeric.get()) {
+            Value::Length(length) if length.em.is_zero() => Some(length.abs),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// Show the values of simple `let` bindings at the top level of a file.
+fn value_hints(world: &dyn World, root: &LinkedNode) -> Vec<InlayHint> {
+    let mut hints = vec![];
+
+    // The values of the top-level bindings so far, with `None` for the ones
+    // whose value is unknown.
+    let mut values: HashMap<EcoString, Option<Value>> = HashMap::new();
+
+    // Whether a wildcard import may have shadowed the standard library.
+    let mut opaque = false;
+
+    for child in root.children() {
+        if let Some(import) = child.cast::<ast::ModuleImport>() {
+            match import.imports() {
+                Some(ast::Imports::Wildcard) => opaque = true,
+                Some(ast::Imports::Items(items)) => {
+                    for item in items.iter() {
+                        values.insert(item.bound_name().get().clone(), None);
+                    }
+                }
+                None => {}
+            }
+            if let Some(name) = import.new_name() {
+                values.insert(name.get().clone(), None);
+            }
+            continue;
+        }
+
+        let Some(binding) = child.cast::<ast::LetBinding>() else { continue };
+        let ast::LetBindingKind::Normal(ast::Pattern::Normal(ast::Expr::Ident(ident))) =
+            binding.kind()
+        else {
+            for ident in binding.kind().bindings() {
+                values.insert(ident.get().clone(), None);
+            }
+            continue;
+        };
+
+        let value = binding
+            .init()
+            .filter(|init| {
+                !init.is_literal() && is_simple(&child, *init, &values, opaque)
+            })
+            .and_then(|init| {
+                let mut scope = Scope::new();
+                for (name, value) in &values {
+                    if let Some(value) = value {
+                        scope.define(name.clone(), value.clone());
+                    }
+                }
+
+                let text = init.to_untyped().clone().into_text();
+                eval_string(world.track(), &text, init.span(), EvalMode::Code, scope).ok()
+            });
+
+        if let Some(value) = &value {
+            if !matches!(value, Value::Content(_) | Value::Func(_) | Value::Module(_)) {
+                let mut repr = value.repr();
+                if repr.chars().count() > MAX_VALUE_LEN {
+                    repr = repr.chars().take(MAX_VALUE_LEN - 3).collect();
+                    repr.push_str("...");
+                }
+
+                hints.push(InlayHint {
+                    position: child.range().end,
+                    label: eco_format!("= {repr}"),
+                    kind: InlayHintKind::Value,
+                    padding_left: true,
+                    padding_right: false,
+                });
+            }
+        }
+
+        values.insert(ident.get().clone(), value);
+    }
+
+    hints
+}
+
+/// Whether the initializer of a top-level binding can be evaluated on its own
+/// or from the known values of earlier bindings.
+fn is_simple(
+    binding: &LinkedNode,
+    init: ast::Expr,
+    values: &HashMap<EcoString, Option<Value>>,
+    opaque: bool,
+) -> bool {
+    let Some(init) = binding.find(init.span()) else { return false };
+
+    let mut simple = true;
+    descendants(&init, &mut |node| {
+        simple &= !matches!(
+            node.kind(),
+            SyntaxKind::Closure
+                | SyntaxKind::ForLoop
+                | SyntaxKind::WhileLoop
+                | SyntaxKind::ModuleImport
+                | SyntaxKind::ModuleInclude
+                | SyntaxKind::Contextual
+                | SyntaxKind::ContentBlock
+        );
+
+        if node.kind() == SyntaxKind::Ident && is_scoped(node) {
+            simple &= match values.get(node.text()) {
+                Some(value) => value.is_some(),
+                None => !opaque,
+            };
+        }
+    });
+
+    simple
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::World;
+
+    use super::{inlay_hints, InlayHintKind};
+    use crate::tests::TestWorld;
+
+    /// The positions and labels of all hints of a kind in a text.
+    fn hints(text: &str, kind: InlayHintKind) -> Vec<(usize, String)> {
+        let world = TestWorld::new(text);
+        inlay_hints(&world, &world.main(), 0..text.len())
+            .into_iter()
+            .filter(|hint| hint.kind == kind)
+            .map(|hint| (hint.position, hint.label.into()))
+            .collect()
+    }
+
+    #[track_caller]
+    fn test(text: &str, kind: InlayHintKind, expected: &[(usize, &str)]) {
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|&(position, label)| (position, label.into()))
+            .collect();
+        assert_eq!(hints(text, kind), expected);
+    }
+
+    #[test]
+    fn test_inlay_params() {
+        use InlayHintKind::Parameter;
+        test("#let f(a, b) = a\n#f(1, 2)", Parameter, &[(20, "a:"), (23, "b:")]);
+        test("#let f(a, b) = a\n#let a = 1\n#f(a, 2)", Parameter, &[(34, "b:")]);
+        test("#calc.pow(2, 3)", Parameter, &[(10, "base:"), (13, "exponent:")]);
+        test("#calc.abs(1)", Parameter, &[]);
+    }
+
+    #[test]
+    fn test_inlay_lengths() {
+        use InlayHintKind::Value;
+        test("#let x = 2em", Value, &[(12, "= 22pt")]);
+        test("#set text(size: 10pt)\n#let x = 2em", Value, &[(34, "= 20pt")]);
+    }
+
+    #[test]
+    fn test_inlay_values() {
+        use InlayHintKind::Value;
+        test(
+            "#let a = 1 + 2\n#let b = a * 2\n#let c = f(a)",
+            Value,
+            &[(14, "= 3"), (29, "= 6")],
+        );
+        test("#let b = calc.pow(2, 3)", Value, &[(23, "= 8")]);
+        test("#import \"a.typ\": *\n#let b = calc.pow(2, 3)", Value, &[]);
+    }
+}
@@ -1,14 +1,40 @@
 //! Capabilities for Typst IDE support.
 
+mod actions;
 mod analyze;
 mod complete;
+mod definition;
+mod folding;
+mod inlay;
 mod jump;
+mod rename;
+mod selection;
+mod semantic;
+mod signature;
+mod symbols;
 mod tooltip;
 
+#[cfg(test)]
+mod tests;
+
+pub use self::actions::{code_actions, CodeAction, CodeActionKind};
 pub use self::analyze::analyze_labels;
 pub use self::complete::{autocomplete, Completion, CompletionKind};
+pub use self::definition::{definition, references, Definition};
+pub use self::folding::{folding_ranges, FoldingKind, FoldingRange};
+pub use self::inlay::{inlay_hints, InlayHint, InlayHintKind};
 pub use self::jump::{jump_from_click, jump_from_cursor, Jump};
-pub use self::tooltip::{tooltip, Tooltip};
+pub use self::rename::{rename, Rename};
+pub use self::selection::{selection_range, SelectionRange};
+pub use self::semantic::{
+    semantic_tokens, semantic_tokens_delta, SemanticToken, SemanticTokensEdit,
+    TokenModifier, TokenType,
+};
+pub use self::signature::{signature_help, SignatureHelp, SignatureParam};
+pub use self::symbols::{
+    document_symbols, workspace_symbols, DocumentSymbol, SymbolKind,
+};
+pub use self::tooltip::{tooltip, Preview, Tooltip};
 
 use std::fmt::Write;
 
@@ -0,0 +1,566 @@
+use std::ops::Range;
+
+use ecow::{eco_format, EcoString};
+use if_chain::if_chain;
+use typst::diag::{bail, StrResult};
+use typst::foundations::Label;
+use typst::model::Document;
+use typst::syntax::ast::{self, AstNode};
+use typst::syntax::{parse_code, parse_math, FileId, LinkedNode, Source, SyntaxKind};
+use typst::World;
+
+use crate::definition::{
+    descendants, ident_references, is_scoped, label_name, name_range, named_leaf_at,
+    project_sources, Definition, Resolver,
+};
+
+/// The edits that rename an identifier or label across a project.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Rename {
+    /// The text edits to apply, as files, byte ranges, and replacements.
+    pub edits: Vec<(FileId, Range<usize>, EcoString)>,
+    /// Places that likely refer to a renamed label, but cannot be renamed
+    /// safely, like strings passed to `label`. These are not edited.
+    pub unsafe_refs: Vec<(FileId, Range<usize>)>,
+}
+
+/// Rename the identifier or label under the cursor across the project.
+///
+/// Renaming a binding edits its definition and all identifiers that resolve
+/// to it, including the ones in other files that import it. Identifiers that
+/// refer to it under a different name through an `as` rename are kept. Fails
+/// if the new name is already defined where it would be used or if the
+/// renamed binding would shadow an existing definition of the new name.
+///
+/// Renaming a named parameter of a function also edits the named arguments
+/// of all calls to the function. Fails if the function is used in a way that
+/// doesn't allow finding all of these calls.
+///
+/// Renaming a label edits all labels and references with its name. Passing a
+/// `document` (from a previous compilation) is optional, but allows to detect
+/// conflicts with labels that are not visible in the syntax.
+pub fn rename(
+    world: &dyn World,
+    document: Option<&Document>,
+    source: &Source,
+    cursor: usize,
+    new_name: &str,
+) -> StrResult<Rename> {
+    let Some(leaf) = named_leaf_at(source, cursor) else {
+        bail!("there is nothing to rename here");
+    };
+
+    let sources = project_sources(world, source);
+    match label_name(&leaf) {
+        Some(label) => rename_label(document, &sources, label, new_name),
+        None => rename_binding(world, &sources, &leaf, new_name),
+    }
+}
+
+/// Rename a label and all references to it.
+fn rename_label(
+    document: Option<&Document>,
+    sources: &[Source],
+    old_name: &str,
+    new_name: &str,
+) -> StrResult<Rename> {
+    if new_name.is_empty()
+        || !new_name
+            .chars()
+            .all(|c| typst::syntax::is_id_continue(c) || matches!(c, ':' | '.'))
+    {
+        bail!("`{new_name}` is not a valid label");
+    }
+
+    let mut rename = Rename::default();
+    if old_name == new_name {
+        return Ok(rename);
+    }
+
+    let mut defined = false;
+    let mut referenced = false;
+    let mut exists = document.is_some_and(|document| {
+        let label = Label::new(new_name);
+        document.introspector.all().any(|elem| elem.label() == Some(label))
+    });
+
+    for source in sources {
+        descendants(&LinkedNode::new(source.root()), &mut |node| {
+            match label_name(node) {
+                Some(name) if name == old_name => {
+                    defined |= node.kind() == SyntaxKind::Label;
+                    referenced |= node.kind() == SyntaxKind::RefMarker;
+                    rename.edits.push((source.id(), name_range(node), new_name.into()));
+                }
+                Some(name) if name == new_name => exists = true,
+                _ => {}
+            }
+
+            // A label created from a string: `label("name")`.
+            if_chain! {
+                if let Some(call) = node.cast::<ast::FuncCall>();
+                if let ast::Expr::Ident(callee) = call.callee();
+                if callee.as_str() == "label";
+                if let Some(ast::Arg::Pos(arg)) = call.args().items().next();
+                if let ast::Expr::Str(string) = arg;
+                if string.get() == old_name;
+                if let Some(string) = node.find(string.span());
+                then {
+                    rename.unsafe_refs.push((source.id(), string.range()));
+                }
+            }
+        });
+    }
+
+    if !defined {
+        bail!("cannot rename `{old_name}` because it is not a label in the project");
+    }
+
+    if exists {
+        bail!("cannot rename `{old_name}` because the label `{new_name}` already exists");
+    }
+
+    if referenced && new_name.ends_with(['.', ':']) {
+        bail!("`{new_name}` cannot be referenced because it ends with punctuation");
+    }
+
+    Ok(rename)
+}
+
+/// Rename a binding and all identifiers that resolve to it.
+fn rename_binding(
+    world: &dyn World,
+    sources: &[Source],
+    leaf: &LinkedNode,
+    new_name: &str,
+) -> StrResult<Rename> {
+    let resolver = Resolver::new(world);
+    let old_name = leaf.text().clone();
+    let Some(target) = resolver.ident(leaf) else {
+        bail!("cannot rename `{old_name}` because it is not defined in the project");
+    };
+
+    if target.id.package().is_some() {
+        bail!("cannot rename `{old_name}` because it is defined in a package");
+    }
+
+    if !is_code_ident(new_name) {
+        bail!("`{new_name}` is not a valid identifier");
+    }
+
+    let mut rename = Rename::default();
+    if old_name.as_str() == new_name {
+        return Ok(rename);
+    }
+
+    // Only rename the identifiers that use the same name as the one under the
+    // cursor. The others are renamed through an `as` import.
+    let sites: Vec<_> = ident_references(&resolver, sources, &target)
+        .into_iter()
+        .filter(|(id, range)| {
+            !range.is_empty()
+                && sources.iter().any(|source| {
+                    source.id() == *id
+                        && source.get(range.clone()) == Some(old_name.as_str())
+                })
+        })
+        .collect();
+
+    // A module is bound under the name of its file, unless it is imported
+    // with `as`.
+    if target.range.is_empty() {
+        let renamed = sites.iter().any(|(id, range)| {
+            with_node(sources, *id, range, |node| {
+                node.parent_kind() == Some(SyntaxKind::ModuleImport)
+            })
+        });
+
+        if !renamed {
+            bail!("cannot rename module `{old_name}` because it is named after its file");
+        }
+    }
+
+    for (id, range) in &sites {
+        let conflict = with_node(sources, *id, range, |node| {
+            if node.kind() == SyntaxKind::MathIdent && !is_math_ident(new_name) {
+                return Some(eco_format!("`{new_name}` cannot be used in math"));
+            }
+
+            if !is_scoped(node) {
+                return None;
+            }
+
+            resolver.scope(node, new_name).map(|_| {
+                eco_format!("`{new_name}` is already defined where `{old_name}` is used")
+            })
+        });
+
+        if let Some(message) = conflict {
+            bail!("cannot rename `{old_name}`: {message}");
+        }
+
+        rename.edits.push((*id, range.clone(), new_name.into()));
+    }
+
+    // Callers pass named parameters by name.
+    if let Some(function) = named_param_function(&resolver, sources, &target, new_name)? {
+        let args = named_args(&resolver, sources, &function, &old_name, new_name)?;
+        rename
+            .edits
+            .extend(args.into_iter().map(|(id, range)| (id, range, new_name.into())));
+    }
+
+    // Existing uses of the new name must not be captured by the renamed
+    // binding.
+    for source in sources {
+        let mut captured = false;
+        descendants(&LinkedNode::new(source.root()), &mut |node| {
+            if captured
+                || !matches!(node.kind(), SyntaxKind::Ident | SyntaxKind::MathIdent)
+                || node.text().as_str() != new_name
+                || !is_scoped(node)
+            {
+                return;
+            }
+
+            if resolver.scope(node, &old_name).as_ref() != Some(&target) {
+                return;
+            }
+
+            // A definition that follows the renamed one in the same file
+            // still shadows it.
+            captured =
+                !resolver.ident(node).is_some_and(|current| shadows(&current, &target));
+        });
+
+        if captured {
+            bail!("cannot rename `{old_name}` because it would shadow `{new_name}`");
+        }
+    }
+
+    Ok(rename)
+}
+
+/// If the definition is a named parameter of a closure, find the definition
+/// of the function the closure is bound to.
+///
+/// Fails if the closure isn't bound to a name, since its calls can't be found
+/// then, or if it already has a parameter with the new name.
+fn named_param_function(
+    resolver: &Resolver,
+    sources: &[Source],
+    target: &Definition,
+    new_name: &str,
+) -> StrResult<Option<Definition>> {
+    let found = with_node(sources, target.id, &target.range, |node| {
+        let named = node.parent().filter(|parent| {
+            parent.kind() == SyntaxKind::Named
+                && parent.parent_kind() == Some(SyntaxKind::Params)
+                && node.index() == 0
+        })?;
+
+        let closure = named.parent()?.parent()?;
+        let params = closure.cast::<ast::Closure>()?.params();
+        let duplicate = params.children().any(|param| match param {
+            ast::Param::Pos(pattern) => {
+                pattern.bindings().iter().any(|ident| ident.as_str() == new_name)
+            }
+            ast::Param::Named(named) => named.name().as_str() == new_name,
+            ast::Param::Spread(spread) => {
+                spread.sink_ident().is_some_and(|ident| ident.as_str() == new_name)
+            }
+        });
+
+        Some((duplicate, closure_binding(resolver, closure)))
+    });
+
+    match found {
+        None => Ok(None),
+        Some((true, _)) => {
+            bail!(
+                "cannot rename `{}`: the function already has a parameter `{new_name}`",
+                target.name
+            )
+        }
+        Some((false, None)) => bail!(
+            "cannot rename `{}` because it is a named parameter of an unnamed function",
+            target.name
+        ),
+        Some((false, Some(function))) => Ok(Some(function)),
+    }
+}
+
+/// The definition of the name a closure is bound to, either through a
+/// function definition like `let f(x) = ..` or a binding like `let f = x => ..`.
+fn closure_binding(resolver: &Resolver, closure: &LinkedNode) -> Option<Definition> {
+    let name = match closure.cast::<ast::Closure>()?.name() {
+        Some(name) => name,
+        None => {
+            let binding = closure.parent()?.cast::<ast::LetBinding>()?;
+            if binding.init()?.span() != closure.span() {
+                return None;
+            }
+            match binding.kind() {
+                ast::LetBindingKind::Normal(ast::Pattern::Normal(ast::Expr::Ident(
+                    name,
+                ))) => name,
+                _ => return None,
+            }
+        }
+    };
+
+    let node = closure.parent()?.find(name.span())?;
+    resolver.ident(&node)
+}
+
+/// Find the names of all named arguments `old_name` in calls to a function.
+///
+/// Fails if the function is used other than by calling it directly, since its
+/// arguments can't be tracked then.
+fn named_args(
+    resolver: &Resolver,
+    sources: &[Source],
+    function: &Definition,
+    old_name: &str,
+    new_name: &str,
+) -> StrResult<Vec<(FileId, Range<usize>)>> {
+    let mut output = vec![];
+    for (id, range) in ident_references(resolver, sources, function) {
+        if id == function.id && range == function.range {
+            continue;
+        }
+
+        let result = with_node(sources, id, &range, |node| {
+            Some(call_args(node, function, old_name, new_name))
+        });
+
+        if let Some(result) = result {
+            output.extend(result?.into_iter().map(|range| (id, range)));
+        }
+    }
+
+    Ok(output)
+}
+
+/// Find the names of the named arguments `old_name` in the call whose callee
+/// is the given identifier.
+fn call_args(
+    node: &LinkedNode,
+    function: &Definition,
+    old_name: &str,
+    new_name: &str,
+) -> StrResult<Vec<Range<usize>>> {
+    let Some(call) = direct_call(node) else {
+        // Imports merely bind the function.
+        return match node.parent_kind() {
+            Some(SyntaxKind::ImportItems | SyntaxKind::RenamedImportItem) => Ok(vec![]),
+            _ => Err(eco_format!(
+                "cannot rename `{old_name}` because `{}` is used other than \
+                 by calling it",
+                function.name
+            )),
+        };
+    };
+
+    let mut ranges = vec![];
+    for arg in call.cast::<ast::FuncCall>().unwrap().args().items() {
+        match arg {
+            ast::Arg::Named(named) if named.name().as_str() == old_name => {
+                ranges.extend(call.find(named.name().span()).map(|n| n.range()));
+            }
+            ast::Arg::Named(named) if named.name().as_str() == new_name => {
+                bail!(
+                    "cannot rename `{old_name}` because a call to `{}` already \
+                     has an argument `{new_name}`",
+                    function.name
+                );
+            }
+            ast::Arg::Spread(_) => bail!(
+                "cannot rename `{old_name}` because a call to `{}` spreads its \
+                 arguments",
+                function.name
+            ),
+            _ => {}
+        }
+    }
+
+    Ok(ranges)
+}
+
+/// The call in which an identifier is the callee, either directly or as the
+/// field of a module.
+fn direct_call<'a>(node: &LinkedNode<'a>) -> Option<LinkedNode<'a>> {
+    let callee = match node.parent() {
+        Some(parent) if parent.kind() == SyntaxKind::FieldAccess && node.index() > 0 => {
+            parent.clone()
+        }
+        _ => node.clone(),
+    };
+
+    let call = callee.parent()?;
+    (call.kind() == SyntaxKind::FuncCall && callee.index() == 0).then(|| call.clone())
+}
+
+/// Whether a definition takes precedence over another one at a place where
+/// both are in scope.
+fn shadows(def: &Definition, other: &Definition) -> bool {
+    def.id == other.id && def.range.start > other.range.start
+}
+
+/// Call `f` with the identifier at the given range.
+fn with_node<T: Default>(
+    sources: &[Source],
+    id: FileId,
+    range: &Range<usize>,
+    f: impl FnOnce(&LinkedNode) -> T,
+) -> T {
+    sources
+        .iter()
+        .find(|source| source.id() == id)
+        .and_then(|source| {
+            let node = LinkedNode::new(source.root()).leaf_at(range.end)?;
+            Some(f(&node))
+        })
+        .unwrap_or_default()
+}
+
+/// Whether a string is an identifier in code that is not a keyword.
+fn is_code_ident(string: &str) -> bool {
+    let root = parse_code(string);
+    let mut children = root.children();
+    matches!(
+        (children.next(), children.next()),
+        (Some(child), None) if child.kind() == SyntaxKind::Ident
+    ) && root.len() == string.len()
+}
+
+/// Whether a string can be used as an identifier in math.
+fn is_math_ident(string: &str) -> bool {
+    let root = parse_math(string);
+    let mut children = root.children();
+    matches!(
+        (children.next(), children.next()),
+        (Some(child), None) if child.kind() == SyntaxKind::MathIdent
+    ) && root.len() == string.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use typst::World;
+
+    use super::rename;
+    use crate::tests::{id, TestWorld};
+
+    #[track_caller]
+    fn test(
+        world: &TestWorld,
+        cursor: usize,
+        new_name: &str,
+        expected: &[(&str, Range<usize>)],
+    ) {
+        let rename = rename(world, None, &world.main(), cursor, new_name).unwrap();
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(path, range)| (id(path), range.clone(), new_name.into()))
+            .collect();
+        assert_eq!(rename.edits, expected);
+    }
+
+    #[track_caller]
+    fn test_error(world: &TestWorld, cursor: usize, new_name: &str, expected: &str) {
+        let error = rename(world, None, &world.main(), cursor, new_name).unwrap_err();
+        assert_eq!(error.as_str(), expected);
+    }
+
+    #[test]
+    fn test_rename_binding() {
+        let world = TestWorld::new("#let x = 1\n#(x + x)");
+        test(
+            &world,
+            5,
+            "y",
+            &[("main.typ", 5..6), ("main.typ", 13..14), ("main.typ", 17..18)],
+        );
+        test(&world, 5, "x", &[]);
+        test_error(&world, 5, "1x", "`1x` is not a valid identifier");
+        test_error(&world, 0, "y", "there is nothing to rename here");
+    }
+
+    #[test]
+    fn test_rename_binding_conflict() {
+        let world = TestWorld::new("#let x = 1\n#let y = 2\n#x");
+        test_error(
+            &world,
+            5,
+            "y",
+            "cannot rename `x`: `y` is already defined where `x` is used",
+        );
+    }
+
+    #[test]
+    fn test_rename_binding_import() {
+        let world =
+            TestWorld::new("#import \"a.typ\": y\n#y").with_source("a.typ", "#let y = 1");
+        test(
+            &world,
+            20,
+            "z",
+            &[("main.typ", 17..18), ("main.typ", 20..21), ("a.typ", 5..6)],
+        );
+    }
+
+    #[test]
+    fn test_rename_named_param() {
+        let world = TestWorld::new("#let f(a: 1) = a\n#f(a: 2)");
+        test(
+            &world,
+            7,
+            "b",
+            &[("main.typ", 7..8), ("main.typ", 15..16), ("main.typ", 20..21)],
+        );
+
+        let world = TestWorld::new("#let f = (a: 1) => a\n#f(a: 2)");
+        test(
+            &world,
+            10,
+            "b",
+            &[("main.typ", 10..11), ("main.typ", 19..20), ("main.typ", 24..25)],
+        );
+    }
+
+    #[test]
+    fn test_rename_named_param_untracked() {
+        let world = TestWorld::new("#let f(a: 1) = a\n#let g = f");
+        test_error(
+            &world,
+            7,
+            "b",
+            "cannot rename `a` because `f` is used other than by calling it",
+        );
+
+        let world = TestWorld::new("#let f(a: 1) = a\n#f(..args)");
+        test_error(
+            &world,
+            7,
+            "b",
+            "cannot rename `a` because a call to `f` spreads its arguments",
+        );
+
+        let world = TestWorld::new("#let f = ((a: 1) => a)");
+        test_error(
+            &world,
+            11,
+            "b",
+            "cannot rename `a` because it is a named parameter of an unnamed function",
+        );
+    }
+
+    #[test]
+    fn test_rename_label() {
+        let world = TestWorld::new("= Intro <intro>\n@intro");
+        test(&world, 9, "start", &[("main.typ", 9..14), ("main.typ", 17..22)]);
+        test_error(&world, 9, "a b", "`a b` is not a valid label");
+    }
+}
@@ -0,0 +1,112 @@
+use std::ops::Range;
+
+use typst::syntax::{LinkedNode, Source, SyntaxKind};
+
+/// A range to select around a cursor, along with the next larger one.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct SelectionRange {
+    /// The byte range to select.
+    pub range: Range<usize>,
+    /// The next larger range that contains this one.
+    pub parent: Option<Box<SelectionRange>>,
+}
+
+/// Compute the ranges that "expand selection" steps through when starting at
+/// the cursor.
+///
+/// The innermost range covers the syntax leaf at the cursor. Each parent
+/// covers the next larger syntax node. For delimited nodes like blocks,
+/// argument lists and strings, the contents between the delimiters are
+/// selected before the whole node.
+pub fn selection_range(source: &Source, cursor: usize) -> Option<SelectionRange> {
+    let root = LinkedNode::new(source.root());
+    let mut leaf = root.leaf_at(cursor)?;
+
+    // Prefer a leaf that starts at the cursor over trivia that ends there.
+    if leaf.kind().is_trivia() && leaf.range().end == cursor {
+        if let Some(next) = leaf.next_leaf().filter(|next| next.offset() == cursor) {
+            leaf = next;
+        }
+    }
+
+    let mut ranges: Vec<Range<usize>> = vec![];
+    let mut node = Some(&leaf);
+    while let Some(current) = node {
+        if let Some(inner) = inner_range(current) {
+            push(&mut ranges, inner);
+        }
+        push(&mut ranges, current.range());
+        node = current.parent();
+    }
+
+    ranges.into_iter().rev().fold(None, |parent, range| {
+        Some(SelectionRange { range, parent: parent.map(Box::new) })
+    })
+}
+
+/// Add a range unless it does not grow the selection.
+fn push(ranges: &mut Vec<Range<usize>>, range: Range<usize>) {
+    let grows = ranges.last().map_or(true, |last| {
+        range.start <= last.start && last.end <= range.end && range != *last
+    });
+    if grows {
+        ranges.push(range);
+    }
+}
+
+/// The range between the delimiters of a node, if it is delimited.
+fn inner_range(node: &LinkedNode) -> Option<Range<usize>> {
+    if node.kind() == SyntaxKind::Str {
+        let range = node.range();
+        let end = if node.text().len() > 1 && node.text().ends_with('"') {
+            range.end - 1
+        } else {
+            range.end
+        };
+        return Some(range.start + 1..end);
+    }
+
+    let first = node.children().next()?;
+    let last = node.children().last()?;
+    let delimited = matches!(
+        (first.kind(), last.kind()),
+        (SyntaxKind::LeftBrace, SyntaxKind::RightBrace)
+            | (SyntaxKind::LeftBracket, SyntaxKind::RightBracket)
+            | (SyntaxKind::LeftParen, SyntaxKind::RightParen)
+            | (SyntaxKind::Dollar, SyntaxKind::Dollar)
+    );
+
+    (delimited && first.range() != last.range()).then(|| first.range().end..last.offset())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use typst::syntax::Source;
+
+    use super::selection_range;
+
+    /// The ranges of a selection, from the innermost to the outermost one.
+    #[track_caller]
+    fn test(text: &str, cursor: usize, expected: &[Range<usize>]) {
+        let source = Source::detached(text);
+        let mut ranges = vec![];
+        let mut selection = selection_range(&source, cursor);
+        while let Some(current) = selection {
+            ranges.push(current.range);
+            selection = current.parent.map(|parent| *parent);
+        }
+        assert_eq!(ranges, expected);
+    }
+
+    #[test]
+    fn test_selection_delimited() {
+        test("#f(a, \"bc\")", 8, &[7..9, 6..10, 3..10, 2..11, 1..11, 0..11]);
+    }
+
+    #[test]
+    fn test_selection_at_boundary() {
+        test("#f(a, b)", 6, &[6..7, 3..7, 2..8, 1..8, 0..8]);
+    }
+}
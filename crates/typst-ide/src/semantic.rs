@@ -0,0 +1,617 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use typst::foundations::Value;
+use typst::syntax::ast::{self, AstNode};
+use typst::syntax::{highlight, LinkedNode, Source, SyntaxKind, SyntaxNode, Tag};
+use typst::World;
+
+use crate::analyze::analyze_expr;
+use crate::definition::{ident_definition, is_scoped, scope_definition, Definition};
+
+/// The type of a semantic token.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum TokenType {
+    /// A line or block comment.
+    Comment,
+    /// Punctuation in code.
+    Punctuation,
+    /// An escape sequence or shorthand.
+    Escape,
+    /// Strong markup.
+    Strong,
+    /// Emphasized markup.
+    Emph,
+    /// A hyperlink.
+    Link,
+    /// Raw text.
+    Raw,
+    /// A label.
+    Label,
+    /// A reference to a label.
+    Ref,
+    /// A section heading.
+    Heading,
+    /// A marker of a list, enumeration, or term list.
+    ListMarker,
+    /// A term in a term list.
+    ListTerm,
+    /// The delimiters of an equation.
+    MathDelimiter,
+    /// An operator with special meaning in an equation.
+    MathOperator,
+    /// A keyword.
+    Keyword,
+    /// An operator in code.
+    Operator,
+    /// A numeric literal.
+    Number,
+    /// A string literal.
+    String,
+    /// A function that is not an element function.
+    Function,
+    /// An element function.
+    Element,
+    /// A type.
+    Type,
+    /// A module.
+    Module,
+    /// A parameter of a closure.
+    Parameter,
+    /// A variable defined in the source.
+    Variable,
+    /// A value from the standard library that is not a function, type, or
+    /// module, like a symbol.
+    Constant,
+    /// An interpolated value that could not be classified further.
+    Interpolated,
+    /// A syntax error.
+    Error,
+}
+
+impl TokenType {
+    /// The list of all token types, in the same order as they are defined.
+    ///
+    /// Can be used as the legend for the `token_type` of a [`SemanticToken`].
+    pub const LIST: &'static [Self] = &[
+        Self::Comment,
+        Self::Punctuation,
+        Self::Escape,
+        Self::Strong,
+        Self::Emph,
+        Self::Link,
+        Self::Raw,
+        Self::Label,
+        Self::Ref,
+        Self::Heading,
+        Self::ListMarker,
+        Self::ListTerm,
+        Self::MathDelimiter,
+        Self::MathOperator,
+        Self::Keyword,
+        Self::Operator,
+        Self::Number,
+        Self::String,
+        Self::Function,
+        Self::Element,
+        Self::Type,
+        Self::Module,
+        Self::Parameter,
+        Self::Variable,
+        Self::Constant,
+        Self::Interpolated,
+        Self::Error,
+    ];
+
+    /// The name of the token type in a semantic token legend.
+    ///
+    /// Uses the standard LSP names where one fits.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Comment => "comment",
+            Self::Punctuation => "punctuation",
+            Self::Escape => "escape",
+            Self::Strong => "strong",
+            Self::Emph => "emph",
+            Self::Link => "link",
+            Self::Raw => "raw",
+            Self::Label => "label",
+            Self::Ref => "ref",
+            Self::Heading => "heading",
+            Self::ListMarker => "marker",
+            Self::ListTerm => "term",
+            Self::MathDelimiter => "delim",
+            Self::MathOperator => "mathOperator",
+            Self::Keyword => "keyword",
+            Self::Operator => "operator",
+            Self::Number => "number",
+            Self::String => "string",
+            Self::Function => "function",
+            Self::Element => "element",
+            Self::Type => "type",
+            Self::Module => "namespace",
+            Self::Parameter => "parameter",
+            Self::Variable => "variable",
+            Self::Constant => "constant",
+            Self::Interpolated => "interpolated",
+            Self::Error => "error",
+        }
+    }
+
+    /// The token type for a syntax highlighting tag.
+    fn from_tag(tag: Tag) -> Self {
+        match tag {
+            Tag::Comment => Self::Comment,
+            Tag::Punctuation => Self::Punctuation,
+            Tag::Escape => Self::Escape,
+            Tag::Strong => Self::Strong,
+            Tag::Emph => Self::Emph,
+            Tag::Link => Self::Link,
+            Tag::Raw => Self::Raw,
+            Tag::Label => Self::Label,
+            Tag::Ref => Self::Ref,
+            Tag::Heading => Self::Heading,
+            Tag::ListMarker => Self::ListMarker,
+            Tag::ListTerm => Self::ListTerm,
+            Tag::MathDelimiter => Self::MathDelimiter,
+            Tag::MathOperator => Self::MathOperator,
+            Tag::Keyword => Self::Keyword,
+            Tag::Operator => Self::Operator,
+            Tag::Number => Self::Number,
+            Tag::String => Self::String,
+            Tag::Function => Self::Function,
+            Tag::Interpolated => Self::Interpolated,
+            Tag::Error => Self::Error,
+        }
+    }
+
+    /// The token type for a value.
+    fn from_value(value: &Value, fallback: Self) -> Self {
+        match value {
+            Value::Func(func) if func.element().is_some() => Self::Element,
+            Value::Func(_) => Self::Function,
+            Value::Type(_) => Self::Type,
+            Value::Module(_) => Self::Module,
+            _ => fallback,
+        }
+    }
+}
+
+/// A modifier of a semantic token.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum TokenModifier {
+    /// The token is where the name is defined.
+    Declaration,
+    /// The token refers to a definition from the standard library.
+    DefaultLibrary,
+}
+
+impl TokenModifier {
+    /// The list of all token modifiers, in the same order as they are defined.
+    ///
+    /// The modifier at index `i` corresponds to bit `1 << i` in the
+    /// `modifiers` of a [`SemanticToken`].
+    pub const LIST: &'static [Self] = &[Self::Declaration, Self::DefaultLibrary];
+
+    /// The name of the modifier in a semantic token legend.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Declaration => "declaration",
+            Self::DefaultLibrary => "defaultLibrary",
+        }
+    }
+
+    /// The modifier's bit.
+    fn bit(self) -> u32 {
+        1 << (self as u32)
+    }
+}
+
+/// A semantic token, relative to the previous one as in the LSP.
+///
+/// Positions and lengths are measured in UTF-16 code units. Tokens never span
+/// multiple lines.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct SemanticToken {
+    /// The line relative to the previous token's line.
+    pub delta_line: u32,
+    /// The start column relative to the previous token's start column if on
+    /// the same line and relative to the line start otherwise.
+    pub delta_start: u32,
+    /// The length of the token.
+    pub length: u32,
+    /// The index of the token's type in [`TokenType::LIST`].
+    pub token_type: u32,
+    /// The token's modifiers as a bit set over [`TokenModifier::LIST`].
+    pub modifiers: u32,
+}
+
+/// An edit that turns one set of semantic tokens into another, as in the LSP.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct SemanticTokensEdit {
+    /// Where to start deleting, counted in integers (five per token).
+    pub start: u32,
+    /// How many integers to delete.
+    pub delete_count: u32,
+    /// The tokens to insert.
+    pub data: Vec<SemanticToken>,
+}
+
+/// Classify the tokens of a source file for semantic highlighting.
+///
+/// Combines the syntactic [highlighting](highlight) with an analysis of what
+/// identifiers refer to, so that element functions, other functions, types,
+/// modules, parameters, variables, and constants can be told apart.
+pub fn semantic_tokens(world: &dyn World, source: &Source) -> Vec<SemanticToken> {
+    let mut ctx = SemanticContext {
+        world,
+        source,
+        cache: HashMap::new(),
+        tokens: vec![],
+        last: (0, 0),
+    };
+
+    ctx.visit(&LinkedNode::new(source.root()), None);
+    ctx.tokens
+}
+
+/// Compute the edit that turns the `old` tokens into the `new` ones.
+///
+/// Returns no edit if the tokens are equal.
+pub fn semantic_tokens_delta(
+    old: &[SemanticToken],
+    new: &[SemanticToken],
+) -> Vec<SemanticTokensEdit> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let deleted = old.len() - prefix - suffix;
+    let inserted = &new[prefix..new.len() - suffix];
+    if deleted == 0 && inserted.is_empty() {
+        return vec![];
+    }
+
+    vec![SemanticTokensEdit {
+        start: 5 * prefix as u32,
+        delete_count: 5 * deleted as u32,
+        data: inserted.to_vec(),
+    }]
+}
+
+/// The state of the semantic token classification.
+struct SemanticContext<'a> {
+    world: &'a dyn World,
+    source: &'a Source,
+    cache: HashMap<Definition, TokenType>,
+    tokens: Vec<SemanticToken>,
+    last: (usize, usize),
+}
+
+impl SemanticContext<'_> {
+    /// Emit tokens for a node and its descendants, inheriting the token type
+    /// of enclosing markup like strong text or headings.
+    fn visit(&mut self, node: &LinkedNode, inherited: Option<TokenType>) {
+        if node.children().len() > 0 {
+            let inherited = highlight(node).map(TokenType::from_tag).or(inherited);
+            for child in node.children() {
+                self.visit(&child, inherited);
+            }
+            return;
+        }
+
+        if matches!(node.kind(), SyntaxKind::Space | SyntaxKind::Parbreak) {
+            return;
+        }
+
+        let classified = match node.kind() {
+            SyntaxKind::Ident | SyntaxKind::MathIdent => self.classify_ident(node),
+            SyntaxKind::Hash => node
+                .next_sibling()
+                .and_then(|next| next.leftmost_leaf())
+                .filter(|leaf| matches!(leaf.kind(), SyntaxKind::Ident))
+                .and_then(|leaf| self.classify_ident(&leaf))
+                .map(|(ty, _)| (ty, 0)),
+            _ => None,
+        };
+
+        let Some((ty, modifiers)) = classified
+            .or_else(|| Some((TokenType::from_tag(highlight(node)?), 0)))
+            .or_else(|| Some((inherited?, 0)))
+        else {
+            return;
+        };
+
+        self.push(node.range(), ty, modifiers);
+    }
+
+    /// Classify an identifier by what it refers to.
+    fn classify_ident(&mut self, leaf: &LinkedNode) -> Option<(TokenType, u32)> {
+        let default = TokenModifier::DefaultLibrary.bit();
+        if !is_scoped(leaf) {
+            // Fields are resolved statically where possible, since analyzing
+            // them requires a compilation.
+            if leaf.parent_kind() != Some(SyntaxKind::FieldAccess) {
+                return None;
+            }
+
+            let value = static_value(self.world, leaf.parent()?)?;
+            return Some((TokenType::from_value(&value, TokenType::Constant), default));
+        }
+
+        let Some(def) = ident_definition(self.world, leaf) else {
+            let value = static_value(self.world, leaf)?;
+            return Some((TokenType::from_value(&value, TokenType::Constant), default));
+        };
+
+        let modifiers = if def.id == self.source.id() && def.range == leaf.range() {
+            TokenModifier::Declaration.bit()
+        } else {
+            0
+        };
+
+        Some((self.classify_definition(&def), modifiers))
+    }
+
+    /// Classify a definition by its syntax, falling back to an analysis of the
+    /// value it is bound to.
+    fn classify_definition(&mut self, def: &Definition) -> TokenType {
+        if let Some(&ty) = self.cache.get(def) {
+            return ty;
+        }
+
+        // Prevent cycles through aliases.
+        self.cache.insert(def.clone(), TokenType::Variable);
+
+        let ty = if def.range.is_empty() {
+            TokenType::Module
+        } else {
+            self.classify_binding(def).unwrap_or(TokenType::Variable)
+        };
+
+        self.cache.insert(def.clone(), ty);
+        ty
+    }
+
+    /// Classify the binding at a definition's site.
+    fn classify_binding(&mut self, def: &Definition) -> Option<TokenType> {
+        let source = if def.id == self.source.id() {
+            self.source.clone()
+        } else {
+            self.world.source(def.id).ok()?
+        };
+
+        let leaf = LinkedNode::new(source.root()).leaf_at(def.range.end)?;
+        let mut node = leaf.parent()?;
+        loop {
+            match node.kind() {
+                SyntaxKind::Params => return Some(TokenType::Parameter),
+                SyntaxKind::ForLoop | SyntaxKind::Destructuring => {
+                    return Some(TokenType::Variable)
+                }
+                SyntaxKind::LetBinding => break,
+                _ => node = node.parent()?,
+            }
+        }
+
+        let binding = node.cast::<ast::LetBinding>()?;
+        match binding.init()? {
+            ast::Expr::Closure(_) => Some(TokenType::Function),
+            ast::Expr::FuncCall(call) => match call.callee() {
+                ast::Expr::FieldAccess(access) if access.field().as_str() == "with" => {
+                    Some(TokenType::Function)
+                }
+                _ => self.classify_expr(node, call.to_untyped()),
+            },
+            ast::Expr::Ident(_) | ast::Expr::FieldAccess(_) => {
+                // An alias for another definition: `let fig = figure`.
+                let init = binding.init()?;
+                let init = node.find(init.span())?.rightmost_leaf()?;
+                self.classify_ident(&init).map(|(ty, _)| ty)
+            }
+            expr if expr.is_literal() => Some(TokenType::Variable),
+            expr => self.classify_expr(node, expr.to_untyped()),
+        }
+    }
+
+    /// Classify an expression by the values it evaluated to.
+    fn classify_expr(
+        &mut self,
+        node: &LinkedNode,
+        expr: &SyntaxNode,
+    ) -> Option<TokenType> {
+        let expr = node.find(expr.span())?;
+        let values = analyze_expr(self.world, &expr);
+        let mut types = values
+            .iter()
+            .map(|(value, _)| TokenType::from_value(value, TokenType::Variable));
+        let first = types.next()?;
+        types.all(|ty| ty == first).then_some(first)
+    }
+
+    /// Emit a token, split into one token per line.
+    fn push(&mut self, range: Range<usize>, ty: TokenType, modifiers: u32) {
+        let token_type = TokenType::LIST.iter().position(|&t| t == ty).unwrap() as u32;
+        let mut start = range.start;
+        while start < range.end {
+            let Some(line) = self.source.byte_to_line(start) else { return };
+            let Some(line_range) = self.source.line_to_range(line) else { return };
+            let end = range.end.min(line_range.end);
+
+            let text = self.source.get(start..end).unwrap_or_default();
+            let trimmed = text.trim_end_matches(['\n', '\r']);
+            if !trimmed.is_empty() {
+                let utf16 = |byte| self.source.byte_to_utf16(byte).unwrap_or_default();
+                let column = utf16(start) - utf16(line_range.start);
+                let length = utf16(start + trimmed.len()) - utf16(start);
+
+                let (last_line, last_column) = self.last;
+                let delta_start =
+                    if line == last_line { column - last_column } else { column };
+
+                self.tokens.push(SemanticToken {
+                    delta_line: (line - last_line) as u32,
+                    delta_start: delta_start as u32,
+                    length: length as u32,
+                    token_type,
+                    modifiers,
+                });
+
+                self.last = (line, column);
+            }
+
+            start = end;
+        }
+    }
+}
+
+/// Resolve an identifier or field access that refers to the standard library
+/// without a compilation.
+pub(crate) fn static_value(world: &dyn World, node: &LinkedNode) -> Option<Value> {
+    match node.cast::<ast::Expr>()? {
+        ast::Expr::Ident(ident) => {
+            if scope_definition(world, node, &ident).is_some() {
+                return None;
+            }
+            world.library().global.scope().get(&ident).cloned()
+        }
+        ast::Expr::MathIdent(ident) => {
+            if scope_definition(world, node, &ident).is_some() {
+                return None;
+            }
+            world
+                .library()
+                .math
+                .scope()
+                .get(&ident)
+                .or_else(|| world.library().global.scope().get(&ident))
+                .cloned()
+        }
+        ast::Expr::FieldAccess(access) => {
+            let target = static_value(world, &node.children().next()?)?;
+            let field = access.field();
+            match target {
+                Value::Module(module) => module.field(&field).ok().cloned(),
+                Value::Func(func) => func.field(&field).ok().cloned(),
+                Value::Type(ty) => ty.field(&field).ok().cloned(),
+                Value::Symbol(symbol) => symbol.modified(&field).ok().map(Value::Symbol),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::World;
+
+    use super::{
+        semantic_tokens, semantic_tokens_delta, SemanticToken, SemanticTokensEdit,
+        TokenType,
+    };
+    use crate::tests::TestWorld;
+
+    /// Classify the tokens of a text, returning the text of each token with its
+    /// type and modifiers.
+    fn tokens(text: &str) -> Vec<(String, TokenType, u32)> {
+        let world = TestWorld::new(text);
+        let source = world.main();
+        let (mut line, mut column) = (0, 0);
+        semantic_tokens(&world, &source)
+            .into_iter()
+            .map(|token| {
+                if token.delta_line > 0 {
+                    column = 0;
+                }
+                line += token.delta_line as usize;
+                column += token.delta_start as usize;
+                let start = source.line_to_byte(line).unwrap() + column;
+                let text = source.get(start..start + token.length as usize).unwrap();
+                let ty = TokenType::LIST[token.token_type as usize];
+                (text.into(), ty, token.modifiers)
+            })
+            .collect()
+    }
+
+    /// A token of the given length right after the previous one.
+    fn token(length: u32) -> SemanticToken {
+        SemanticToken {
+            delta_line: 0,
+            delta_start: 1,
+            length,
+            token_type: 0,
+            modifiers: 0,
+        }
+    }
+
+    #[test]
+    fn test_semantic_idents() {
+        let found: Vec<_> = tokens("#let f(x) = x\n#f(rect)")
+            .into_iter()
+            .filter(|(_, ty, _)| {
+                matches!(
+                    ty,
+                    TokenType::Function
+                        | TokenType::Element
+                        | TokenType::Parameter
+                        | TokenType::Variable
+                )
+            })
+            .collect();
+
+        let expected = [
+            ("f", TokenType::Function, 1),
+            ("x", TokenType::Parameter, 1),
+            ("x", TokenType::Parameter, 0),
+            ("#", TokenType::Function, 0),
+            ("f", TokenType::Function, 0),
+            ("rect", TokenType::Element, 2),
+        ];
+
+        let expected: Vec<_> = expected
+            .into_iter()
+            .map(|(text, ty, m)| (text.into(), ty, m))
+            .collect();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn test_semantic_library() {
+        let found = tokens("#let v = 1\n#v $pi$ #calc.pow");
+        assert!(found.contains(&("v".into(), TokenType::Variable, 0)));
+        assert!(found.contains(&("pi".into(), TokenType::Constant, 2)));
+        assert!(found.contains(&("calc".into(), TokenType::Module, 2)));
+        assert!(found.contains(&("pow".into(), TokenType::Function, 2)));
+    }
+
+    #[test]
+    fn test_semantic_multiline() {
+        assert_eq!(
+            tokens("/* a\nb */"),
+            [
+                ("/* a".into(), TokenType::Comment, 0),
+                ("b */".into(), TokenType::Comment, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_semantic_delta() {
+        let old = [token(1), token(2), token(3)];
+        let new = [token(1), token(4), token(5), token(3)];
+        assert_eq!(semantic_tokens_delta(&old, &old), []);
+        assert_eq!(
+            semantic_tokens_delta(&old, &new),
+            [SemanticTokensEdit {
+                start: 5,
+                delete_count: 5,
+                data: vec![token(4), token(5)],
+            }]
+        );
+    }
+}
@@ -0,0 +1,375 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+use ecow::EcoString;
+use typst::foundations::{CastInfo, Func, ParamInfo, Repr, Value};
+use typst::syntax::ast::{self, AstNode};
+use typst::syntax::{LinkedNode, Source, SyntaxKind};
+use typst::World;
+
+use crate::analyze::analyze_expr;
+use crate::definition::scope_definition;
+use crate::plain_docs_sentence;
+
+/// Help for the signature of the function call the cursor is in.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SignatureHelp {
+    /// The full signature, e.g. `rect(width: auto | relative, ..) -> content`.
+    pub label: EcoString,
+    /// The first sentence of the function's documentation.
+    pub docs: Option<EcoString>,
+    /// The function's parameters.
+    pub params: Vec<SignatureParam>,
+    /// The index of the parameter the argument under the cursor is for.
+    pub active: Option<usize>,
+}
+
+/// A parameter in a [`SignatureHelp`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SignatureParam {
+    /// The parameter's name.
+    pub name: EcoString,
+    /// The byte range of the parameter in the signature's label.
+    pub range: Range<usize>,
+    /// The first sentence of the parameter's documentation.
+    pub docs: Option<EcoString>,
+    /// The types the parameter accepts, if known.
+    pub types: Option<EcoString>,
+    /// The parameter's default value, if it has one.
+    pub default: Option<EcoString>,
+    /// Whether the parameter can be given positionally.
+    pub positional: bool,
+    /// Whether the parameter can be given by name.
+    pub named: bool,
+    /// Whether the parameter can be given any number of times.
+    pub variadic: bool,
+}
+
+/// Describe the signature of the function call the cursor is in.
+///
+/// Works for native functions, element functions, and user-defined closures
+/// as well as for methods on values.
+pub fn signature_help(
+    world: &dyn World,
+    source: &Source,
+    cursor: usize,
+) -> Option<SignatureHelp> {
+    let leaf = LinkedNode::new(source.root()).leaf_at(cursor)?;
+
+    // Find the innermost argument list whose parentheses contain the cursor.
+    let mut node = leaf.clone();
+    let args = loop {
+        if node.kind() == SyntaxKind::Args
+            && matches!(
+                node.parent_kind(),
+                Some(SyntaxKind::FuncCall | SyntaxKind::SetRule)
+            )
+            && in_parens(&node, cursor)
+        {
+            break node;
+        }
+        node = node.parent()?.clone();
+    };
+
+    let callee = args.parent()?.children().find(|child| child.is::<ast::Expr>())?;
+    let (func, method) = resolve_callee(world, &callee)?;
+
+    let mut help = match func.params() {
+        Some(params) => native_signature(&func, params, method),
+        None => closure_signature(world, &func)?,
+    };
+
+    help.active = active_param(&args, cursor, &help.params);
+    Some(help)
+}
+
+/// Whether the cursor is within the parentheses of an argument list.
+fn in_parens(args: &LinkedNode, cursor: usize) -> bool {
+    let mut children = args.children();
+    let Some(open) = children.find(|child| child.kind() == SyntaxKind::LeftParen) else {
+        return false;
+    };
+
+    let close = children.find(|child| child.kind() == SyntaxKind::RightParen);
+    open.range().end <= cursor && close.map_or(true, |close| cursor <= close.offset())
+}
+
+/// Resolve the function that is called by a callee expression.
+///
+/// Also returns whether the function is a method that is called on a value.
+pub(crate) fn resolve_callee(
+    world: &dyn World,
+    callee: &LinkedNode,
+) -> Option<(Func, bool)> {
+    let value = match callee.cast::<ast::Expr>()? {
+        // Avoid the analysis if the identifier refers to the standard library.
+        ast::Expr::Ident(ident) if scope_definition(world, callee, &ident).is_none() => {
+            world.library().global.scope().get(&ident)?.clone()
+        }
+        ast::Expr::FieldAccess(access) => {
+            let target = callee.children().next()?;
+            let (value, _) = analyze_expr(world, &target).into_iter().next()?;
+            let field = access.field();
+            match value {
+                Value::Module(module) => module.field(&field).ok()?.clone(),
+                Value::Func(func) => func.field(&field).ok()?.clone(),
+                Value::Type(ty) => ty.field(&field).ok()?.clone(),
+                other => {
+                    let Value::Func(method) = other.ty().scope().get(&field)? else {
+                        return None;
+                    };
+                    return Some((method.clone(), true));
+                }
+            }
+        }
+        _ => analyze_expr(world, callee).into_iter().next()?.0,
+    };
+
+    match value {
+        Value::Func(func) => Some((func, false)),
+        Value::Type(ty) => Some((ty.constructor().ok()?, false)),
+        _ => None,
+    }
+}
+
+/// The signature of a native or element function.
+fn native_signature(func: &Func, params: &[ParamInfo], method: bool) -> SignatureHelp {
+    let mut label = EcoString::from(func.name().unwrap_or_default());
+    label.push('(');
+
+    let mut infos = vec![];
+    for param in params.iter().skip(usize::from(method)) {
+        if !infos.is_empty() {
+            label.push_str(", ");
+        }
+
+        let start = label.len();
+        if param.variadic {
+            label.push_str("..");
+        }
+        label.push_str(param.name);
+
+        let types = describe_cast(&param.input);
+        write!(label, ": {types}").unwrap();
+
+        infos.push(SignatureParam {
+            name: param.name.into(),
+            range: start..label.len(),
+            docs: Some(plain_docs_sentence(param.docs)),
+            types: Some(types),
+            default: param.default.map(|default| default().repr()),
+            positional: param.positional,
+            named: param.named,
+            variadic: param.variadic,
+        });
+    }
+
+    label.push(')');
+    if let Some(returns) = func.returns() {
+        write!(label, " -> {}", describe_cast(returns)).unwrap();
+    }
+
+    SignatureHelp {
+        label,
+        docs: func.docs().map(plain_docs_sentence),
+        params: infos,
+        active: None,
+    }
+}
+
+/// The signature of a user-defined closure, determined from its syntax.
+fn closure_signature(world: &dyn World, func: &Func) -> Option<SignatureHelp> {
+    let span = func.span();
+    let source = world.source(span.id()?).ok()?;
+    let node = source.find(span)?;
+    let closure = node.parent()?.cast::<ast::Closure>()?;
+
+    let mut label = EcoString::from(func.name().unwrap_or("closure"));
+    label.push('(');
+
+    let mut infos = vec![];
+    for (param, annotation) in closure.params().annotated() {
+        if !infos.is_empty() {
+            label.push_str(", ");
+        }
+
+        let start = label.len();
+        let (name, default, positional, named, variadic) = match param {
+            ast::Param::Pos(pattern) => {
+                let name = pattern.to_untyped().clone().into_text();
+                (name, None, true, false, false)
+            }
+            ast::Param::Named(named) => {
+                let default = named.expr().to_untyped().clone().into_text();
+                (named.name().get().clone(), Some(default), false, true, false)
+            }
+            ast::Param::Spread(spread) => {
+                let name = spread.sink_ident().map(|ident| ident.get().clone());
+                (name.unwrap_or_default(), None, true, true, true)
+            }
+        };
+
+        if variadic {
+            label.push_str("..");
+        }
+        label.push_str(&name);
+        let types = annotation
+            .map(|annotation| annotation.types().to_untyped().clone().into_text());
+        if let Some(types) = &types {
+            write!(label, " as {types}").unwrap();
+        }
+        if let Some(default) = &default {
+            write!(label, ": {default}").unwrap();
+        }
+
+        infos.push(SignatureParam {
+            name,
+            range: start..label.len(),
+            docs: None,
+            types,
+            default,
+            positional,
+            named,
+            variadic,
+        });
+    }
+
+    label.push(')');
+    if let Some(annotation) = closure.returns() {
+        write!(label, " -> {}", annotation.types().to_untyped().clone().into_text())
+            .unwrap();
+    }
+
+    Some(SignatureHelp { label, docs: None, params: infos, active: None })
+}
+
+/// Determine the parameter for the argument under the cursor.
+fn active_param(
+    args: &LinkedNode,
+    cursor: usize,
+    params: &[SignatureParam],
+) -> Option<usize> {
+    // The argument under the cursor and the number of positional arguments
+    // before it.
+    let mut positional = 0;
+    let mut current = None;
+    for child in args.children() {
+        if child.kind() == SyntaxKind::RightParen || child.offset() >= cursor {
+            break;
+        }
+
+        match child.get().cast::<ast::Arg>() {
+            Some(arg) if cursor <= child.range().end => current = Some(arg),
+            Some(ast::Arg::Pos(_)) => positional += 1,
+            _ if child.kind() == SyntaxKind::Comma => current = None,
+            _ => {}
+        }
+    }
+
+    match current {
+        Some(ast::Arg::Named(named)) => params
+            .iter()
+            .position(|param| param.named && param.name == named.name().as_str()),
+        Some(ast::Arg::Spread(_)) => None,
+        Some(ast::Arg::Pos(_)) | None => {
+            let mut seen = 0;
+            for (i, param) in params.iter().enumerate() {
+                if !param.positional {
+                    continue;
+                }
+                if param.variadic || seen == positional {
+                    return Some(i);
+                }
+                seen += 1;
+            }
+            None
+        }
+    }
+}
+
+/// Describe the values a parameter accepts, like `length | auto`.
+fn describe_cast(info: &CastInfo) -> EcoString {
+    let mut parts: Vec<EcoString> = vec![];
+    info.walk(|info| {
+        let part = match info {
+            CastInfo::Any => "any".into(),
+            CastInfo::Value(value @ Value::Str(_), _) => value.repr(),
+            CastInfo::Value(value, _) => value.ty().short_name().into(),
+            CastInfo::Type(ty) => ty.short_name().into(),
+            CastInfo::Union(_) => return,
+        };
+
+        if !parts.contains(&part) {
+            parts.push(part);
+        }
+    });
+
+    if parts.is_empty() {
+        return "none".into();
+    }
+
+    let mut output = EcoString::new();
+    for (i, part) in parts.iter().enumerate() {
+        if i > 0 {
+            output.push_str(" | ");
+        }
+        output.push_str(part);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use typst::World;
+
+    use super::{signature_help, SignatureHelp};
+    use crate::tests::TestWorld;
+
+    #[track_caller]
+    fn help(text: &str, cursor: usize) -> Option<SignatureHelp> {
+        let world = TestWorld::new(text);
+        signature_help(&world, &world.main(), cursor)
+    }
+
+    #[track_caller]
+    fn active(help: &SignatureHelp) -> &str {
+        &help.params[help.active.expect("no active parameter")].name
+    }
+
+    #[test]
+    fn test_signature_closure() {
+        let text = "#let f(a, b: 1) = a\n#f(1, b: 2)";
+        let first = help(text, 23).unwrap();
+        assert_eq!(first.label, "f(a, b: 1)");
+        assert_eq!(first.params.len(), 2);
+        assert_eq!(first.params[0].range, 2..3);
+        assert_eq!(first.params[1].range, 5..9);
+        assert_eq!(first.params[1].default.as_deref(), Some("1"));
+        assert_eq!(active(&first), "a");
+
+        let second = help(text, 29).unwrap();
+        assert_eq!(active(&second), "b");
+    }
+
+    #[test]
+    fn test_signature_native() {
+        let help = help("#rect(width: 1pt)", 13).unwrap();
+        assert!(help.label.starts_with("rect("));
+        assert!(help.label.ends_with(" -> content"));
+        assert_eq!(active(&help), "width");
+    }
+
+    #[test]
+    fn test_signature_method() {
+        let help = help("#\"abc\".split(\"b\")", 14).unwrap();
+        assert!(help.label.starts_with("split(pattern: "));
+        assert_eq!(active(&help), "pattern");
+    }
+
+    #[test]
+    fn test_signature_outside_call() {
+        assert_eq!(help("#rect()", 1), None);
+        assert_eq!(help("#let x = 1", 9), None);
+    }
+}
@@ -0,0 +1,323 @@
+use std::num::NonZeroUsize;
+use std::ops::Range;
+
+use ecow::{eco_format, EcoString};
+use typst::syntax::ast::{self, AstNode};
+use typst::syntax::{FileId, LinkedNode, Source, SyntaxKind, SyntaxNode};
+use typst::World;
+
+use crate::analyze::analyze_sources;
+use crate::definition::{label_name, name_range};
+
+/// A symbol in the outline of a source file.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct DocumentSymbol {
+    /// The symbol's name, e.g. a heading's text or a bound identifier.
+    pub name: EcoString,
+    /// Additional details, e.g. the parameters of a function.
+    pub detail: Option<EcoString>,
+    /// What kind of symbol this is.
+    pub kind: SymbolKind,
+    /// The byte range of the whole symbol. For headings, this is the whole
+    /// section up to the next heading of the same or a lower level.
+    pub range: Range<usize>,
+    /// The byte range of the symbol's name.
+    pub selection: Range<usize>,
+    /// Symbols nested within this one.
+    pub children: Vec<DocumentSymbol>,
+}
+
+impl DocumentSymbol {
+    /// Create a symbol without details and children that spans a node.
+    fn new(
+        node: &LinkedNode,
+        name: EcoString,
+        kind: SymbolKind,
+        selection: Range<usize>,
+    ) -> Self {
+        Self {
+            name,
+            detail: None,
+            kind,
+            range: node.range(),
+            selection,
+            children: vec![],
+        }
+    }
+}
+
+/// A kind of [`DocumentSymbol`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum SymbolKind {
+    /// A heading of the given level.
+    Heading(NonZeroUsize),
+    /// A let binding of a value other than a function.
+    Variable,
+    /// A let binding of a function.
+    Function,
+    /// A label attached to markup.
+    Label,
+    /// A show rule.
+    Show,
+    /// A set rule.
+    Set,
+}
+
+/// Build the outline of a source file.
+///
+/// Headings contain all following symbols up to the next heading of the same
+/// or a lower level. Symbols within let bindings and rules are nested into
+/// them.
+pub fn document_symbols(source: &Source) -> Vec<DocumentSymbol> {
+    let root = LinkedNode::new(source.root());
+    let mut symbols = vec![];
+    collect(&root, &mut symbols);
+    nest(symbols, source.len_bytes())
+}
+
+/// Search the symbols of all files in the project and the packages it
+/// imports.
+///
+/// Matches symbols whose name contains the query, ignoring case. The returned
+/// symbols have no children.
+pub fn workspace_symbols(
+    world: &dyn World,
+    query: &str,
+) -> Vec<(FileId, DocumentSymbol)> {
+    let query = query.to_lowercase();
+    let mut output = vec![];
+    for source in analyze_sources(world, true) {
+        let mut pending = document_symbols(&source);
+        while let Some(mut symbol) = pending.pop() {
+            pending.append(&mut symbol.children);
+            if symbol.name.to_lowercase().contains(&query) {
+                output.push((source.id(), symbol));
+            }
+        }
+    }
+
+    output.sort_by(|(a_id, a), (b_id, b)| {
+        a_id.vpath()
+            .as_rooted_path()
+            .cmp(b_id.vpath().as_rooted_path())
+            .then(a.range.start.cmp(&b.range.start))
+    });
+
+    output
+}
+
+/// Collect the symbols in the children of a node.
+fn collect(node: &LinkedNode, output: &mut Vec<DocumentSymbol>) {
+    for child in node.children() {
+        let mut symbols = node_symbols(&child);
+        if symbols.is_empty() {
+            collect(&child, output);
+            continue;
+        }
+
+        if let [symbol] = symbols.as_mut_slice() {
+            let mut children = vec![];
+            collect(&child, &mut children);
+            symbol.children = nest(children, child.range().end);
+        }
+
+        output.extend(symbols);
+    }
+}
+
+/// The symbols a node defines by itself, without its children.
+fn node_symbols(node: &LinkedNode) -> Vec<DocumentSymbol> {
+    let Some(expr) = node.cast::<ast::Expr>() else { return vec![] };
+
+    match expr {
+        ast::Expr::Heading(heading) => {
+            let body = heading.body();
+            let mut name = EcoString::new();
+            for child in body.to_untyped().children() {
+                if child.kind() != SyntaxKind::Label {
+                    name.push_str(&child.clone().into_text());
+                }
+            }
+
+            let mut name: EcoString =
+                name.split_whitespace().collect::<Vec<_>>().join(" ").into();
+            if name.is_empty() {
+                name = "(untitled)".into();
+            }
+
+            let kind = SymbolKind::Heading(heading.depth());
+            let selection = range_of(node, body.to_untyped());
+            vec![DocumentSymbol::new(node, name, kind, selection)]
+        }
+        ast::Expr::Let(binding) => {
+            let closure = match binding.init() {
+                Some(ast::Expr::Closure(closure)) => Some(closure),
+                _ => None,
+            };
+
+            let detail =
+                closure.map(|closure| closure.params().to_untyped().clone().into_text());
+            let kind = if closure.is_some() {
+                SymbolKind::Function
+            } else {
+                SymbolKind::Variable
+            };
+            binding
+                .kind()
+                .bindings()
+                .into_iter()
+                .map(|ident| {
+                    let selection = range_of(node, ident.to_untyped());
+                    let name = ident.get().clone();
+                    DocumentSymbol {
+                        detail: detail.clone(),
+                        ..DocumentSymbol::new(node, name, kind, selection)
+                    }
+                })
+                .collect()
+        }
+        ast::Expr::Set(set) => {
+            let target = set.target().to_untyped();
+            let name = eco_format!("set {}", target.clone().into_text());
+            vec![DocumentSymbol::new(node, name, SymbolKind::Set, range_of(node, target))]
+        }
+        ast::Expr::Label(_) if node.parent_kind() == Some(SyntaxKind::Markup) => {
+            let name = label_name(node).unwrap_or_default().into();
+            let selection = name_range(node);
+            vec![DocumentSymbol::new(node, name, SymbolKind::Label, selection)]
+        }
+        ast::Expr::Show(show) => {
+            let (name, selection) = match show.selector() {
+                Some(selector) => {
+                    let selector = selector.to_untyped();
+                    (
+                        eco_format!("show {}", selector.clone().into_text()),
+                        range_of(node, selector),
+                    )
+                }
+                None => ("show".into(), node.offset()..node.offset() + 4),
+            };
+            vec![DocumentSymbol::new(node, name, SymbolKind::Show, selection)]
+        }
+        _ => vec![],
+    }
+}
+
+/// The byte range of a descendant of a node.
+fn range_of(node: &LinkedNode, descendant: &SyntaxNode) -> Range<usize> {
+    node.find(descendant.span())
+        .map(|found| found.range())
+        .unwrap_or_else(|| node.range())
+}
+
+/// Nest symbols into the sections of the headings that precede them.
+///
+/// The section of the last heading ends at `end`.
+fn nest(symbols: Vec<DocumentSymbol>, end: usize) -> Vec<DocumentSymbol> {
+    fn level(symbol: &DocumentSymbol) -> Option<NonZeroUsize> {
+        match symbol.kind {
+            SymbolKind::Heading(level) => Some(level),
+            _ => None,
+        }
+    }
+
+    fn close(
+        stack: &mut Vec<DocumentSymbol>,
+        output: &mut Vec<DocumentSymbol>,
+        end: usize,
+    ) {
+        let mut heading = stack.pop().unwrap();
+        heading.range.end = heading.range.end.max(end);
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(heading),
+            None => output.push(heading),
+        }
+    }
+
+    let mut output = vec![];
+    let mut stack: Vec<DocumentSymbol> = vec![];
+    for symbol in symbols {
+        if let Some(current) = level(&symbol) {
+            while stack.last().and_then(level).is_some_and(|open| open >= current) {
+                close(&mut stack, &mut output, symbol.range.start);
+            }
+            stack.push(symbol);
+        } else if let Some(open) = stack.last_mut() {
+            open.children.push(symbol);
+        } else {
+            output.push(symbol);
+        }
+    }
+
+    while !stack.is_empty() {
+        close(&mut stack, &mut output, end);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use typst::World;
+
+    use super::{document_symbols, workspace_symbols, DocumentSymbol, SymbolKind};
+    use crate::tests::{id, TestWorld};
+
+    /// Summarize the names of nested symbols.
+    fn outline(symbols: &[DocumentSymbol]) -> String {
+        let names: Vec<_> = symbols
+            .iter()
+            .map(|symbol| match symbol.children.as_slice() {
+                [] => symbol.name.to_string(),
+                children => format!("{} [{}]", symbol.name, outline(children)),
+            })
+            .collect();
+        names.join(", ")
+    }
+
+    #[test]
+    fn test_document_symbols() {
+        let world = TestWorld::new(
+            "= Intro\n#let x = 1\n== Sub\n#let f(a) = a\n\
+             = Next <next>\n#set text(red)\n#show heading: it => it",
+        );
+
+        let symbols = document_symbols(&world.main());
+        assert_eq!(
+            outline(&symbols),
+            "Intro [x, Sub [f]], Next [next, set text, show heading]"
+        );
+
+        let intro = &symbols[0];
+        assert_eq!(intro.kind, SymbolKind::Heading(NonZeroUsize::new(1).unwrap()));
+        assert_eq!(intro.selection, 2..7);
+        assert_eq!(intro.range.end, symbols[1].range.start);
+        assert_eq!(intro.children[0].kind, SymbolKind::Variable);
+
+        let f = &intro.children[1].children[0];
+        assert_eq!(f.kind, SymbolKind::Function);
+        assert_eq!(f.detail.as_deref(), Some("(a)"));
+
+        let next = &symbols[1];
+        assert_eq!(next.children[0].kind, SymbolKind::Label);
+        assert_eq!(next.children[1].kind, SymbolKind::Set);
+        assert_eq!(next.children[2].kind, SymbolKind::Show);
+    }
+
+    #[test]
+    fn test_workspace_symbols() {
+        let world = TestWorld::new("#import \"a.typ\": helper\n= Helpers")
+            .with_source("a.typ", "#let helper(x) = x\n#let other = 1");
+
+        let found: Vec<_> = workspace_symbols(&world, "HELP")
+            .into_iter()
+            .map(|(file, symbol)| (file, symbol.name))
+            .collect();
+        assert_eq!(
+            found,
+            [(id("a.typ"), "helper".into()), (id("main.typ"), "Helpers".into())]
+        );
+    }
+}
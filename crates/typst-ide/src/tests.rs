@@ -0,0 +1,120 @@
+use comemo::Prehashed;
+use ecow::EcoString;
+use once_cell::sync::Lazy;
+use typst::diag::{FileError, FileResult};
+use typst::foundations::{Bytes, Datetime};
+use typst::syntax::package::PackageSpec;
+use typst::syntax::{FileId, Source, VirtualPath};
+use typst::text::{Font, FontBook};
+use typst::{Library, World};
+
+/// A world with in-memory files for testing.
+pub struct TestWorld {
+    sources: Vec<Source>,
+    files: Vec<(FileId, Bytes)>,
+    packages: Vec<(PackageSpec, Option<EcoString>)>,
+    base: &'static TestBase,
+}
+
+impl TestWorld {
+    /// Create a new world with the given text as its main file `main.typ`.
+    pub fn new(text: &str) -> Self {
+        static BASE: Lazy<TestBase> = Lazy::new(TestBase::default);
+        Self {
+            sources: vec![Source::new(id("main.typ"), text.into())],
+            files: vec![],
+            packages: vec![],
+            base: &BASE,
+        }
+    }
+
+    /// Add a source file to the world.
+    pub fn with_source(mut self, path: &str, text: &str) -> Self {
+        self.sources.push(Source::new(id(path), text.into()));
+        self
+    }
+
+    /// Add a binary file to the world.
+    pub fn with_file(mut self, path: &str, data: &'static [u8]) -> Self {
+        self.files.push((id(path), Bytes::from_static(data)));
+        self
+    }
+
+    /// Make a package like `@preview/example:0.1.0` available.
+    pub fn with_package(mut self, spec: &str) -> Self {
+        self.packages.push((spec.parse().unwrap(), None));
+        self
+    }
+}
+
+impl World for TestWorld {
+    fn library(&self) -> &Prehashed<Library> {
+        &self.base.library
+    }
+
+    fn book(&self) -> &Prehashed<FontBook> {
+        &self.base.book
+    }
+
+    fn main(&self) -> Source {
+        self.sources[0].clone()
+    }
+
+    fn source(&self, id: FileId) -> FileResult<Source> {
+        self.sources
+            .iter()
+            .find(|source| source.id() == id)
+            .cloned()
+            .ok_or_else(|| FileError::NotFound(id.vpath().as_rootless_path().into()))
+    }
+
+    fn file(&self, id: FileId) -> FileResult<Bytes> {
+        match self.files.iter().find(|(file, _)| *file == id) {
+            Some((_, data)) => Ok(data.clone()),
+            None => Ok(self.source(id)?.text().as_bytes().into()),
+        }
+    }
+
+    fn font(&self, index: usize) -> Option<Font> {
+        self.base.fonts.get(index).cloned()
+    }
+
+    fn today(&self, _: Option<i64>) -> Option<Datetime> {
+        None
+    }
+
+    fn packages(&self) -> &[(PackageSpec, Option<EcoString>)] {
+        &self.packages
+    }
+
+    fn files(&self) -> Vec<FileId> {
+        let sources = self.sources.iter().map(Source::id);
+        sources.chain(self.files.iter().map(|(id, _)| *id)).collect()
+    }
+}
+
+/// Shared foundation of all test worlds.
+struct TestBase {
+    library: Prehashed<Library>,
+    book: Prehashed<FontBook>,
+    fonts: Vec<Font>,
+}
+
+impl Default for TestBase {
+    fn default() -> Self {
+        let fonts: Vec<_> = typst_assets::fonts()
+            .flat_map(|data| Font::iter(Bytes::from_static(data)))
+            .collect();
+
+        Self {
+            library: Prehashed::new(Library::default()),
+            book: Prehashed::new(FontBook::from_fonts(&fonts)),
+            fonts,
+        }
+    }
+}
+
+/// The ID of a file in the test project.
+pub fn id(path: &str) -> FileId {
+    FileId::new(None, VirtualPath::new(path))
+}
@@ -1,16 +1,24 @@
+use std::ffi::OsStr;
 use std::fmt::Write;
 
+use comemo::Prehashed;
 use ecow::{eco_format, EcoString};
 use if_chain::if_chain;
+use typst::diag::FileResult;
 use typst::eval::{CapturesVisitor, Tracer};
-use typst::foundations::{repr, Capturer, CastInfo, Repr, Value};
-use typst::layout::Length;
+use typst::foundations::{repr, Bytes, Capturer, CastInfo, Datetime, Label, Repr, Value};
+use typst::introspection::{Location, Meta};
+use typst::layout::{Abs, Frame, FrameItem, Length, Point, Size, Transform};
 use typst::model::Document;
-use typst::syntax::{ast, LinkedNode, Source, SyntaxKind};
+use typst::syntax::ast::{self, AstNode};
+use typst::syntax::{FileId, LinkedNode, Source, SyntaxKind, VirtualPath};
+use typst::text::{Font, FontBook};
 use typst::util::{round_2, Numeric};
-use typst::World;
+use typst::visualize::{Color, ImageFormat, Path, RasterFormat, VectorFormat};
+use typst::{Library, World};
 
 use crate::analyze::{analyze_expr, analyze_labels};
+use crate::semantic::static_value;
 use crate::{plain_docs_sentence, summarize_font_family};
 
 /// Describe the item under the cursor.
@@ -31,6 +39,10 @@ pub fn tooltip(
 
     named_param_tooltip(world, &leaf)
         .or_else(|| font_tooltip(world, &leaf))
+        .or_else(|| image_tooltip(world, &leaf))
+        .or_else(|| document.and_then(|doc| label_preview_tooltip(doc, &leaf)))
+        .or_else(|| document.and_then(|doc| equation_tooltip(doc, &leaf)))
+        .or_else(|| symbol_tooltip(world, &leaf))
         .or_else(|| document.and_then(|doc| label_tooltip(doc, &leaf)))
         .or_else(|| expr_tooltip(world, &leaf))
         .or_else(|| closure_tooltip(&leaf))
@@ -43,6 +55,30 @@ pub enum Tooltip {
     Text(EcoString),
     /// A string of Typst code.
     Code(EcoString),
+    /// A rendered image of the hovered item.
+    Preview(Preview),
+}
+
+/// A small rendered image shown in a tooltip.
+#[derive(Debug, Clone)]
+pub struct Preview {
+    /// The encoded image.
+    pub data: Bytes,
+    /// The format of the encoded image.
+    pub format: ImageFormat,
+    /// A text describing the image.
+    pub caption: Option<EcoString>,
+}
+
+impl Preview {
+    /// Render a frame into an SVG preview.
+    fn svg(frame: &Frame, caption: Option<EcoString>) -> Self {
+        Self {
+            data: Bytes::from(typst_svg::svg(frame).into_bytes()),
+            format: ImageFormat::Vector(VectorFormat::Svg),
+            caption,
+        }
+    }
 }
 
 /// Tooltip for a hovered expression.
@@ -149,21 +185,263 @@ fn length_tooltip(length: Length) -> Option<Tooltip> {
     })
 }
 
-/// Tooltip for a hovered reference or label.
-fn label_tooltip(document: &Document, leaf: &LinkedNode) -> Option<Tooltip> {
-    let target = match leaf.kind() {
+/// Extract the label name from a hovered reference or label.
+fn label_target<'a>(leaf: &'a LinkedNode) -> Option<&'a str> {
+    Some(match leaf.kind() {
         SyntaxKind::RefMarker => leaf.text().trim_start_matches('@'),
         SyntaxKind::Label => leaf.text().trim_start_matches('<').trim_end_matches('>'),
         _ => return None,
+    })
+}
+
+/// Find the details for a label.
+fn label_detail(document: &Document, target: &str) -> Option<EcoString> {
+    analyze_labels(document)
+        .0
+        .into_iter()
+        .find(|(label, _)| label.as_str() == target)
+        .and_then(|(_, detail)| detail)
+}
+
+/// Tooltip with a rendered preview of a referenced or labelled element.
+fn label_preview_tooltip(document: &Document, leaf: &LinkedNode) -> Option<Tooltip> {
+    let target = label_target(leaf)?;
+    let elem = document.introspector.query_label(Label::new(target)).ok()?;
+    let location = elem.location()?;
+    let caption = label_detail(document, target);
+    frame_preview(document, location, caption)
+}
+
+/// Tooltip with a rendered preview of a hovered equation.
+fn equation_tooltip(document: &Document, leaf: &LinkedNode) -> Option<Tooltip> {
+    let mut ancestor = leaf;
+    while ancestor.kind() != SyntaxKind::Equation {
+        ancestor = ancestor.parent()?;
+    }
+
+    let span = ancestor.span();
+    let location = document
+        .introspector
+        .all()
+        .find(|elem| elem.span() == span)
+        .and_then(|elem| elem.location())?;
+
+    frame_preview(document, location, None)
+}
+
+/// Render the region of the page that is occupied by the element with the
+/// given location.
+fn frame_preview(
+    document: &Document,
+    location: Location,
+    caption: Option<EcoString>,
+) -> Option<Tooltip> {
+    let padding = Abs::pt(4.0);
+    let index = document.introspector.page(location).get() - 1;
+    let page = &document.pages.get(index)?.frame;
+
+    let mut bounds = None;
+    element_bounds(page, location, Transform::identity(), &mut bounds);
+    let (min, max) = bounds?;
+
+    let size = (max - min).to_size() + Size::splat(2.0 * padding);
+    let mut frame = Frame::hard(size);
+    frame.push_frame(Point::splat(padding) - min, page.clone());
+    frame.clip(Path::rect(size));
+    frame.fill(Color::WHITE.into());
+
+    Some(Tooltip::Preview(Preview::svg(&frame, caption)))
+}
+
+/// Grow `bounds` to include all areas of the frame that are occupied by the
+/// element with the given location.
+fn element_bounds(
+    frame: &Frame,
+    location: Location,
+    ts: Transform,
+    bounds: &mut Option<(Point, Point)>,
+) {
+    for (pos, item) in frame.items() {
+        let ts = ts.pre_concat(Transform::translate(pos.x, pos.y));
+        match item {
+            FrameItem::Group(group) => {
+                let ts = ts.pre_concat(group.transform);
+                element_bounds(&group.frame, location, ts, bounds);
+            }
+            FrameItem::Meta(Meta::Elem(elem), size)
+                if !size.is_zero() && elem.location() == Some(location) =>
+            {
+                let corners = [
+                    Point::zero(),
+                    Point::with_x(size.x),
+                    Point::with_y(size.y),
+                    size.to_point(),
+                ];
+
+                for corner in corners {
+                    let point = corner.transform(ts);
+                    let (min, max) = bounds.get_or_insert((point, point));
+                    *min = min.min(point);
+                    *max = max.max(point);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Tooltip with the image behind a hovered image path.
+fn image_tooltip(world: &dyn World, leaf: &LinkedNode) -> Option<Tooltip> {
+    let path = if_chain! {
+        // Ensure that we are on the first positional argument of an image
+        // call.
+        if let Some(string) = leaf.cast::<ast::Str>();
+        if let Some(parent) = leaf.parent();
+        if let Some(args) = parent.cast::<ast::Args>();
+        if let Some(ast::Arg::Pos(ast::Expr::Str(first))) = args.items().next();
+        if first.span() == leaf.span();
+        if let Some(grand) = parent.parent();
+        if let Some(ast::Expr::FuncCall(call)) = grand.cast::<ast::Expr>();
+        if let ast::Expr::Ident(callee) = call.callee();
+        if callee.as_str() == "image";
+        then { string.get() }
+        else { return None; }
+    };
+
+    let id = leaf.span().id()?.join(&path);
+    let data = world.file(id).ok()?;
+    let ext = std::path::Path::new(path.as_str())
+        .extension()
+        .and_then(OsStr::to_str)
+        .unwrap_or_default()
+        .to_lowercase();
+
+    let format = match ext.as_str() {
+        "png" => ImageFormat::Raster(RasterFormat::Png),
+        "jpg" | "jpeg" => ImageFormat::Raster(RasterFormat::Jpg),
+        "gif" => ImageFormat::Raster(RasterFormat::Gif),
+        "svg" => ImageFormat::Vector(VectorFormat::Svg),
+        _ => ImageFormat::Raster(RasterFormat::detect(&data)?),
     };
 
-    for (label, detail) in analyze_labels(document).0 {
-        if label.as_str() == target {
-            return Some(Tooltip::Text(detail?));
+    Some(Tooltip::Preview(Preview { data, format, caption: Some(path) }))
+}
+
+/// Tooltip with a rendered preview of a hovered symbol.
+fn symbol_tooltip(world: &dyn World, leaf: &LinkedNode) -> Option<Tooltip> {
+    let c = if let Some(shorthand) = leaf.cast::<ast::Shorthand>() {
+        shorthand.get()
+    } else {
+        // Symbols are accessed through identifiers and field chains like
+        // `sym.arrow.r.double`.
+        if !matches!(leaf.kind(), SyntaxKind::Ident | SyntaxKind::MathIdent) {
+            return None;
+        }
+
+        let mut node = leaf.clone();
+        while let Some(parent) = node.parent() {
+            if parent.kind() != SyntaxKind::FieldAccess {
+                break;
+            }
+            node = parent.clone();
+        }
+
+        match static_value(world, &node)? {
+            Value::Symbol(symbol) => symbol.get(),
+            _ => return None,
+        }
+    };
+
+    let body = if in_math(leaf) {
+        let escape = if c.is_ascii_punctuation() { "\\" } else { "" };
+        eco_format!("${escape}{c}$")
+    } else {
+        eco_format!("#{}", EcoString::from(c).repr())
+    };
+
+    let text = format!(
+        "#set page(width: auto, height: auto, margin: 4pt, fill: white)\n\
+         #set text(size: 24pt)\n\
+         {body}"
+    );
+
+    let world = PreviewWorld::new(world, text);
+    let document = typst::compile(&world, &mut Tracer::new()).ok()?;
+    let page = document.pages.first()?;
+    let caption = eco_format!("U+{:04X}", c as u32);
+    Some(Tooltip::Preview(Preview::svg(&page.frame, Some(caption))))
+}
+
+/// Whether a node is part of an equation.
+fn in_math(leaf: &LinkedNode) -> bool {
+    let mut node = leaf.clone();
+    loop {
+        match node.kind() {
+            SyntaxKind::Equation => return true,
+            SyntaxKind::Markup | SyntaxKind::Code => return false,
+            _ => {}
+        }
+
+        match node.parent() {
+            Some(parent) => node = parent.clone(),
+            None => return false,
         }
     }
+}
 
-    None
+/// A world that compiles a synthetic main file, but otherwise reads fonts
+/// and files from another world.
+struct PreviewWorld<'a> {
+    world: &'a dyn World,
+    main: Source,
+}
+
+impl<'a> PreviewWorld<'a> {
+    /// Wrap a world with a main file with the given text.
+    fn new(world: &'a dyn World, text: String) -> Self {
+        let id = FileId::new(None, VirtualPath::new("/__preview__.typ"));
+        Self { world, main: Source::new(id, text) }
+    }
+}
+
+impl World for PreviewWorld<'_> {
+    fn library(&self) -> &Prehashed<Library> {
+        self.world.library()
+    }
+
+    fn book(&self) -> &Prehashed<FontBook> {
+        self.world.book()
+    }
+
+    fn main(&self) -> Source {
+        self.main.clone()
+    }
+
+    fn source(&self, id: FileId) -> FileResult<Source> {
+        if id == self.main.id() {
+            Ok(self.main.clone())
+        } else {
+            self.world.source(id)
+        }
+    }
+
+    fn file(&self, id: FileId) -> FileResult<Bytes> {
+        self.world.file(id)
+    }
+
+    fn font(&self, index: usize) -> Option<Font> {
+        self.world.font(index)
+    }
+
+    fn today(&self, offset: Option<i64>) -> Option<Datetime> {
+        self.world.today(offset)
+    }
+}
+
+/// Tooltip for a hovered reference or label.
+fn label_tooltip(document: &Document, leaf: &LinkedNode) -> Option<Tooltip> {
+    let target = label_target(leaf)?;
+    label_detail(document, target).map(Tooltip::Text)
 }
 
 /// Tooltips for components of a named parameter.
@@ -249,3 +527,92 @@ fn font_tooltip(world: &dyn World, leaf: &LinkedNode) -> Option<Tooltip> {
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use typst::eval::Tracer;
+    use typst::model::Document;
+    use typst::visualize::{ImageFormat, VectorFormat};
+    use typst::World;
+
+    use super::{tooltip, Preview, Tooltip};
+    use crate::tests::TestWorld;
+
+    /// The tooltip at a cursor position, optionally with a compiled document.
+    fn hover(world: &TestWorld, cursor: usize, compile: bool) -> Option<Tooltip> {
+        let document: Option<Document> =
+            compile.then(|| typst::compile(world, &mut Tracer::new()).unwrap());
+        tooltip(world, document.as_ref(), &world.main(), cursor)
+    }
+
+    #[track_caller]
+    fn text(tooltip: Option<Tooltip>) -> String {
+        match tooltip {
+            Some(Tooltip::Text(text) | Tooltip::Code(text)) => text.into(),
+            other => panic!("expected a text tooltip, found {other:?}"),
+        }
+    }
+
+    #[track_caller]
+    fn preview(tooltip: Option<Tooltip>) -> Preview {
+        match tooltip {
+            Some(Tooltip::Preview(preview)) => preview,
+            other => panic!("expected a preview, found {other:?}"),
+        }
+    }
+
+    #[track_caller]
+    fn test_svg(preview: &Preview, caption: Option<&str>) {
+        assert_eq!(preview.format, ImageFormat::Vector(VectorFormat::Svg));
+        assert_eq!(preview.caption.as_deref(), caption);
+        assert!(preview.data.starts_with(b"<svg"));
+    }
+
+    #[test]
+    fn test_tooltip_expr() {
+        let world = TestWorld::new("#let x = 1pt\n#x");
+        assert_eq!(text(hover(&world, 15, false)), "1pt = 0.35mm = 0.04cm = 0.01in");
+
+        let world = TestWorld::new("#let x = 1\n#let f(y) = x + y");
+        assert_eq!(text(hover(&world, 22, false)), "This closure captures `x`.");
+    }
+
+    #[test]
+    fn test_tooltip_params() {
+        let world = TestWorld::new("#rect(width: 1pt)");
+        assert!(text(hover(&world, 8, false)).starts_with("The rectangle's width"));
+
+        let world = TestWorld::new("#set text(font: \"Linux Libertine\")");
+        assert!(text(hover(&world, 20, false)).contains("variants."));
+    }
+
+    #[test]
+    fn test_tooltip_image() {
+        let svg = b"<svg xmlns=\"http://www.w3.org/2000/svg\"></svg>";
+        let world = TestWorld::new("#image(\"image.svg\")").with_file("image.svg", svg);
+        let preview = preview(hover(&world, 10, false));
+        test_svg(&preview, Some("image.svg"));
+        assert_eq!(preview.data.as_slice(), svg);
+    }
+
+    #[test]
+    fn test_tooltip_symbol() {
+        let world = TestWorld::new("#sym.arrow.l");
+        test_svg(&preview(hover(&world, 12, false)), Some("U+2190"));
+
+        let world = TestWorld::new("$arrow.r$");
+        test_svg(&preview(hover(&world, 8, false)), Some("U+2192"));
+    }
+
+    #[test]
+    fn test_tooltip_document_previews() {
+        let world = TestWorld::new(
+            "#set heading(numbering: \"1.\")\n= Intro <intro>\nSee @intro.",
+        );
+        test_svg(&preview(hover(&world, 52, true)), Some("Intro"));
+        assert!(hover(&world, 52, false).is_none());
+
+        let world = TestWorld::new("$ x^2 $");
+        test_svg(&preview(hover(&world, 3, true)), None);
+    }
+}
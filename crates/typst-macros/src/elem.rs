@@ -621,7 +621,8 @@ fn create_native_elem_impl(element: &Elem) -> TokenStream {
             field_from_styles: <#ident as #foundations::Fields>::field_from_styles,
             local_name: #local_name,
             scope: #foundations::Lazy::new(|| #scope),
-            params: #foundations::Lazy::new(|| ::std::vec![#(#params),*])
+            params: #foundations::Lazy::new(|| ::std::vec![#(#params),*]),
+            custom: None,
         }
     };
 
@@ -98,6 +98,7 @@ fn create(ty: &Type, item: Option<&syn::Item>) -> TokenStream {
             keywords: &[#(#keywords),*],
             constructor: #foundations::Lazy::new(|| #constructor),
             scope: #foundations::Lazy::new(|| #scope),
+            custom: None,
         }
     };
 
@@ -1244,6 +1244,13 @@ impl<'a> Named<'a> {
     pub fn pattern(self) -> Pattern<'a> {
         self.0.cast_last_match().unwrap_or_default()
     }
+
+    /// The type annotation of a named parameter: `as length`.
+    ///
+    /// This should only be accessed if this `Named` is contained in a `Param`.
+    pub fn annotation(self) -> Option<Annotation<'a>> {
+        self.0.cast_first_match()
+    }
 }
 
 node! {
@@ -1647,6 +1654,11 @@ impl<'a> Closure<'a> {
         self.0.cast_first_match().unwrap_or_default()
     }
 
+    /// The annotation of the closure's return type: `-> content`.
+    pub fn returns(self) -> Option<Annotation<'a>> {
+        self.0.cast_first_match()
+    }
+
     /// The body of the closure.
     pub fn body(self) -> Expr<'a> {
         self.0.cast_last_match().unwrap_or_default()
@@ -1663,6 +1675,38 @@ impl<'a> Params<'a> {
     pub fn children(self) -> impl DoubleEndedIterator<Item = Param<'a>> {
         self.0.children().filter_map(SyntaxNode::cast)
     }
+
+    /// The parameter bindings along with their type annotations.
+    pub fn annotated(self) -> impl Iterator<Item = (Param<'a>, Option<Annotation<'a>>)> {
+        let mut children = self.0.children().peekable();
+        std::iter::from_fn(move || {
+            let param = children.find_map(|child| child.cast::<Param>())?;
+            let annotation = match param {
+                Param::Named(named) => named.annotation(),
+                _ => {
+                    while children.next_if(|child| child.kind().is_trivia()).is_some() {}
+                    children
+                        .next_if(|child| child.kind() == SyntaxKind::Annotation)
+                        .and_then(SyntaxNode::cast)
+                }
+            };
+            Some((param, annotation))
+        })
+    }
+}
+
+node! {
+    /// A type annotation of a parameter or a closure's return value: `as int`,
+    /// `-> content`.
+    Annotation
+}
+
+impl<'a> Annotation<'a> {
+    /// The expression that evaluates to the accepted type or types: `int`,
+    /// `(str, none)`.
+    pub fn types(self) -> Expr<'a> {
+        self.0.cast_last_match().unwrap_or_default()
+    }
 }
 
 /// A parameter to a closure.
@@ -1805,7 +1849,7 @@ impl<'a> AstNode<'a> for DestructuringItem<'a> {
 }
 
 node! {
-    /// A let binding: `let x = 1`.
+    /// A let binding: `let x = 1`, or a private one: `private let x = 1`.
     LetBinding
 }
 
@@ -1829,6 +1873,11 @@ impl<'a> LetBindingKind<'a> {
 }
 
 impl<'a> LetBinding<'a> {
+    /// Whether the binding is marked with the `private` keyword.
+    pub fn is_private(self) -> bool {
+        self.0.children().next().map(SyntaxNode::kind) == Some(SyntaxKind::Private)
+    }
+
     /// The kind of the let binding.
     pub fn kind(self) -> LetBindingKind<'a> {
         match self.0.cast_first_match::<Pattern>() {
@@ -1996,11 +2045,17 @@ impl<'a> ForLoop<'a> {
 }
 
 node! {
-    /// A module import: `import "utils.typ": a, b, c`.
+    /// A module import: `import "utils.typ": a, b, c`, optionally marked as
+    /// `private`.
     ModuleImport
 }
 
 impl<'a> ModuleImport<'a> {
+    /// Whether the import is marked with the `private` keyword.
+    pub fn is_private(self) -> bool {
+        self.0.children().next().map(SyntaxNode::kind) == Some(SyntaxKind::Private)
+    }
+
     /// The module or path from which the items should be imported.
     pub fn source(self) -> Expr<'a> {
         self.0.cast_first_match().unwrap_or_default()
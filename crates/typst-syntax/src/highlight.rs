@@ -223,6 +223,7 @@ pub fn highlight(node: &LinkedNode) -> Option<Tag> {
         SyntaxKind::SlashEq => Some(Tag::Operator),
         SyntaxKind::Dots => Some(Tag::Operator),
         SyntaxKind::Arrow => Some(Tag::Operator),
+        SyntaxKind::ThinArrow => Some(Tag::Operator),
         SyntaxKind::Root => Some(Tag::MathOperator),
 
         SyntaxKind::Not => Some(Tag::Keyword),
@@ -243,6 +244,7 @@ pub fn highlight(node: &LinkedNode) -> Option<Tag> {
         SyntaxKind::Continue => Some(Tag::Keyword),
         SyntaxKind::Return => Some(Tag::Keyword),
         SyntaxKind::Import => Some(Tag::Keyword),
+        SyntaxKind::Private => Some(Tag::Keyword),
         SyntaxKind::Include => Some(Tag::Keyword),
         SyntaxKind::As => Some(Tag::Keyword),
 
@@ -268,6 +270,7 @@ pub fn highlight(node: &LinkedNode) -> Option<Tag> {
         SyntaxKind::Spread => None,
         SyntaxKind::Closure => None,
         SyntaxKind::Params => None,
+        SyntaxKind::Annotation => None,
         SyntaxKind::LetBinding => None,
         SyntaxKind::SetRule => None,
         SyntaxKind::ShowRule => None,
@@ -459,5 +462,27 @@ mod tests {
                 (10..11, Operator),
             ],
         );
+
+        test(
+            "#private let x = 1",
+            &[
+                (0..1, Keyword),
+                (1..8, Keyword),
+                (9..12, Keyword),
+                (15..16, Operator),
+                (17..18, Number),
+            ],
+        );
+
+        // Elsewhere, `private` is an identifier, just like `export`.
+        test(
+            "#private.export",
+            &[
+                (0..1, Interpolated),
+                (1..8, Interpolated),
+                (8..9, Punctuation),
+                (9..15, Interpolated),
+            ],
+        );
     }
 }
@@ -4,6 +4,17 @@
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 #[repr(u8)]
 pub enum SyntaxKind {
+    // Kinds that appear in syntax sets must have a discriminant below 128,
+    // so trivia, errors, and the end marker come first.
+    /// The end of the file.
+    Eof,
+    /// An invalid sequence of characters.
+    Error,
+    /// A line comment: `// ...`.
+    LineComment,
+    /// A block comment: `/* ... */`.
+    BlockComment,
+
     /// The contents of a file or content block.
     Markup,
     /// Plain text without markup.
@@ -146,6 +157,8 @@ pub enum SyntaxKind {
     Dots,
     /// An arrow between a closure's parameters and body: `=>`.
     Arrow,
+    /// An arrow before a closure's return type: `->`.
+    ThinArrow,
     /// A root: `√`, `∛` or `∜`.
     Root,
 
@@ -185,6 +198,8 @@ pub enum SyntaxKind {
     Return,
     /// The `import` keyword.
     Import,
+    /// The contextual `private` keyword.
+    Private,
     /// The `include` keyword.
     Include,
     /// The `as` keyword.
@@ -234,6 +249,9 @@ pub enum SyntaxKind {
     Closure,
     /// A closure's parameters: `(x, y)`.
     Params,
+    /// A type annotation of a closure's parameter or return value: `as int`,
+    /// `-> content`.
+    Annotation,
     /// A let binding: `let x = 1`.
     LetBinding,
     /// A set rule: `set text(...)`.
@@ -266,15 +284,6 @@ pub enum SyntaxKind {
     Destructuring,
     /// A destructuring assignment expression: `(x, y) = (1, 2)`.
     DestructAssignment,
-
-    /// A line comment: `// ...`.
-    LineComment,
-    /// A block comment: `/* ... */`.
-    BlockComment,
-    /// An invalid sequence of characters.
-    Error,
-    /// The end of the file.
-    Eof,
 }
 
 impl SyntaxKind {
@@ -342,6 +351,7 @@ impl SyntaxKind {
                 | Self::Continue
                 | Self::Return
                 | Self::Import
+                | Self::Private
                 | Self::Include
                 | Self::As
         )
@@ -431,6 +441,7 @@ impl SyntaxKind {
             Self::SlashEq => "divide-assign operator",
             Self::Dots => "dots",
             Self::Arrow => "arrow",
+            Self::ThinArrow => "thin arrow",
             Self::Root => "root",
             Self::Not => "operator `not`",
             Self::And => "operator `and`",
@@ -450,6 +461,7 @@ impl SyntaxKind {
             Self::Continue => "keyword `continue`",
             Self::Return => "keyword `return`",
             Self::Import => "keyword `import`",
+            Self::Private => "keyword `private`",
             Self::Include => "keyword `include`",
             Self::As => "keyword `as`",
             Self::Code => "code",
@@ -474,6 +486,7 @@ impl SyntaxKind {
             Self::Spread => "spread",
             Self::Closure => "closure",
             Self::Params => "closure parameters",
+            Self::Annotation => "type annotation",
             Self::LetBinding => "`let` expression",
             Self::SetRule => "`set` expression",
             Self::ShowRule => "`show` expression",
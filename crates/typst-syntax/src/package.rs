@@ -41,6 +41,8 @@ pub struct PackageInfo {
     pub entrypoint: EcoString,
     /// The minimum required compiler version for the package.
     pub compiler: Option<PackageVersion>,
+    /// A short description of the package.
+    pub description: Option<EcoString>,
 }
 
 impl PackageManifest {
@@ -642,7 +642,7 @@ fn embedded_code_expr(p: &mut Parser) {
     p.assert(SyntaxKind::Hash);
     p.unskip();
 
-    let stmt = p.at_set(set::STMT);
+    let stmt = p.at_set(set::STMT) || at_private(p);
     let at = p.at_set(set::ATOMIC_CODE_EXPR);
     code_expr_prec(p, true, 0);
 
@@ -736,11 +736,21 @@ fn code_expr_prec(p: &mut Parser, atomic: bool, min_prec: usize) {
 fn code_primary(p: &mut Parser, atomic: bool) {
     let m = p.marker();
     match p.current() {
+        SyntaxKind::Ident if at_private(p) => {
+            if p.peek_on_line() == Some(SyntaxKind::Let) {
+                let_binding(p);
+            } else {
+                module_import(p);
+            }
+        }
         SyntaxKind::Ident => {
             p.eat();
-            if !atomic && p.at(SyntaxKind::Arrow) {
+            if !atomic && (p.at(SyntaxKind::Arrow) || p.at(SyntaxKind::ThinArrow)) {
                 p.wrap(m, SyntaxKind::Params);
-                p.assert(SyntaxKind::Arrow);
+                if p.at(SyntaxKind::ThinArrow) {
+                    annotation(p);
+                }
+                p.expect(SyntaxKind::Arrow);
                 code_expr(p);
                 p.wrap(m, SyntaxKind::Closure);
             }
@@ -838,9 +848,22 @@ fn content_block(p: &mut Parser) {
     p.wrap(m, SyntaxKind::ContentBlock);
 }
 
-/// Parses a let binding: `let x = 1`.
+/// Determines whether the parser is at the contextual `private` keyword.
+///
+/// `private` is only a keyword in front of the statements it modifies, so that
+/// it remains usable as an identifier.
+fn at_private(p: &Parser) -> bool {
+    p.at(SyntaxKind::Ident)
+        && p.current_text() == "private"
+        && matches!(p.peek_on_line(), Some(SyntaxKind::Let | SyntaxKind::Import))
+}
+
+/// Parses a let binding: `let x = 1`, optionally marked as `private`.
 fn let_binding(p: &mut Parser) {
     let m = p.marker();
+    if p.at(SyntaxKind::Ident) {
+        p.convert(SyntaxKind::Private);
+    }
     p.assert(SyntaxKind::Let);
 
     let m2 = p.marker();
@@ -850,6 +873,9 @@ fn let_binding(p: &mut Parser) {
     if p.eat_if(SyntaxKind::Ident) {
         if p.directly_at(SyntaxKind::LeftParen) {
             params(p);
+            if p.at(SyntaxKind::ThinArrow) {
+                annotation(p);
+            }
             closure = true;
         }
     } else {
@@ -964,9 +990,13 @@ fn for_loop(p: &mut Parser) {
     p.wrap(m, SyntaxKind::ForLoop);
 }
 
-/// Parses a module import: `import "utils.typ": a, b, c`.
+/// Parses a module import: `import "utils.typ": a, b, c`, optionally marked
+/// as `private`.
 fn module_import(p: &mut Parser) {
     let m = p.marker();
+    if p.at(SyntaxKind::Ident) {
+        p.convert(SyntaxKind::Private);
+    }
     p.assert(SyntaxKind::Import);
     code_expr(p);
     if p.eat_if(SyntaxKind::As) {
@@ -1061,9 +1091,10 @@ fn expr_with_paren(p: &mut Parser, atomic: bool) {
         return;
     }
 
-    // If, however, '=>' or '=' follows, we must backtrack and reparse as either
-    // a parameter list or a destructuring. To be able to do that, we created a
-    // parser checkpoint before our speculative parse, which we can restore.
+    // If, however, '=>', '->', or '=' follows, we must backtrack and reparse as
+    // either a parameter list or a destructuring. To be able to do that, we
+    // created a parser checkpoint before our speculative parse, which we can
+    // restore.
     //
     // However, naive backtracking has a fatal flaw: It can lead to exponential
     // parsing time if we are constantly getting things wrong in a nested
@@ -1078,10 +1109,13 @@ fn expr_with_paren(p: &mut Parser, atomic: bool) {
     // again, we can then just restore this result. In this way, no
     // parenthesized expression is parsed more than twice, leading to a worst
     // case running time of O(2n).
-    if p.at(SyntaxKind::Arrow) {
+    if p.at(SyntaxKind::Arrow) || p.at(SyntaxKind::ThinArrow) {
         p.restore(checkpoint);
         params(p);
-        p.assert(SyntaxKind::Arrow);
+        if p.at(SyntaxKind::ThinArrow) {
+            annotation(p);
+        }
+        p.expect(SyntaxKind::Arrow);
         code_expr(p);
         p.wrap(m, SyntaxKind::Closure);
     } else if p.at(SyntaxKind::Eq) && kind != SyntaxKind::Parenthesized {
@@ -1324,6 +1358,11 @@ fn param<'s>(p: &mut Parser<'s>, seen: &mut HashSet<&'s str>, sink: &mut bool) {
     let was_at_pat = p.at_set(set::PATTERN);
     pattern(p, false, seen, Some("parameter"));
 
+    // Parses a type annotation: `x as int`.
+    if p.at(SyntaxKind::As) {
+        annotation(p);
+    }
+
     // Parses a named parameter: `thickness: 12pt`.
     if p.eat_if(SyntaxKind::Colon) {
         // Recover from bad parameter name.
@@ -1336,6 +1375,15 @@ fn param<'s>(p: &mut Parser<'s>, seen: &mut HashSet<&'s str>, sink: &mut bool) {
     }
 }
 
+/// Parses a type annotation of a parameter or a closure's return value:
+/// `as int`, `-> content`.
+fn annotation(p: &mut Parser) {
+    let m = p.marker();
+    p.eat();
+    code_expr_prec(p, true, 0);
+    p.wrap(m, SyntaxKind::Annotation);
+}
+
 /// Parses a binding or reassignment pattern.
 fn pattern<'s>(
     p: &mut Parser<'s>,
@@ -1565,6 +1613,20 @@ impl<'s> Parser<'s> {
         self.at(SyntaxKind::Eof)
     }
 
+    /// The kind of the next non-trivia token, if it is on the same line.
+    fn peek_on_line(&self) -> Option<SyntaxKind> {
+        let mut lexer = self.lexer.clone();
+        loop {
+            let kind = lexer.next();
+            if lexer.newline() {
+                return None;
+            }
+            if !kind.is_trivia() {
+                return Some(kind);
+            }
+        }
+    }
+
     fn directly_at(&self, kind: SyntaxKind) -> bool {
         self.current == kind && self.prev_end == self.current_start
     }
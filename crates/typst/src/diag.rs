@@ -138,6 +138,12 @@ pub struct SourceDiagnostic {
     /// Additional hints to the user, indicating how this problem could be avoided
     /// or worked around.
     pub hints: EcoVec<EcoString>,
+    /// Fixes for the problem that can be applied automatically, e.g. by an IDE.
+    pub suggestions: EcoVec<Suggestion>,
+    /// Whether the error stems from a cancellation or an exceeded limit. Such
+    /// errors abort the compilation right away: They can't be caught in Typst
+    /// code and aren't delayed.
+    pub fatal: bool,
 }
 
 /// The severity of a [`SourceDiagnostic`].
@@ -158,6 +164,8 @@ impl SourceDiagnostic {
             trace: eco_vec![],
             message: message.into(),
             hints: eco_vec![],
+            suggestions: eco_vec![],
+            fatal: false,
         }
     }
 
@@ -169,6 +177,8 @@ impl SourceDiagnostic {
             trace: eco_vec![],
             message: message.into(),
             hints: eco_vec![],
+            suggestions: eco_vec![],
+            fatal: false,
         }
     }
 
@@ -188,6 +198,23 @@ impl SourceDiagnostic {
         self.hints.extend(hints);
         self
     }
+
+    /// Adds a single suggestion to the diagnostic.
+    pub fn suggest(&mut self, suggestion: Suggestion) {
+        self.suggestions.push(suggestion);
+    }
+
+    /// Adds a single suggestion to the diagnostic.
+    pub fn with_suggestion(mut self, suggestion: Suggestion) -> Self {
+        self.suggest(suggestion);
+        self
+    }
+
+    /// Marks the diagnostic as [fatal](Self::fatal).
+    pub fn into_fatal(mut self) -> Self {
+        self.fatal = true;
+        self
+    }
 }
 
 impl From<SyntaxError> for SourceDiagnostic {
@@ -198,6 +225,34 @@ impl From<SyntaxError> for SourceDiagnostic {
             message: error.message,
             trace: eco_vec![],
             hints: error.hints,
+            suggestions: eco_vec![],
+            fatal: false,
+        }
+    }
+}
+
+/// A machine-applicable fix for a [`SourceDiagnostic`].
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Suggestion {
+    /// A short description of the fix, e.g. "replace with `calc.pow`".
+    pub message: EcoString,
+    /// The span of the node whose text should be replaced.
+    pub span: Span,
+    /// The text to replace the node with.
+    pub replacement: EcoString,
+}
+
+impl Suggestion {
+    /// Create a new suggestion that replaces the node at `span`.
+    pub fn new(
+        message: impl Into<EcoString>,
+        span: Span,
+        replacement: impl Into<EcoString>,
+    ) -> Self {
+        Self {
+            message: message.into(),
+            span,
+            replacement: replacement.into(),
         }
     }
 }
@@ -1,13 +1,19 @@
 //! Definition of the central compilation context.
 
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::fmt::{self, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use comemo::{Track, Tracked, TrackedMut, Validate};
+use rayon::prelude::*;
 
-use crate::diag::SourceResult;
+use crate::diag::{bail, error, SourceResult};
 use crate::eval::Tracer;
+use crate::foundations::Value;
 use crate::introspection::{Introspector, Locator};
-use crate::syntax::FileId;
+use crate::syntax::{FileId, Span};
 use crate::World;
 
 /// Holds all data needed during compilation.
@@ -29,17 +35,228 @@ impl Engine<'_> {
     /// Performs a fallible operation that does not immediately terminate further
     /// execution. Instead it produces a delayed error that is only promoted to
     /// a fatal one if it remains at the end of the introspection loop.
-    pub fn delayed<F, T>(&mut self, f: F) -> T
+    ///
+    /// [Fatal](crate::diag::SourceDiagnostic::fatal) errors, like those of a
+    /// cancelled compilation, are not delayed but returned right away.
+    pub fn delayed<F, T>(&mut self, f: F) -> SourceResult<T>
     where
         F: FnOnce(&mut Self) -> SourceResult<T>,
         T: Default,
     {
         match f(self) {
-            Ok(value) => value,
+            Ok(value) => Ok(value),
+            Err(errors) if errors.iter().any(|error| error.fatal) => Err(errors),
             Err(errors) => {
                 self.tracer.delay(errors);
-                T::default()
+                Ok(T::default())
+            }
+        }
+    }
+
+    /// Runs tasks in parallel and returns their results in order.
+    ///
+    /// Each task gets its own engine. Its locator is chained to this engine's
+    /// locator and salted with the task's salt. The salts must be distinct and
+    /// should be derived from the task's input rather than its position, so
+    /// that the locations a task produces stay the same when other tasks are
+    /// added or removed. The traces of the tasks are forwarded to this
+    /// engine's tracer in the order of the tasks.
+    pub fn parallelize<T, U, F>(&mut self, tasks: Vec<(u128, T)>, f: F) -> Vec<U>
+    where
+        T: Send,
+        U: Send,
+        F: Fn(&mut Engine, T) -> U + Sync,
+    {
+        let (world, introspector) = (self.world, self.introspector);
+        let route = self.route.track();
+        let locator = self.locator.track();
+        let fork = Tracer::forked(&self.tracer);
+        let results: Vec<_> = tasks
+            .into_par_iter()
+            .map(|(salt, task)| {
+                let mut locator = Locator::chained(locator).with_salt(salt);
+                let mut tracer = fork.clone();
+                let mut engine = Engine {
+                    world,
+                    introspector,
+                    route: Route::extend(route).unnested(),
+                    locator: &mut locator,
+                    tracer: tracer.track_mut(),
+                };
+                (f(&mut engine, task), tracer)
+            })
+            .collect();
+
+        results
+            .into_iter()
+            .map(|(output, tracer)| {
+                tracer.replay(TrackedMut::reborrow_mut(&mut self.tracer));
+                output
+            })
+            .collect()
+    }
+
+    /// The limits the compilation must stay within.
+    pub fn limits(&self) -> ResourceLimits {
+        self.world.library().limits
+    }
+
+    /// Fails if the compilation was cancelled or exceeded its time budget.
+    ///
+    /// This is checked regularly during evaluation so that runaway documents
+    /// can be aborted.
+    pub fn check_interrupt(&self, span: Span) -> SourceResult<()> {
+        if self.route.cancelled() {
+            self.route.interrupt();
+            bail!(error!(span, "compilation was cancelled").into_fatal());
+        }
+
+        if self.route.expired() {
+            self.route.interrupt();
+            bail!(error!(
+                span, "compilation exceeded its time budget";
+                hint: "the document might contain a very expensive computation"
+            )
+            .into_fatal());
+        }
+
+        Ok(())
+    }
+
+    /// Fails if a value that was just produced is larger than the limits
+    /// allow or exhausts the compilation's allocation budget.
+    pub fn check_size(&self, value: &Value, span: Span) -> SourceResult<()> {
+        self.limits().check_size(value, span)?;
+        self.allocate(ResourceLimits::footprint(value), span)
+    }
+
+    /// Charges the given number of bytes to the compilation's allocation
+    /// budget and fails if it is exhausted.
+    pub fn allocate(&self, bytes: usize, span: Span) -> SourceResult<()> {
+        let max = self.limits().max_allocation;
+        if max != usize::MAX && !self.route.allocate(bytes, max) {
+            self.route.interrupt();
+            bail!(error!(
+                span, "evaluation exceeded its allocation budget";
+                hint: "at most {max} bytes may be allocated"
+            )
+            .into_fatal());
+        }
+        Ok(())
+    }
+}
+
+/// Limits on the resources a compilation may use.
+///
+/// The limits are configured through the [`Library`](crate::Library). They
+/// make it possible to safely compile untrusted documents.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct ResourceLimits {
+    /// The maximum function call nesting depth.
+    pub max_call_depth: usize,
+    /// The maximum number of iterations of a single `while` loop.
+    pub max_iterations: usize,
+    /// The maximum length of a string in bytes.
+    pub max_str_len: usize,
+    /// The maximum number of items in an array or dictionary.
+    pub max_collection_len: usize,
+    /// The maximum number of bytes that the strings, arrays, and dictionaries
+    /// produced during evaluation may occupy in total.
+    ///
+    /// This only approximates the actual allocations: Every value produced by
+    /// a literal, an operator, or a function call counts with its full size,
+    /// even if it shares memory with an existing value. Conversely, values
+    /// that are reused from a memoized result, for instance the bindings of a
+    /// module imported in an earlier compilation, are not counted again.
+    pub max_allocation: usize,
+    /// The maximum number of layout iterations for introspections to
+    /// stabilize.
+    pub max_layout_iterations: usize,
+    /// How long a compilation may take in total.
+    ///
+    /// Not supported on WebAssembly, where it is ignored.
+    pub time_budget: Option<Duration>,
+}
+
+impl ResourceLimits {
+    /// Fails if a value is larger than the limits allow.
+    pub fn check_size(&self, value: &Value, span: Span) -> SourceResult<()> {
+        match value {
+            Value::Str(string) => self.check_str_len(string.len(), span),
+            Value::Array(array) => self.check_collection_len("array", array.len(), span),
+            Value::Dict(dict) => {
+                self.check_collection_len("dictionary", dict.len(), span)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails if repeating a string or array `n` times would make it larger
+    /// than the limits allow.
+    ///
+    /// This is checked before repeating so that the memory for a value that
+    /// is too large is never allocated.
+    pub fn check_repeat(&self, value: &Value, n: i64, span: Span) -> SourceResult<()> {
+        let n = usize::try_from(n).unwrap_or(0);
+        match value {
+            Value::Str(string) => {
+                self.check_str_len(string.len().saturating_mul(n), span)
             }
+            Value::Array(array) => {
+                self.check_collection_len("array", array.len().saturating_mul(n), span)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fails if a string with the given length in bytes is too large.
+    pub fn check_str_len(&self, len: usize, span: Span) -> SourceResult<()> {
+        check_len("string", len, self.max_str_len, span)
+    }
+
+    /// Fails if an array or dictionary with the given number of items is too
+    /// large.
+    pub fn check_collection_len(
+        &self,
+        kind: &str,
+        len: usize,
+        span: Span,
+    ) -> SourceResult<()> {
+        check_len(kind, len, self.max_collection_len, span)
+    }
+
+    /// The number of bytes a value occupies for the purpose of the
+    /// [allocation budget](Self::max_allocation).
+    pub fn footprint(value: &Value) -> usize {
+        let item = std::mem::size_of::<Value>();
+        match value {
+            Value::Str(string) => string.len(),
+            Value::Array(array) => array.len().saturating_mul(item),
+            Value::Dict(dict) => dict.len().saturating_mul(2 * item),
+            _ => 0,
+        }
+    }
+}
+
+/// Fails if a value of the given kind and length exceeds the maximum.
+fn check_len(kind: &str, len: usize, max: usize, span: Span) -> SourceResult<()> {
+    if len > max {
+        bail!(error!(span, "{kind} is too large"; hint: "the maximum size is {max}")
+            .into_fatal());
+    }
+    Ok(())
+}
+
+impl Default for ResourceLimits {
+    fn default() -> Self {
+        Self {
+            max_call_depth: Route::MAX_CALL_DEPTH,
+            max_iterations: 10_000,
+            max_str_len: usize::MAX,
+            max_collection_len: usize::MAX,
+            max_allocation: usize::MAX,
+            max_layout_iterations: 5,
+            time_budget: None,
         }
     }
 }
@@ -65,6 +282,8 @@ pub struct Route<'a> {
     /// would prevent cache reuse of some computation at different,
     /// non-exceeding depths).
     upper: AtomicUsize,
+    /// The compilation's budget, shared by all segments of the route.
+    budget: Budget,
 }
 
 /// The maximum nesting depths. They are different so that even if show rule and
@@ -78,7 +297,8 @@ impl Route<'_> {
     /// The maximum layout nesting depth.
     pub const MAX_LAYOUT_DEPTH: usize = 72;
 
-    /// The maximum function call nesting depth.
+    /// The default maximum function call nesting depth. Can be configured
+    /// through [`ResourceLimits`].
     pub const MAX_CALL_DEPTH: usize = 80;
 }
 
@@ -90,9 +310,29 @@ impl<'a> Route<'a> {
             outer: None,
             len: 0,
             upper: AtomicUsize::new(0),
+            budget: Budget::default(),
         }
     }
 
+    /// Create a new, empty route for a compilation with the given time
+    /// budget.
+    pub fn with_budget(budget: Option<Duration>) -> Self {
+        if cfg!(target_arch = "wasm32") {
+            return Self::root();
+        }
+
+        let mut route = Self::root();
+        route.budget.deadline =
+            budget.and_then(|budget| Instant::now().checked_add(budget));
+        route
+    }
+
+    /// Make the compilation cancellable through the given token.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.budget.cancellation = Some(token);
+        self
+    }
+
     /// Extend the route with another segment with a default length of 1.
     pub fn extend(outer: Tracked<'a, Self>) -> Self {
         Route {
@@ -100,6 +340,7 @@ impl<'a> Route<'a> {
             id: None,
             len: 1,
             upper: AtomicUsize::new(usize::MAX),
+            budget: outer.budget(),
         }
     }
 
@@ -133,6 +374,41 @@ impl<'a> Route<'a> {
     pub fn decrease(&mut self) {
         self.len -= 1;
     }
+
+    /// Whether the compilation's time budget is used up.
+    pub fn expired(&self) -> bool {
+        self.budget
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Whether the compilation was cancelled through its token.
+    pub fn cancelled(&self) -> bool {
+        self.budget
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Charges the given number of bytes to the allocation budget and returns
+    /// whether the total stays within `max`.
+    pub fn allocate(&self, bytes: usize, max: usize) -> bool {
+        let before = self.budget.allocated.fetch_add(bytes, Ordering::Relaxed);
+        before.saturating_add(bytes) <= max
+    }
+
+    /// Marks the compilation as interrupted.
+    ///
+    /// The checks above aren't tracked because their results change while a
+    /// memoized function runs. Instead, this records that the current
+    /// computation observed an interruption, so that its result is not reused
+    /// by a later compilation that isn't interrupted.
+    pub fn interrupt(&self) {
+        self.budget.interrupted.store(true, Ordering::Relaxed);
+        if let Some(outer) = self.outer {
+            outer.interrupted();
+        }
+    }
 }
 
 #[comemo::track]
@@ -142,6 +418,20 @@ impl<'a> Route<'a> {
         self.id == Some(id) || self.outer.is_some_and(|outer| outer.contains(id))
     }
 
+    /// Whether the compilation was interrupted. Once this is true, it stays
+    /// true for the rest of the compilation.
+    pub fn interrupted(&self) -> bool {
+        match self.outer {
+            Some(outer) => outer.interrupted(),
+            None => self.budget.interrupted.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The compilation's budget.
+    fn budget(&self) -> Budget {
+        self.budget.clone()
+    }
+
     /// Whether the route's depth is less than or equal to the given depth.
     pub fn within(&self, depth: usize) -> bool {
         use Ordering::Relaxed;
@@ -182,6 +472,133 @@ impl Clone for Route<'_> {
             // The ordering doesn't really matter since it's the upper bound
             // is only an optimization.
             upper: AtomicUsize::new(self.upper.load(Ordering::Relaxed)),
+            budget: self.budget.clone(),
         }
     }
 }
+
+/// The limits on a compilation's resources that are enforced while it runs.
+#[derive(Debug, Default, Clone)]
+struct Budget {
+    /// When the compilation must be finished.
+    deadline: Option<Instant>,
+    /// A token through which the compilation can be cancelled.
+    cancellation: Option<CancellationToken>,
+    /// How many bytes were charged to the allocation budget.
+    allocated: Arc<AtomicUsize>,
+    /// Whether the compilation was interrupted by one of the above.
+    interrupted: Arc<AtomicBool>,
+}
+
+impl Hash for Budget {
+    fn hash<H: Hasher>(&self, _: &mut H) {
+        // The budget is the same for all segments of a route and doesn't
+        // influence the output of a computation unless it interrupts it,
+        // which `Route::interrupted` tracks separately. Hashing it as nothing
+        // thus keeps memoized results reusable across compilations.
+    }
+}
+
+/// A token through which a running compilation can be cancelled, for instance
+/// from another thread.
+///
+/// Cloning the token yields a handle to the same cancellation state.
+#[derive(Debug, Default, Clone)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a new token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel all compilations using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// The compilation was aborted because its [`CancellationToken`] was
+/// cancelled.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str("compilation was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::foundations::{array, dict, IntoValue};
+
+    #[test]
+    fn test_limits_check_size() {
+        let limits = ResourceLimits {
+            max_str_len: 3,
+            max_collection_len: 2,
+            ..ResourceLimits::default()
+        };
+        let span = Span::detached();
+        assert!(limits.check_size(&"abc".into_value(), span).is_ok());
+        assert!(limits.check_size(&array![1, 2].into_value(), span).is_ok());
+        assert!(limits.check_size(&dict!["a" => 1].into_value(), span).is_ok());
+        assert!(limits.check_size(&10.into_value(), span).is_ok());
+
+        let errors = limits.check_size(&"abcd".into_value(), span).unwrap_err();
+        assert_eq!(errors[0].message, "string is too large");
+        assert_eq!(errors[0].hints[0], "the maximum size is 3");
+        assert!(errors[0].fatal);
+
+        let errors = limits.check_size(&array![1, 2, 3].into_value(), span).unwrap_err();
+        assert_eq!(errors[0].message, "array is too large");
+
+        let dict = dict!["a" => 1, "b" => 2, "c" => 3].into_value();
+        let errors = limits.check_size(&dict, span).unwrap_err();
+        assert_eq!(errors[0].message, "dictionary is too large");
+    }
+
+    #[test]
+    fn test_limits_check_repeat() {
+        let limits = ResourceLimits {
+            max_str_len: 4,
+            max_collection_len: 4,
+            ..ResourceLimits::default()
+        };
+        let span = Span::detached();
+        assert!(limits.check_repeat(&"ab".into_value(), 2, span).is_ok());
+        assert!(limits.check_repeat(&"ab".into_value(), 3, span).is_err());
+        assert!(limits.check_repeat(&"ab".into_value(), i64::MAX, span).is_err());
+        assert!(limits.check_repeat(&"ab".into_value(), -1, span).is_ok());
+        assert!(limits.check_repeat(&array![1].into_value(), 4, span).is_ok());
+        assert!(limits.check_repeat(&array![1].into_value(), 5, span).is_err());
+        assert!(limits.check_repeat(&1.into_value(), i64::MAX, span).is_ok());
+    }
+
+    #[test]
+    fn test_route_allocate() {
+        let item = std::mem::size_of::<Value>();
+        assert_eq!(ResourceLimits::footprint(&"abc".into_value()), 3);
+        assert_eq!(ResourceLimits::footprint(&array![1, 2].into_value()), 2 * item);
+        assert_eq!(ResourceLimits::footprint(&1.into_value()), 0);
+
+        let route = Route::root();
+        assert!(route.allocate(60, 100));
+        assert!(route.clone().allocate(40, 100));
+        assert!(!route.allocate(1, 100));
+
+        // Nested segments charge the root's budget.
+        let nested = Route::extend(route.track());
+        assert!(!nested.allocate(0, 100));
+        assert!(Route::root().allocate(100, 100));
+    }
+}
@@ -17,8 +17,14 @@ impl Eval for ast::LetBinding<'_> {
             return Ok(Value::None);
         }
 
+        let private = self.is_private();
         match self.kind() {
-            ast::LetBindingKind::Normal(pattern) => destructure(vm, pattern, value)?,
+            ast::LetBindingKind::Normal(pattern) => {
+                destructure_as(vm, pattern, value, private)?
+            }
+            ast::LetBindingKind::Closure(ident) if private => {
+                vm.define_private(ident, value)
+            }
             ast::LetBindingKind::Closure(ident) => vm.define(ident, value),
         }
 
@@ -45,8 +51,22 @@ pub(crate) fn destructure(
     vm: &mut Vm,
     pattern: ast::Pattern,
     value: Value,
+) -> SourceResult<()> {
+    destructure_as(vm, pattern, value, false)
+}
+
+/// Destructures a value into a pattern, optionally defining private bindings.
+fn destructure_as(
+    vm: &mut Vm,
+    pattern: ast::Pattern,
+    value: Value,
+    private: bool,
 ) -> SourceResult<()> {
     destructure_impl(vm, pattern, value, &mut |vm, expr, value| match expr {
+        ast::Expr::Ident(ident) if private => {
+            vm.define_private(ident, value);
+            Ok(())
+        }
         ast::Expr::Ident(ident) => {
             vm.define(ident, value);
             Ok(())
@@ -1,12 +1,16 @@
 use comemo::{Tracked, TrackedMut};
 use ecow::{eco_format, EcoVec};
 
-use crate::diag::{bail, error, At, HintedStrResult, SourceResult, Trace, Tracepoint};
+use crate::diag::{
+    bail, error, At, Hint, HintedStrResult, SourceDiagnostic, SourceResult, Suggestion,
+    Trace, Tracepoint,
+};
 use crate::engine::Engine;
 use crate::eval::{Access, Eval, FlowEvent, Route, Tracer, Vm};
 use crate::foundations::{
-    call_method_mut, is_mutating_method, Arg, Args, Bytes, Capturer, Closure, Content,
-    Context, Func, IntoValue, NativeElement, Scope, Scopes, Value,
+    call_method_mut, check_types, is_mutating_method, parse_types, Arg, Args, Bytes,
+    Capturer, Closure, ClosureParam, Content, Context, Func, IntoValue, NativeElement,
+    Scope, Scopes, Type, Value,
 };
 use crate::introspection::{Introspector, Locator};
 use crate::math::{Accent, AccentElem, LrElem};
@@ -14,7 +18,7 @@ use crate::symbols::Symbol;
 use crate::syntax::ast::{self, AstNode};
 use crate::syntax::{Span, Spanned, SyntaxNode};
 use crate::text::TextElem;
-use crate::util::LazyHash;
+use crate::util::{closest, LazyHash};
 use crate::World;
 
 impl Eval for ast::FuncCall<'_> {
@@ -28,10 +32,12 @@ impl Eval for ast::FuncCall<'_> {
         let args = self.args();
         let trailing_comma = args.trailing_comma();
 
-        if !vm.engine.route.within(Route::MAX_CALL_DEPTH) {
-            bail!(span, "maximum function call depth exceeded");
+        if !vm.engine.route.within(vm.engine.limits().max_call_depth) {
+            bail!(error!(span, "maximum function call depth exceeded").into_fatal());
         }
 
+        vm.engine.check_interrupt(span)?;
+
         // Try to evaluate as a call to an associated function or field.
         let (callee, mut args) = if let ast::Expr::FieldAccess(access) = callee {
             let target = access.target();
@@ -166,9 +172,12 @@ impl Eval for ast::FuncCall<'_> {
         let callee = callee.cast::<Func>().at(callee_span)?;
         let point = || Tracepoint::Call(callee.name().map(Into::into));
         let f = || {
-            callee
+            let output = callee
                 .call(&mut vm.engine, vm.context, args)
                 .trace(vm.world(), point, span)
+                .map_err(|errors| suggest_param_names(&callee, self.args(), errors))?;
+            vm.engine.check_size(&output, span)?;
+            Ok(output)
         };
 
         // Stacker is broken on WASM.
@@ -180,6 +189,42 @@ impl Eval for ast::FuncCall<'_> {
     }
 }
 
+/// Suggest the closest parameter names for misspelled named arguments.
+///
+/// An error that points at a named argument whose name the function doesn't
+/// declare can only stem from the argument being unexpected.
+#[cold]
+fn suggest_param_names(
+    func: &Func,
+    args: ast::Args,
+    mut errors: EcoVec<SourceDiagnostic>,
+) -> EcoVec<SourceDiagnostic> {
+    let Some(params) = func.params() else { return errors };
+    for error in errors.make_mut() {
+        let Some(named) = args.items().find_map(|arg| match arg {
+            ast::Arg::Named(named) if named.span() == error.span => Some(named),
+            _ => None,
+        }) else {
+            continue;
+        };
+
+        let name = named.name();
+        if params.iter().any(|param| param.named && param.name == name.as_str()) {
+            continue;
+        }
+
+        let names = params.iter().filter(|param| param.named).map(|param| param.name);
+        if let Some(param) = closest(&name, names) {
+            error.suggest(Suggestion::new(
+                eco_format!("replace with `{param}`"),
+                name.span(),
+                param,
+            ));
+        }
+    }
+    errors
+}
+
 impl Eval for ast::Args<'_> {
     type Output = Args;
 
@@ -236,14 +281,29 @@ impl Eval for ast::Closure<'_> {
     type Output = Value;
 
     fn eval(self, vm: &mut Vm) -> SourceResult<Self::Output> {
-        // Evaluate default values of named parameters.
-        let mut defaults = Vec::new();
-        for param in self.params().children() {
-            if let ast::Param::Named(named) = param {
-                defaults.push(named.expr().eval(vm)?);
-            }
+        // Evaluate type annotations and default values of named parameters.
+        let mut params = Vec::new();
+        for (param, annotation) in self.params().annotated() {
+            let types = match annotation {
+                Some(annotation) => eval_annotation(vm, annotation)?,
+                None => vec![],
+            };
+            let default = match param {
+                ast::Param::Named(named) => {
+                    let expr = named.expr();
+                    let default = expr.eval(vm)?;
+                    Some(check_types(&types, default).at(expr.span())?)
+                }
+                _ => None,
+            };
+            params.push(ClosureParam { types, default });
         }
 
+        let returns = match self.returns() {
+            Some(annotation) => eval_annotation(vm, annotation)?,
+            None => vec![],
+        };
+
         // Collect captured variables.
         let captured = {
             let mut visitor = CapturesVisitor::new(Some(&vm.scopes), Capturer::Function);
@@ -254,19 +314,26 @@ impl Eval for ast::Closure<'_> {
         // Define the closure.
         let closure = Closure {
             node: self.to_untyped().clone(),
-            defaults,
+            params,
             captured,
             num_pos_params: self
                 .params()
                 .children()
                 .filter(|p| matches!(p, ast::Param::Pos(_)))
                 .count(),
+            returns,
         };
 
         Ok(Value::Func(Func::from(closure).spanned(self.params().span())))
     }
 }
 
+/// Evaluate a type annotation to the types it accepts.
+fn eval_annotation(vm: &mut Vm, annotation: ast::Annotation) -> SourceResult<Vec<Type>> {
+    let expr = annotation.types();
+    parse_types(expr.eval(vm)?).at(expr.span())
+}
+
 /// Call the function in the context with the arguments.
 #[comemo::memoize]
 #[allow(clippy::too_many_arguments)]
@@ -281,7 +348,8 @@ pub(crate) fn call_closure(
     context: Tracked<Context>,
     mut args: Args,
 ) -> SourceResult<Value> {
-    let (name, params, body) = match closure.node.cast::<ast::Closure>() {
+    let node = closure.node.cast::<ast::Closure>();
+    let (name, params, body) = match node {
         Some(node) => (node.name(), node.params(), node.body()),
         None => (None, ast::Params::default(), closure.node.cast().unwrap()),
     };
@@ -314,19 +382,19 @@ pub(crate) fn call_closure(
 
     let mut sink = None;
     let mut sink_pos_values = None;
-    let mut defaults = closure.defaults.iter();
-    for p in params.children() {
+    for (p, param) in params.children().zip(&closure.params) {
+        let accepted = param.types.as_slice();
         match p {
             ast::Param::Pos(pattern) => match pattern {
                 ast::Pattern::Normal(ast::Expr::Ident(ident)) => {
-                    vm.define(ident, args.expect::<Value>(&ident)?)
+                    let Spanned { v, span } = args.expect::<Spanned<Value>>(&ident)?;
+                    vm.define(ident, check_types(accepted, v).at(span)?)
                 }
                 pattern => {
-                    crate::eval::destructure(
-                        &mut vm,
-                        pattern,
-                        args.expect::<Value>("pattern parameter")?,
-                    )?;
+                    let Spanned { v, span } =
+                        args.expect::<Spanned<Value>>("pattern parameter")?;
+                    let value = check_types(accepted, v).at(span)?;
+                    crate::eval::destructure(&mut vm, pattern, value)?;
                 }
             },
             ast::Param::Spread(spread) => {
@@ -337,9 +405,10 @@ pub(crate) fn call_closure(
             }
             ast::Param::Named(named) => {
                 let name = named.name();
-                let default = defaults.next().unwrap();
-                let value =
-                    args.named::<Value>(&name)?.unwrap_or_else(|| default.clone());
+                let value = match args.named::<Spanned<Value>>(&name)? {
+                    Some(Spanned { v, span }) => check_types(accepted, v).at(span)?,
+                    None => param.default.clone().unwrap(),
+                };
                 vm.define(name, value);
             }
         }
@@ -360,15 +429,19 @@ pub(crate) fn call_closure(
     args.finish()?;
 
     // Handle control flow.
-    let output = body.eval(&mut vm)?;
+    let mut output = body.eval(&mut vm)?;
     match vm.flow {
-        Some(FlowEvent::Return(_, Some(explicit))) => return Ok(explicit),
+        Some(FlowEvent::Return(_, Some(explicit))) => output = explicit,
         Some(FlowEvent::Return(_, None)) => {}
         Some(flow) => bail!(flow.forbidden()),
         None => {}
     }
 
-    Ok(output)
+    // Check the returned value against the return type annotation.
+    let span = node.and_then(ast::Closure::returns).map_or(body.span(), |a| a.span());
+    check_types(&closure.returns, output)
+        .hint("the returned value must match the return type annotation")
+        .at(span)
 }
 
 fn in_math(expr: ast::Expr) -> bool {
@@ -433,12 +506,19 @@ impl<'a> CapturesVisitor<'a> {
             // body is evaluated. Care must be taken so that the default values
             // of named parameters cannot access previous parameter bindings.
             Some(ast::Expr::Closure(expr)) => {
-                for param in expr.params().children() {
+                for (param, annotation) in expr.params().annotated() {
+                    if let Some(annotation) = annotation {
+                        self.visit(annotation.types().to_untyped());
+                    }
                     if let ast::Param::Named(named) = param {
                         self.visit(named.expr().to_untyped());
                     }
                 }
 
+                if let Some(annotation) = expr.returns() {
+                    self.visit(annotation.types().to_untyped());
+                }
+
                 self.internal.enter();
                 if let Some(name) = expr.name() {
                     self.bind(name);
@@ -1,11 +1,15 @@
-use ecow::{eco_vec, EcoVec};
+use ecow::{eco_format, eco_vec, EcoVec};
 
-use crate::diag::{bail, error, At, SourceDiagnostic, SourceResult};
+use crate::diag::{
+    bail, error, At, HintedString, SourceDiagnostic, SourceResult, Suggestion,
+};
 use crate::eval::{ops, CapturesVisitor, Eval, Vm};
 use crate::foundations::{
-    Array, Capturer, Closure, Content, ContextElem, Dict, Func, NativeElement, Str, Value,
+    Array, Capturer, Closure, Content, ContextElem, Dict, Func, NativeElement, Scopes,
+    Str, Value,
 };
 use crate::syntax::ast::{self, AstNode};
+use crate::syntax::Span;
 
 impl Eval for ast::Code<'_> {
     type Output = Value;
@@ -147,8 +151,38 @@ impl Eval for ast::Ident<'_> {
     type Output = Value;
 
     fn eval(self, vm: &mut Vm) -> SourceResult<Self::Output> {
-        vm.scopes.get(&self).cloned().at(self.span())
+        vm.scopes.get(&self).cloned().map_err(|error| {
+            eco_vec![unknown_variable(&vm.scopes, error, self.span(), &self, false)]
+        })
+    }
+}
+
+/// Turn an unknown variable error into a diagnostic with suggested fixes.
+#[cold]
+pub(crate) fn unknown_variable(
+    scopes: &Scopes,
+    error: HintedString,
+    span: Span,
+    var: &str,
+    in_math: bool,
+) -> SourceDiagnostic {
+    let mut diag = SourceDiagnostic::error(span, error.message).with_hints(error.hints);
+
+    if in_math && matches!(var, "none" | "auto" | "false" | "true") {
+        diag.suggest(Suggestion::new("add a hash", span, eco_format!("#{var}")));
+    } else if !in_math && var.contains('-') && !var.split('-').any(str::is_empty) {
+        diag.suggest(Suggestion::new(
+            "add spaces around the minus sign",
+            span,
+            var.replace('-', " - "),
+        ));
     }
+
+    for name in scopes.qualified(var, in_math) {
+        diag.suggest(Suggestion::new(eco_format!("replace with `{name}`"), span, name));
+    }
+
+    diag
 }
 
 impl Eval for ast::None<'_> {
@@ -212,6 +246,7 @@ impl Eval for ast::Array<'_> {
 
     fn eval(self, vm: &mut Vm) -> SourceResult<Self::Output> {
         let items = self.items();
+        let limits = vm.engine.limits();
 
         let mut vec = EcoVec::with_capacity(items.size_hint().0);
         for item in items {
@@ -219,13 +254,19 @@ impl Eval for ast::Array<'_> {
                 ast::ArrayItem::Pos(expr) => vec.push(expr.eval(vm)?),
                 ast::ArrayItem::Spread(spread) => match spread.expr().eval(vm)? {
                     Value::None => {}
-                    Value::Array(array) => vec.extend(array.into_iter()),
+                    Value::Array(array) => {
+                        let len = vec.len().saturating_add(array.len());
+                        limits.check_collection_len("array", len, spread.span())?;
+                        vec.extend(array.into_iter());
+                    }
                     v => bail!(spread.span(), "cannot spread {} into array", v.ty()),
                 },
             }
         }
 
-        Ok(vec.into())
+        let array = Array::from(vec);
+        vm.engine.check_size(&Value::Array(array.clone()), self.span())?;
+        Ok(array)
     }
 }
 
@@ -233,6 +274,7 @@ impl Eval for ast::Dict<'_> {
     type Output = Dict;
 
     fn eval(self, vm: &mut Vm) -> SourceResult<Self::Output> {
+        let limits = vm.engine.limits();
         let mut map = indexmap::IndexMap::new();
         let mut invalid_keys = eco_vec![];
 
@@ -253,7 +295,11 @@ impl Eval for ast::Dict<'_> {
                 }
                 ast::DictItem::Spread(spread) => match spread.expr().eval(vm)? {
                     Value::None => {}
-                    Value::Dict(dict) => map.extend(dict.into_iter()),
+                    Value::Dict(dict) => {
+                        let len = map.len().saturating_add(dict.len());
+                        limits.check_collection_len("dictionary", len, spread.span())?;
+                        map.extend(dict.into_iter());
+                    }
                     v => bail!(spread.span(), "cannot spread {} into dictionary", v.ty()),
                 },
             }
@@ -263,7 +309,9 @@ impl Eval for ast::Dict<'_> {
             return Err(invalid_keys);
         }
 
-        Ok(map.into())
+        let dict = Dict::from(map);
+        vm.engine.check_size(&Value::Dict(dict.clone()), self.span())?;
+        Ok(dict)
     }
 }
 
@@ -347,9 +395,10 @@ impl Eval for ast::Contextual<'_> {
         // Define the closure.
         let closure = Closure {
             node: self.body().to_untyped().clone(),
-            defaults: vec![],
+            params: vec![],
             captured,
             num_pos_params: 0,
+            returns: vec![],
         };
 
         let func = Func::from(closure).spanned(body.span());
@@ -2,13 +2,10 @@ use unicode_segmentation::UnicodeSegmentation;
 
 use crate::diag::{bail, error, At, SourceDiagnostic, SourceResult};
 use crate::eval::{destructure, ops, Eval, Vm};
-use crate::foundations::{IntoValue, Value};
+use crate::foundations::{IntoValue, Iter, Value};
 use crate::syntax::ast::{self, AstNode};
 use crate::syntax::{Span, SyntaxKind, SyntaxNode};
 
-/// The maximum number of loop iterations.
-const MAX_ITERATIONS: usize = 10_000;
-
 /// A control flow event that occurred during evaluation.
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum FlowEvent {
@@ -61,6 +58,7 @@ impl Eval for ast::WhileLoop<'_> {
         let flow = vm.flow.take();
         let mut output = Value::None;
         let mut i = 0;
+        let max = vm.engine.limits().max_iterations;
 
         let condition = self.condition();
         let body = self.body();
@@ -71,10 +69,12 @@ impl Eval for ast::WhileLoop<'_> {
                 && !can_diverge(body.to_untyped())
             {
                 bail!(condition.span(), "condition is always true");
-            } else if i >= MAX_ITERATIONS {
-                bail!(self.span(), "loop seems to be infinite");
+            } else if i >= max {
+                bail!(error!(self.span(), "loop seems to be infinite").into_fatal());
             }
 
+            vm.engine.check_interrupt(self.span())?;
+
             let value = body.eval(vm)?;
             output = ops::join(output, value).at(body.span())?;
 
@@ -109,10 +109,15 @@ impl Eval for ast::ForLoop<'_> {
 
         macro_rules! iter {
             (for $pat:ident in $iterable:expr) => {{
+                #[allow(unused_parens)]
+                let mut iter = ($iterable).into_iter();
+                iter!(for $pat in next iter.next());
+            }};
+            (for $pat:ident in next $next:expr) => {{
                 vm.scopes.enter();
 
-                #[allow(unused_parens)]
-                for value in $iterable {
+                while let Some(value) = $next {
+                    vm.engine.check_interrupt(self.span())?;
                     destructure(vm, $pat, value.into_value())?;
 
                     let body = self.body();
@@ -148,6 +153,12 @@ impl Eval for ast::ForLoop<'_> {
                 // Iterate over key-value pairs of dict.
                 iter!(for pattern in dict.iter());
             }
+            (_, Value::Dyn(dynamic)) if dynamic.is::<Iter>() => {
+                // Pull values from an iterator on demand.
+                let iter = dynamic.downcast::<Iter>().unwrap();
+                let mut cursor = iter.cursor()?;
+                iter!(for pattern in next cursor.next(&mut vm.engine, vm.context)?);
+            }
             (Pattern::Normal(_) | Pattern::Placeholder(_), Value::Str(str)) => {
                 // Iterate over graphemes of string.
                 iter!(for pattern in str.as_str().graphemes(true));
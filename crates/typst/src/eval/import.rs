@@ -3,7 +3,7 @@ use ecow::{eco_format, eco_vec, EcoString};
 
 use crate::diag::{bail, error, warning, At, FileError, SourceResult, Trace, Tracepoint};
 use crate::eval::{eval, Eval, Vm};
-use crate::foundations::{Content, Module, Value};
+use crate::foundations::{Content, Import, Module, Value};
 use crate::syntax::ast::{self, AstNode};
 use crate::syntax::package::{PackageManifest, PackageSpec};
 use crate::syntax::{FileId, Span, VirtualPath};
@@ -18,6 +18,7 @@ impl Eval for ast::ModuleImport<'_> {
         let mut source = source.eval(vm)?;
         let new_name = self.new_name();
         let imports = self.imports();
+        let private = self.is_private();
 
         match &source {
             Value::Func(func) => {
@@ -43,7 +44,8 @@ impl Eval for ast::ModuleImport<'_> {
             }
 
             // Define renamed module on the scope.
-            vm.scopes.top.define(new_name.as_str(), source.clone());
+            let import = Import { private, glob: false };
+            vm.scopes.top.define_import(new_name.as_str(), source.clone(), import);
         }
 
         let scope = source.scope().unwrap();
@@ -52,12 +54,26 @@ impl Eval for ast::ModuleImport<'_> {
                 // Only import here if there is no rename.
                 if new_name.is_none() {
                     let name: EcoString = source.name().unwrap().into();
-                    vm.scopes.top.define(name, source);
+                    let import = Import { private, glob: false };
+                    vm.scopes.top.define_import(name, source, import);
                 }
             }
             Some(ast::Imports::Wildcard) => {
                 for (var, value) in scope.iter() {
-                    vm.scopes.top.define(var.clone(), value.clone());
+                    // Warn if an earlier wildcard import brought in a
+                    // different definition with the same name.
+                    let top = &vm.scopes.top;
+                    if top.is_glob_import(var) && top.get(var) != Some(value) {
+                        vm.engine.tracer.warn(warning!(
+                            source_span,
+                            "`{var}` is imported by multiple wildcard imports";
+                            hint: "this import shadows the earlier definition of `{var}`";
+                            hint: "import `{var}` explicitly to choose which one to use",
+                        ));
+                    }
+
+                    let import = Import { private, glob: true };
+                    vm.scopes.top.define_import(var.clone(), value.clone(), import);
                 }
             }
             Some(ast::Imports::Items(items)) => {
@@ -77,7 +93,8 @@ impl Eval for ast::ModuleImport<'_> {
                             }
                         }
 
-                        vm.define(item.bound_name(), value.clone());
+                        let import = Import { private, glob: false };
+                        vm.define_import(item.bound_name(), value.clone(), import);
                     } else {
                         errors.push(error!(original_ident.span(), "unresolved import"));
                     }
@@ -1,7 +1,7 @@
-use ecow::eco_format;
+use ecow::{eco_format, eco_vec};
 
-use crate::diag::{At, SourceResult};
-use crate::eval::{Eval, Vm};
+use crate::diag::SourceResult;
+use crate::eval::{unknown_variable, Eval, Vm};
 use crate::foundations::{Content, NativeElement, Value};
 use crate::math::{AlignPointElem, AttachElem, FracElem, LrElem, PrimesElem, RootElem};
 use crate::syntax::ast::{self, AstNode};
@@ -22,7 +22,9 @@ impl Eval for ast::MathIdent<'_> {
     type Output = Value;
 
     fn eval(self, vm: &mut Vm) -> SourceResult<Self::Output> {
-        vm.scopes.get_in_math(&self).cloned().at(self.span())
+        vm.scopes.get_in_math(&self).cloned().map_err(|error| {
+            eco_vec![unknown_variable(&vm.scopes, error, self.span(), &self, true)]
+        })
     }
 }
 
@@ -21,6 +21,7 @@ pub use self::vm::*;
 
 pub(crate) use self::access::*;
 pub(crate) use self::binding::*;
+pub(crate) use self::code::*;
 pub(crate) use self::flow::*;
 
 use comemo::{Track, Tracked, TrackedMut};
@@ -88,7 +89,9 @@ pub fn eval(
         .unwrap_or_default()
         .to_string_lossy();
 
-    Ok(Module::new(name, vm.scopes.top).with_content(output))
+    Ok(Module::new(name, vm.scopes.top.exports())
+        .with_content(output)
+        .with_file(id))
 }
 
 /// Evaluate a string as code and return the resulting value.
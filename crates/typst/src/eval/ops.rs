@@ -5,6 +5,7 @@ use std::cmp::Ordering;
 use ecow::eco_format;
 
 use crate::diag::{bail, At, SourceResult, StrResult};
+use crate::engine::ResourceLimits;
 use crate::eval::{access_dict, Access, Eval, Vm};
 use crate::foundations::{format_str, Datetime, IntoValue, Regex, Repr, Value};
 use crate::layout::{Alignment, Length, Rel};
@@ -71,7 +72,10 @@ fn apply_binary(
     }
 
     let rhs = binary.rhs().eval(vm)?;
-    op(lhs, rhs).at(binary.span())
+    check_repeat(binary, vm.engine.limits(), &lhs, &rhs)?;
+    let output = op(lhs, rhs).at(binary.span())?;
+    vm.engine.check_size(&output, binary.span())?;
+    Ok(output)
 }
 
 /// Apply an assignment operation.
@@ -93,12 +97,39 @@ fn apply_assignment(
         }
     }
 
+    let limits = vm.engine.limits();
     let location = binary.lhs().access(vm)?;
     let lhs = std::mem::take(&mut *location);
-    *location = op(lhs, rhs).at(binary.span())?;
+    check_repeat(binary, limits, &lhs, &rhs)?;
+    let before = ResourceLimits::footprint(&lhs);
+    let output = op(lhs, rhs).at(binary.span())?;
+    limits.check_size(&output, binary.span())?;
+    let growth = ResourceLimits::footprint(&output).saturating_sub(before);
+    *location = output;
+    vm.engine.allocate(growth, binary.span())?;
     Ok(Value::None)
 }
 
+/// Fails if multiplying a string or array with an integer would produce a
+/// value that is larger than the limits allow, before allocating it.
+fn check_repeat(
+    binary: ast::Binary,
+    limits: ResourceLimits,
+    lhs: &Value,
+    rhs: &Value,
+) -> SourceResult<()> {
+    if !matches!(binary.op(), ast::BinOp::Mul | ast::BinOp::MulAssign) {
+        return Ok(());
+    }
+
+    match (lhs, rhs) {
+        (value, Value::Int(n)) | (Value::Int(n), value) => {
+            limits.check_repeat(value, *n, binary.span())
+        }
+        _ => Ok(()),
+    }
+}
+
 /// Bail with a type mismatch error.
 macro_rules! mismatch {
     ($fmt:expr, $($value:expr),* $(,)?) => {
@@ -1,5 +1,6 @@
 use std::collections::HashSet;
 
+use comemo::TrackedMut;
 use ecow::EcoVec;
 
 use crate::diag::SourceDiagnostic;
@@ -46,6 +47,30 @@ impl Tracer {
     pub fn values(self) -> EcoVec<(Value, Option<Styles>)> {
         self.values
     }
+
+    /// Create a new tracer that inspects the same span as `outer`.
+    ///
+    /// Such a tracer can be used for work that happens independently of the
+    /// outer tracer's other work. Its results are then forwarded to the outer
+    /// tracer with [`replay`](Self::replay).
+    pub fn forked(outer: &TrackedMut<Self>) -> Self {
+        Self {
+            inspected: outer.inspected_span(),
+            ..Self::default()
+        }
+    }
+
+    /// Forward all delayed errors, warnings, and values that were traced by
+    /// this tracer to another one.
+    pub fn replay(self, mut target: TrackedMut<Self>) {
+        target.delay(self.delayed);
+        for warning in self.warnings {
+            target.warn(warning);
+        }
+        for (value, styles) in self.values {
+            target.value(value, styles);
+        }
+    }
 }
 
 #[comemo::track]
@@ -73,6 +98,11 @@ impl Tracer {
         }
     }
 
+    /// The inspected span, regardless of the file it belongs to.
+    pub fn inspected_span(&self) -> Option<Span> {
+        self.inspected
+    }
+
     /// Trace a value for the span.
     pub fn value(&mut self, value: Value, styles: Option<Styles>) {
         if self.values.len() < Self::MAX_VALUES {
@@ -2,7 +2,7 @@ use comemo::Tracked;
 
 use crate::engine::Engine;
 use crate::eval::FlowEvent;
-use crate::foundations::{Context, IntoValue, Scopes, Value};
+use crate::foundations::{Context, Import, IntoValue, Scopes, Value};
 use crate::syntax::ast::{self, AstNode};
 use crate::syntax::Span;
 use crate::World;
@@ -50,6 +50,23 @@ impl<'a> Vm<'a> {
         self.scopes.top.define(var.get().clone(), value);
     }
 
+    /// Define a variable that is not part of the module.
+    pub fn define_private(&mut self, var: ast::Ident, value: impl IntoValue) {
+        let value = value.into_value();
+        if self.inspected == Some(var.span()) {
+            self.trace(value.clone());
+        }
+        self.scopes.top.define_private(var.get().clone(), value);
+    }
+
+    /// Define a variable that was brought into scope by an import.
+    pub fn define_import(&mut self, var: ast::Ident, value: Value, import: Import) {
+        if self.inspected == Some(var.span()) {
+            self.trace(value.clone());
+        }
+        self.scopes.top.define_import(var.get().clone(), value, import);
+    }
+
     /// Trace a value.
     #[cold]
     pub fn trace(&mut self, value: Value) {
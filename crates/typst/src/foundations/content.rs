@@ -702,7 +702,7 @@ impl<T: NativeElement> Bounds for T {
     }
 
     fn dyn_elem(&self) -> Element {
-        Self::elem()
+        self.instance_elem()
     }
 
     fn dyn_clone(&self, inner: &Inner<dyn Bounds>, span: Span) -> Content {
@@ -0,0 +1,608 @@
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::ptr::NonNull;
+
+use comemo::Track;
+use ecow::{eco_format, EcoString};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::diag::{bail, At, SourceResult, StrResult};
+use crate::engine::Engine;
+use crate::foundations::{
+    func, repr, Args, Capable, CastInfo, Construct, Content, Context, Dict, Element,
+    Fields, Func, NativeElement, NativeElementData, Packed, ParamInfo, Property, Repr,
+    Scope, Set, Show, Str, StyleChain, Styles, Synthesize, Type, Value,
+};
+use crate::introspection::{Count, Counter, CounterUpdate, Locatable};
+use crate::model::{Numbering, Refable};
+use crate::syntax::{is_ident, Span, Spanned};
+use crate::text::TextElem;
+use crate::util::{hash128, NonZeroExt, PicoStr};
+
+/// Defines a new element.
+///
+/// Custom elements work just like built-in ones: They can be styled with set
+/// rules, transformed with show rules, queried, labelled and referenced. An
+/// element is defined through a name and its fields. When the element is
+/// shown, the `display` function is called with the element and should return
+/// its visual representation. Within it, the element's fields are available
+/// through field access syntax.
+///
+/// ```example
+/// #let theorem = element(
+///   "theorem",
+///   fields: (
+///     body: (type: content, positional: true),
+///     title: (type: (content, none), default: none),
+///     supplement: (type: content, default: [Theorem]),
+///     numbering: (type: (str, function, none), default: "1"),
+///   ),
+///   display: it => block[
+///     *#it.supplement #counter(it.func()).display(it.numbering)*
+///     #if it.title != none [(#it.title)] \
+///     #it.body
+///   ],
+/// )
+///
+/// #set theorem(supplement: [Lemma])
+/// #theorem(title: [Pythagoras])[
+///   $a^2 + b^2 = c^2$
+/// ] <pythagoras>
+///
+/// See @pythagoras.
+/// ```
+///
+/// # Fields
+/// Each field is specified either through its type, an array of types, or a
+/// dictionary with the following keys:
+///
+/// - `type`: The type or types the field accepts. If omitted, the field
+///   accepts any value.
+/// - `default`: The field's default value. Fields with a default are optional
+///   and can be customized with set rules. Fields without one are required.
+/// - `positional`: Whether the field is passed positionally rather than by
+///   name. Defaults to `{false}`.
+///
+/// Values are checked against the field's types when the element is
+/// constructed or a set rule is applied, with the same conversions as for the
+/// arguments of built-in elements. For instance, a string is accepted for a
+/// field of type `content`.
+///
+/// If the element has a `numbering` field, it is counted and can be
+/// referenced whenever the field is not `{none}`. A `supplement` field, if
+/// present, is then used as the default supplement for references.
+#[func(name = "element")]
+pub fn define_element(
+    /// The call span of this function.
+    span: Span,
+    /// The element's name.
+    name: Str,
+    /// The element's fields, as a dictionary from field names to their
+    /// specification.
+    #[named]
+    #[default]
+    fields: Dict,
+    /// A function that receives an instance of the element and returns its
+    /// visual representation.
+    ///
+    /// If omitted, the element displays its `body` field, if it has one, and
+    /// nothing otherwise.
+    #[named]
+    #[default]
+    display: Option<Func>,
+) -> StrResult<Element> {
+    if !is_ident(&name) {
+        bail!("invalid element name: {}", name.repr());
+    }
+
+    if fields.len() >= 255 {
+        bail!("too many fields");
+    }
+
+    // Reuse the element of an identical definition so that elements stay the
+    // same across re-evaluations.
+    let key = hash128(&(&name, &fields, &display));
+    let data = ELEMENTS.get_or_define(span, key, || {
+        let fields = fields
+            .into_iter()
+            .map(|(name, spec)| match name.as_str() {
+                "label" => bail!("invalid field name: {}", name.repr()),
+                _ => CustomField::parse(&name, spec),
+            })
+            .collect::<StrResult<Vec<_>>>()?;
+
+        let params = fields.iter().map(CustomField::param).collect();
+        let custom = CustomElementData { fields, display, params };
+        Ok(NativeElementData {
+            name: PicoStr::new(&name).resolve(),
+            title: PicoStr::new(&title_case(&name)).resolve(),
+            docs: "",
+            keywords: &[],
+            construct: <CustomElem as Construct>::construct,
+            set: <CustomElem as Set>::set,
+            vtable: <CustomElem as Capable>::vtable,
+            field_id: |_| None,
+            field_name: |_| None,
+            field_from_styles: <CustomElem as Fields>::field_from_styles,
+            local_name: None,
+            scope: Lazy::new(Scope::new),
+            params: Lazy::new(Vec::new),
+            custom: Some(custom),
+        })
+    })?;
+
+    Ok(Element::from(data))
+}
+
+/// All elements defined so far, by their call site.
+static ELEMENTS: Lazy<Definitions<NativeElementData>> = Lazy::new(Definitions::new);
+
+/// Definitions of custom elements or types, interned by their call site and
+/// inputs.
+///
+/// Elements and types are compared by identity, so their data must live
+/// forever. Each distinct definition is leaked exactly once: Evaluating the
+/// same call site with the same inputs again always yields the same data, no
+/// matter what was defined in between.
+pub(crate) struct Definitions<T: 'static> {
+    map: Mutex<HashMap<(Span, u128), &'static T>>,
+}
+
+impl<T: 'static> Definitions<T> {
+    /// Create an empty set of definitions.
+    pub fn new() -> Self {
+        Self { map: Mutex::new(HashMap::new()) }
+    }
+
+    /// Return the definition at the call site whose inputs hash to `key` or
+    /// create it if there is none yet.
+    pub fn get_or_define(
+        &self,
+        span: Span,
+        key: u128,
+        define: impl FnOnce() -> StrResult<T>,
+    ) -> StrResult<&'static T> {
+        let mut map = self.map.lock();
+        if let Some(&data) = map.get(&(span, key)) {
+            return Ok(data);
+        }
+
+        let data = &*Box::leak(Box::new(define()?));
+        map.insert((span, key), data);
+        Ok(data)
+    }
+}
+
+/// Turns a name like `proof-sketch` into `Proof Sketch`.
+pub(crate) fn title_case(name: &str) -> EcoString {
+    let mut title = EcoString::new();
+    for (i, word) in name.split(['-', '_']).filter(|w| !w.is_empty()).enumerate() {
+        if i > 0 {
+            title.push(' ');
+        }
+        let mut chars = word.chars();
+        title.extend(chars.next().into_iter().flat_map(char::to_uppercase));
+        title.push_str(chars.as_str());
+    }
+    title
+}
+
+/// The definition of an element that was defined in Typst code.
+#[derive(Debug)]
+pub struct CustomElementData {
+    fields: Vec<CustomField>,
+    display: Option<Func>,
+    params: Vec<ParamInfo>,
+}
+
+impl CustomElementData {
+    /// Construct an instance of the element.
+    pub(crate) fn construct(
+        &self,
+        elem: Element,
+        args: &mut Args,
+    ) -> SourceResult<Content> {
+        let fields = self
+            .fields
+            .iter()
+            .map(|field| field.take(args))
+            .collect::<SourceResult<_>>()?;
+        Ok(CustomElem { elem, fields, numbering: None }.pack())
+    }
+
+    /// Parse the settable fields into style properties.
+    pub(crate) fn set(&self, elem: Element, args: &mut Args) -> SourceResult<Styles> {
+        let mut styles = Styles::new();
+        for (id, field) in self.fields.iter().enumerate() {
+            if field.default.is_none() {
+                continue;
+            }
+
+            if let Some(Spanned { v, span }) = args.named::<Spanned<Value>>(field.name)? {
+                let value = field.check(v).at(span)?;
+                styles.set(Property::of(elem, id as u8, value));
+            }
+        }
+        Ok(styles)
+    }
+
+    /// Details about the element's fields.
+    pub(crate) fn params(&'static self) -> &'static [ParamInfo] {
+        &self.params
+    }
+
+    /// Extract the field ID for the given field name.
+    pub(crate) fn field_id(&self, name: &str) -> Option<u8> {
+        self.fields
+            .iter()
+            .position(|field| field.name == name)
+            .map(|id| id as u8)
+    }
+
+    /// Extract the field name for the given field ID.
+    pub(crate) fn field_name(&self, id: u8) -> Option<&'static str> {
+        self.fields.get(usize::from(id)).map(|field| field.name)
+    }
+
+    /// Get the field with the given ID from the styles, falling back to its
+    /// default.
+    pub(crate) fn field_from_styles(
+        &self,
+        elem: Element,
+        id: u8,
+        styles: StyleChain,
+    ) -> Option<Value> {
+        let default = self.fields.get(usize::from(id))?.default.as_ref()?;
+        Some(styles.get(elem, id, None, || default.clone()))
+    }
+}
+
+/// A field of an element or type that was defined in Typst code.
+#[derive(Debug)]
+pub(crate) struct CustomField {
+    /// The field's name.
+    pub name: &'static str,
+    /// The types the field accepts. Empty if it accepts any value.
+    pub types: Vec<Type>,
+    /// The field's default value. Fields with a default are optional and, for
+    /// elements, settable.
+    pub default: Option<Value>,
+    /// Whether the field is passed positionally.
+    pub positional: bool,
+}
+
+impl CustomField {
+    /// Parse a field from its specification.
+    pub fn parse(name: &str, spec: Value) -> StrResult<Self> {
+        if !is_ident(name) {
+            bail!("invalid field name: {}", name.repr());
+        }
+
+        let mut field = Self {
+            name: PicoStr::new(name).resolve(),
+            types: vec![],
+            default: None,
+            positional: false,
+        };
+
+        match spec {
+            Value::Dict(mut dict) => {
+                if let Ok(types) = dict.take("type") {
+                    field.types = parse_types(types)?;
+                }
+                field.default = dict.take("default").ok();
+                if let Ok(positional) = dict.take("positional") {
+                    field.positional = positional.cast()?;
+                }
+                dict.finish(&["type", "default", "positional"])?;
+            }
+            spec => field.types = parse_types(spec)?,
+        }
+
+        Ok(field)
+    }
+
+    /// Take and check the field's argument, if it was given.
+    pub fn take(&self, args: &mut Args) -> SourceResult<Option<Value>> {
+        let value: Option<Spanned<Value>> = match (self.positional, &self.default) {
+            (true, None) => Some(args.expect(self.name)?),
+            (true, Some(_)) => args.eat()?,
+            (false, _) => args.named(self.name)?,
+        };
+
+        if value.is_none() && self.default.is_none() {
+            bail!(args.span, "missing argument: {}", self.name);
+        }
+
+        value.map(|Spanned { v, span }| self.check(v).at(span)).transpose()
+    }
+
+    /// Check that a value is acceptable for this field.
+    pub fn check(&self, value: Value) -> StrResult<Value> {
+        check_types(&self.types, value)
+    }
+
+    /// Describes what values this field accepts.
+    pub fn input(&self) -> CastInfo {
+        types_info(&self.types)
+    }
+
+    /// Describes this field as a parameter of the constructor.
+    pub fn param(&self) -> ParamInfo {
+        ParamInfo {
+            name: self.name,
+            docs: "",
+            input: self.input(),
+            default: None,
+            positional: self.positional,
+            named: !self.positional,
+            variadic: false,
+            required: self.default.is_none(),
+            settable: self.default.is_some(),
+        }
+    }
+}
+
+/// Parse the types a field or an annotated parameter accepts.
+pub(crate) fn parse_types(spec: Value) -> StrResult<Vec<Type>> {
+    match spec {
+        Value::Array(array) => array.into_iter().map(parse_type).collect(),
+        spec => Ok(vec![parse_type(spec)?]),
+    }
+}
+
+/// Check that a value has one of the given types, converting it like the
+/// arguments of native functions if necessary. If no types are given, any
+/// value is accepted.
+pub(crate) fn check_types(types: &[Type], value: Value) -> StrResult<Value> {
+    if types.is_empty() {
+        return Ok(value);
+    }
+
+    types
+        .iter()
+        .find_map(|ty| ty.coerce(&value))
+        .ok_or_else(|| types_info(types).error(&value))
+}
+
+/// Describes the values that have one of the given types.
+pub(crate) fn types_info(types: &[Type]) -> CastInfo {
+    match types {
+        [] => CastInfo::Any,
+        [ty] => CastInfo::Type(*ty),
+        types => CastInfo::Union(types.iter().copied().map(CastInfo::Type).collect()),
+    }
+}
+
+/// Parse a single type. `{none}` and `{auto}` stand for their own types.
+fn parse_type(value: Value) -> StrResult<Type> {
+    match value {
+        Value::Type(ty) => Ok(ty),
+        Value::None | Value::Auto => Ok(value.ty()),
+        v => bail!("expected type, found {}", v.ty()),
+    }
+}
+
+/// An instance of an element that was defined in Typst code.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct CustomElem {
+    /// The element this is an instance of.
+    elem: Element,
+    /// The values of the fields, indexed by field ID.
+    fields: Vec<Option<Value>>,
+    /// The resolved value of the `numbering` field, if any.
+    numbering: Option<Numbering>,
+}
+
+impl CustomElem {
+    /// The element's definition.
+    fn definition(&self) -> &'static CustomElementData {
+        self.elem.custom().expect("custom element without definition")
+    }
+
+    /// Get the value of the field with the given name, if it is set.
+    fn get(&self, name: &str) -> Option<&Value> {
+        let id = self.elem.field_id(name)?;
+        self.fields.get(usize::from(id))?.as_ref()
+    }
+}
+
+impl NativeElement for CustomElem {
+    fn data() -> &'static NativeElementData {
+        static DATA: NativeElementData = NativeElementData {
+            name: "custom",
+            title: "Custom",
+            docs: "",
+            keywords: &[],
+            construct: <CustomElem as Construct>::construct,
+            set: <CustomElem as Set>::set,
+            vtable: <CustomElem as Capable>::vtable,
+            field_id: |_| None,
+            field_name: |_| None,
+            field_from_styles: <CustomElem as Fields>::field_from_styles,
+            local_name: None,
+            scope: Lazy::new(Scope::new),
+            params: Lazy::new(Vec::new),
+            custom: None,
+        };
+        &DATA
+    }
+
+    fn instance_elem(&self) -> Element {
+        self.elem
+    }
+}
+
+impl Construct for CustomElem {
+    fn construct(_: &mut Engine, args: &mut Args) -> SourceResult<Content> {
+        bail!(
+            args.span,
+            "custom elements can only be constructed through their definition"
+        )
+    }
+}
+
+impl Set for CustomElem {
+    fn set(_: &mut Engine, args: &mut Args) -> SourceResult<Styles> {
+        bail!(
+            args.span,
+            "custom elements can only be configured through their definition"
+        )
+    }
+}
+
+unsafe impl Capable for CustomElem {
+    fn vtable(capability: TypeId) -> Option<*const ()> {
+        let dangling = NonNull::<Packed<CustomElem>>::dangling().as_ptr();
+        // Safety: The vtable function doesn't require initialized data, so
+        // it's fine to use a dangling pointer.
+        unsafe {
+            if capability == TypeId::of::<dyn Show>() {
+                return Some(crate::util::fat::vtable(dangling as *const dyn Show));
+            }
+            if capability == TypeId::of::<dyn Synthesize>() {
+                return Some(crate::util::fat::vtable(dangling as *const dyn Synthesize));
+            }
+            if capability == TypeId::of::<dyn Locatable>() {
+                return Some(crate::util::fat::vtable(dangling as *const dyn Locatable));
+            }
+            if capability == TypeId::of::<dyn Count>() {
+                return Some(crate::util::fat::vtable(dangling as *const dyn Count));
+            }
+            if capability == TypeId::of::<dyn Refable>() {
+                return Some(crate::util::fat::vtable(dangling as *const dyn Refable));
+            }
+        }
+        None
+    }
+}
+
+impl Fields for CustomElem {
+    type Enum = u8;
+
+    fn has(&self, id: u8) -> bool {
+        self.fields.get(usize::from(id)).is_some_and(Option::is_some)
+    }
+
+    fn field(&self, id: u8) -> Option<Value> {
+        self.fields.get(usize::from(id))?.clone()
+    }
+
+    fn field_with_styles(&self, id: u8, styles: StyleChain) -> Option<Value> {
+        self.field(id).or_else(|| self.elem.field_from_styles(id, styles))
+    }
+
+    fn field_from_styles(_: u8, _: StyleChain) -> Option<Value> {
+        None
+    }
+
+    fn materialize(&mut self, styles: StyleChain) {
+        for (id, value) in self.fields.iter_mut().enumerate() {
+            if value.is_none() {
+                *value = self.elem.field_from_styles(id as u8, styles);
+            }
+        }
+    }
+
+    fn fields(&self) -> Dict {
+        self.definition()
+            .fields
+            .iter()
+            .zip(&self.fields)
+            .filter_map(|(field, value)| Some((field.name.into(), value.clone()?)))
+            .collect()
+    }
+}
+
+impl Repr for CustomElem {
+    fn repr(&self) -> EcoString {
+        let fields = self
+            .fields()
+            .into_iter()
+            .map(|(name, value)| eco_format!("{}: {}", name, value.repr()))
+            .collect::<Vec<_>>();
+        eco_format!("{}{}", self.elem.name(), repr::pretty_array_like(&fields, false))
+    }
+}
+
+impl Synthesize for Packed<CustomElem> {
+    fn synthesize(&mut self, _: &mut Engine, styles: StyleChain) -> SourceResult<()> {
+        let numbering = self
+            .elem
+            .field_id("numbering")
+            .and_then(|id| self.field_with_styles(id, styles))
+            .and_then(|value| value.cast().ok());
+        self.numbering = numbering;
+        Ok(())
+    }
+}
+
+impl Show for Packed<CustomElem> {
+    fn show(&self, engine: &mut Engine, styles: StyleChain) -> SourceResult<Content> {
+        let Some(display) = &self.definition().display else {
+            return Ok(self
+                .get("body")
+                .cloned()
+                .map_or_else(Content::empty, Value::display));
+        };
+
+        let context = Context::new(self.location(), Some(styles));
+        Ok(display
+            .call(engine, context.track(), [self.clone().pack()])?
+            .display())
+    }
+}
+
+impl Count for Packed<CustomElem> {
+    fn update(&self) -> Option<CounterUpdate> {
+        self.numbering
+            .is_some()
+            .then(|| CounterUpdate::Step(NonZeroUsize::ONE))
+    }
+}
+
+impl Refable for Packed<CustomElem> {
+    fn supplement(&self) -> Content {
+        match self.get("supplement") {
+            Some(supplement) => supplement.clone().display(),
+            None => TextElem::packed(self.elem.title()),
+        }
+    }
+
+    fn counter(&self) -> Counter {
+        Counter::of(self.elem)
+    }
+
+    fn numbering(&self) -> Option<&Numbering> {
+        self.numbering.as_ref()
+    }
+}
+
+impl Locatable for Packed<CustomElem> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_definitions_are_reused() {
+        let definitions = Definitions::new();
+        let span = Span::detached();
+        let a = definitions.get_or_define(span, 1, || Ok(1)).unwrap();
+        let b = definitions.get_or_define(span, 2, || Ok(2)).unwrap();
+        let c = definitions.get_or_define(span, 1, || unreachable!()).unwrap();
+        assert!(!std::ptr::eq(a, b));
+        assert!(std::ptr::eq(a, c));
+    }
+
+    #[test]
+    fn test_definitions_are_unbounded() {
+        let definitions = Definitions::new();
+        let span = Span::detached();
+        for i in 0..100_000_u128 {
+            assert_eq!(*definitions.get_or_define(span, i, || Ok(i)).unwrap(), i);
+        }
+    }
+}
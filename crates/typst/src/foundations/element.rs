@@ -10,8 +10,8 @@ use smallvec::SmallVec;
 use crate::diag::SourceResult;
 use crate::engine::Engine;
 use crate::foundations::{
-    cast, Args, Content, Dict, Func, ParamInfo, Repr, Scope, Selector, StyleChain,
-    Styles, Value,
+    cast, Args, Content, CustomElementData, Dict, Func, ParamInfo, Repr, Scope, Selector,
+    StyleChain, Styles, Value,
 };
 use crate::text::{Lang, Region};
 use crate::util::Static;
@@ -56,12 +56,18 @@ impl Element {
         engine: &mut Engine,
         args: &mut Args,
     ) -> SourceResult<Content> {
-        (self.0.construct)(engine, args)
+        match self.custom() {
+            Some(custom) => custom.construct(self, args),
+            None => (self.0.construct)(engine, args),
+        }
     }
 
     /// Execute the set rule for the element and return the resulting style map.
     pub fn set(self, engine: &mut Engine, mut args: Args) -> SourceResult<Styles> {
-        let styles = (self.0.set)(engine, &mut args)?;
+        let styles = match self.custom() {
+            Some(custom) => custom.set(self, &mut args)?,
+            None => (self.0.set)(engine, &mut args)?,
+        };
         args.finish()?;
         Ok(styles)
     }
@@ -103,7 +109,10 @@ impl Element {
 
     /// Details about the element's fields.
     pub fn params(&self) -> &'static [ParamInfo] {
-        &(self.0).0.params
+        match self.custom() {
+            Some(custom) => custom.params(),
+            None => &(self.0).0.params,
+        }
     }
 
     /// Extract the field ID for the given field name.
@@ -111,7 +120,10 @@ impl Element {
         if name == "label" {
             return Some(255);
         }
-        (self.0.field_id)(name)
+        match self.custom() {
+            Some(custom) => custom.field_id(name),
+            None => (self.0.field_id)(name),
+        }
     }
 
     /// Extract the field name for the given field ID.
@@ -119,18 +131,29 @@ impl Element {
         if id == 255 {
             return Some("label");
         }
-        (self.0.field_name)(id)
+        match self.custom() {
+            Some(custom) => custom.field_name(id),
+            None => (self.0.field_name)(id),
+        }
     }
 
     /// Extract the field name for the given field ID.
     pub fn field_from_styles(&self, id: u8, styles: StyleChain) -> Option<Value> {
-        (self.0.field_from_styles)(id, styles)
+        match self.custom() {
+            Some(custom) => custom.field_from_styles(*self, id, styles),
+            None => (self.0.field_from_styles)(id, styles),
+        }
     }
 
     /// The element's local name, if any.
     pub fn local_name(&self, lang: Lang, region: Option<Region>) -> Option<&'static str> {
         (self.0).0.local_name.map(|f| f(lang, region))
     }
+
+    /// The definition of the element if it was defined in Typst code.
+    pub fn custom(self) -> Option<&'static CustomElementData> {
+        (self.0).0.custom.as_ref()
+    }
 }
 
 impl Debug for Element {
@@ -198,6 +221,17 @@ pub trait NativeElement:
     fn data() -> &'static NativeElementData
     where
         Self: Sized;
+
+    /// Get the element this instance belongs to.
+    ///
+    /// This is the same as [`elem`](Self::elem) except for user-defined
+    /// elements, which all share the same Rust type.
+    fn instance_elem(&self) -> Element
+    where
+        Self: Sized,
+    {
+        Self::elem()
+    }
 }
 
 /// Used to cast an element to a trait object for a trait it implements.
@@ -274,6 +308,7 @@ pub struct NativeElementData {
     pub local_name: Option<fn(Lang, Option<Region>) -> &'static str>,
     pub scope: Lazy<Scope>,
     pub params: Lazy<Vec<ParamInfo>>,
+    pub custom: Option<CustomElementData>,
 }
 
 impl From<&'static NativeElementData> for Element {
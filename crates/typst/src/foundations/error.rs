@@ -0,0 +1,217 @@
+use comemo::Tracked;
+use ecow::{eco_format, eco_vec, EcoString, EcoVec};
+
+use crate::diag::{SourceDiagnostic, SourceResult};
+use crate::engine::Engine;
+use crate::foundations::{
+    func, repr, scope, ty, Context, Func, IntoValue, Never, Repr, Value,
+};
+use crate::syntax::Span;
+use crate::World;
+
+/// Calls a function and catches the errors it fails with.
+///
+/// If the function succeeds, `catch` returns its result. If it fails, the
+/// first error is turned into an [`error`] value. This value is passed to the
+/// `handler` function, whose result is returned instead. Without a handler,
+/// the error value itself is returned.
+///
+/// Only errors that occur while the function is called are caught. Errors
+/// that occur later, for instance while laying out content that the function
+/// returned, abort the compilation as usual.
+///
+/// Errors that stem from a cancelled compilation or an exceeded limit, like the
+/// maximum function call depth, are never caught.
+///
+/// ```example
+/// #let config = catch(
+///   () => json("config.json"),
+///   err => (title: "Untitled"),
+/// )
+///
+/// #config.title
+///
+/// #let result = catch(() => calc.pow(2, 99))
+/// #result.message
+/// ```
+#[func]
+pub fn catch(
+    /// The engine.
+    engine: &mut Engine,
+    /// The callsite context.
+    context: Tracked<Context>,
+    /// The function to call. It is called without arguments.
+    body: Func,
+    /// A function that is called with the caught error if `body` fails.
+    #[default]
+    handler: Option<Func>,
+) -> SourceResult<Value> {
+    let errors = match body.call(engine, context, [] as [Value; 0]) {
+        Ok(value) => return Ok(value),
+        Err(errors) if errors.iter().any(|error| error.fatal) => return Err(errors),
+        Err(errors) => errors,
+    };
+
+    let error = match errors.first() {
+        Some(diagnostic) => Error::caught(engine.world, diagnostic),
+        None => Error::new("unknown error".into(), EcoVec::new()),
+    };
+
+    match handler {
+        Some(handler) => handler.call(engine, context, [error]),
+        None => Ok(error.into_value()),
+    }
+}
+
+/// An error that occurred during evaluation or that was defined in Typst
+/// code.
+///
+/// Errors are produced by the [`catch`] function, but you can also construct
+/// your own errors with a message and hints and raise them with the
+/// [`throw`]($error.throw) method.
+///
+/// # Fields
+/// - `message`: The error message as a [string]($str).
+/// - `hints`: An [array] of hints that explain how the error could be fixed.
+/// - `file`: The path of the file the error occurred in, or `{none}` if it is
+///   unknown.
+/// - `line`: The one-based line the error occurred on, or `{none}` if it is
+///   unknown.
+/// - `column`: The one-based column the error occurred at, or `{none}` if it is
+///   unknown.
+///
+/// # Example
+/// ```example
+/// #let parse(text) = {
+///   if not text.starts-with("v") {
+///     error(
+///       "invalid version: " + repr(text),
+///       hints: ("versions start with a `v`",),
+///     ).throw()
+///   }
+///   text.slice(1)
+/// }
+///
+/// #let result = catch(() => parse("1.0"))
+/// #result.message \
+/// #result.hints.first()
+/// ```
+#[ty(scope)]
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Error {
+    /// The error message.
+    message: EcoString,
+    /// Hints for how the error could be fixed.
+    hints: EcoVec<EcoString>,
+    /// Where the error occurred.
+    span: Span,
+    /// The file, line, and column the error occurred at, if known.
+    position: Option<(EcoString, usize, usize)>,
+}
+
+impl Error {
+    /// The names of the error's fields.
+    pub const FIELDS: [&'static str; 5] = ["message", "hints", "file", "line", "column"];
+
+    /// Create a new error that didn't occur at a specific place yet.
+    pub fn new(message: EcoString, hints: EcoVec<EcoString>) -> Self {
+        Self {
+            message,
+            hints,
+            span: Span::detached(),
+            position: None,
+        }
+    }
+
+    /// Create an error from a diagnostic that was caught.
+    pub fn caught(world: Tracked<dyn World + '_>, diagnostic: &SourceDiagnostic) -> Self {
+        let span = diagnostic.span;
+        let position = span.id().and_then(|id| {
+            let source = world.source(id).ok()?;
+            let start = source.range(span)?.start;
+            let line = source.byte_to_line(start)?;
+            let column = source.byte_to_column(start)?;
+            let path = id.vpath().as_rooted_path().to_string_lossy();
+            let file = match id.package() {
+                Some(package) => eco_format!("{package}{path}"),
+                None => path.as_ref().into(),
+            };
+            Some((file, line + 1, column + 1))
+        });
+
+        Self {
+            message: diagnostic.message.clone(),
+            hints: diagnostic.hints.clone(),
+            span,
+            position,
+        }
+    }
+
+    /// Access a field on the error.
+    pub fn field(&self, field: &str) -> Option<Value> {
+        let position = self.position.as_ref();
+        Some(match field {
+            "message" => self.message.clone().into_value(),
+            "hints" => self.hints.iter().cloned().collect::<Vec<_>>().into_value(),
+            "file" => position.map(|(file, _, _)| file.clone()).into_value(),
+            "line" => position.map(|&(_, line, _)| line as i64).into_value(),
+            "column" => position.map(|&(_, _, column)| column as i64).into_value(),
+            _ => return None,
+        })
+    }
+}
+
+#[scope]
+impl Error {
+    /// Creates a new error.
+    ///
+    /// The error is only raised once its [`throw`]($error.throw) method is
+    /// called.
+    ///
+    /// ```example
+    /// #let err = error("out of stock", hints: ("try again later",))
+    /// #err.message
+    /// ```
+    #[func(constructor)]
+    pub fn construct(
+        /// The error message.
+        message: EcoString,
+        /// Hints for how the error could be fixed.
+        #[named]
+        #[default]
+        hints: Vec<EcoString>,
+    ) -> Error {
+        Error::new(message, hints.into_iter().collect())
+    }
+
+    /// Raises the error, failing just like a built-in function would.
+    ///
+    /// An error that was caught with [`catch`] is raised at the place where
+    /// it originally occurred. Other errors are raised at the call of
+    /// `throw`.
+    ///
+    /// ```typ
+    /// #error("something went wrong").throw()
+    /// ```
+    #[func]
+    pub fn throw(
+        self,
+        /// The span of the `throw` call.
+        span: Span,
+    ) -> SourceResult<Never> {
+        let span = if self.span.is_detached() { span } else { self.span };
+        Err(eco_vec![SourceDiagnostic::error(span, self.message).with_hints(self.hints)])
+    }
+}
+
+impl Repr for Error {
+    fn repr(&self) -> EcoString {
+        let mut pieces = vec![self.message.repr()];
+        if !self.hints.is_empty() {
+            let hints: Vec<_> = self.hints.iter().map(Repr::repr).collect();
+            let hints = repr::pretty_array_like(&hints, hints.len() == 1);
+            pieces.push(eco_format!("hints: {hints}"));
+        }
+        eco_format!("error{}", repr::pretty_array_like(&pieces, false))
+    }
+}
@@ -3,7 +3,7 @@
 use ecow::{eco_format, EcoString};
 
 use crate::diag::StrResult;
-use crate::foundations::{IntoValue, Type, Value, Version};
+use crate::foundations::{Error, IntoValue, Record, Type, Value, Version};
 use crate::layout::{Alignment, Length, Rel};
 use crate::visualize::Stroke;
 
@@ -45,6 +45,13 @@ pub(crate) fn field(value: &Value, field: &str) -> StrResult<Value> {
                     }
                     _ => return missing(),
                 }
+            } else if let Some(record) = dynamic.downcast::<Record>() {
+                record.field(field)?.clone()
+            } else if let Some(error) = dynamic.downcast::<Error>() {
+                match error.field(field) {
+                    Some(value) => value,
+                    None => return missing(),
+                }
             } else if let Some(align) = dynamic.downcast::<Alignment>() {
                 match field {
                     "x" => align.x().into_value(),
@@ -75,8 +82,12 @@ fn missing_field(ty: Type, field: &str) -> EcoString {
 
 /// List the available fields for a type.
 pub fn fields_on(ty: Type) -> &'static [&'static str] {
-    if ty == Type::of::<Version>() {
+    if let Some(custom) = ty.custom() {
+        custom.fields()
+    } else if ty == Type::of::<Version>() {
         &Version::COMPONENTS
+    } else if ty == Type::of::<Error>() {
+        &Error::FIELDS
     } else if ty == Type::of::<Length>() {
         &["em", "abs"]
     } else if ty == Type::of::<Rel>() {
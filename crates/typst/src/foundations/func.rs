@@ -102,6 +102,23 @@ pub use typst_macros::func;
 /// ]
 /// ```
 ///
+/// # Type annotations
+/// Parameters can be annotated with the types they accept by writing `as`
+/// and a type or an array of types after the parameter's name. Likewise, the
+/// type of the return value can be annotated with `->` after the parameter
+/// list. When the function is called, Typst checks the arguments and the
+/// return value against these annotations and reports an error if they don't
+/// match. Integers are automatically converted to floats where a float is
+/// expected, just like for built-in functions.
+///
+/// ```example
+/// #let scale(factor as (int, float), size as length: 1em) -> length = {
+///   factor * size
+/// }
+///
+/// #box(width: scale(2), fill: aqua)[A]
+/// ```
+///
 /// # Unnamed functions { #unnamed }
 /// You can also created an unnamed function without creating a binding by
 /// specifying a parameter list followed by `=>` and the function body. If your
@@ -249,6 +266,16 @@ impl Func {
         }
     }
 
+    /// The types the parameter with the given name accepts according to its
+    /// type annotation, if this is a user-defined function.
+    pub fn param_types(&self, name: &str) -> Option<&[Type]> {
+        match &self.repr {
+            Repr::Closure(closure) => closure.param_types(name),
+            Repr::With(with) => with.0.param_types(name),
+            _ => None,
+        }
+    }
+
     /// Extract the element function, if it is one.
     pub fn element(&self) -> Option<Element> {
         match self.repr {
@@ -495,12 +522,16 @@ pub struct Closure {
     /// `ast::Expr`. In the latter case, this is a synthesized closure without
     /// any parameters (used by `context` expressions).
     pub node: SyntaxNode,
-    /// Default values of named parameters.
-    pub defaults: Vec<Value>,
+    /// The type annotations and default values of the closure's parameters,
+    /// in the order of the parameters.
+    pub params: Vec<ClosureParam>,
     /// Captured values from outer scopes.
     pub captured: Scope,
     /// The number of positional parameters in the closure.
     pub num_pos_params: usize,
+    /// The types the closure may return according to its return type
+    /// annotation. Empty if there is no annotation.
+    pub returns: Vec<Type>,
 }
 
 impl Closure {
@@ -508,6 +539,30 @@ impl Closure {
     pub fn name(&self) -> Option<&str> {
         self.node.cast::<ast::Closure>()?.name().map(|ident| ident.as_str())
     }
+
+    /// The types the parameter with the given name accepts according to its
+    /// type annotation.
+    pub fn param_types(&self, name: &str) -> Option<&[Type]> {
+        let params = self.node.cast::<ast::Closure>()?.params();
+        params.children().zip(&self.params).find_map(|(param, info)| {
+            let ident = match param {
+                ast::Param::Pos(ast::Pattern::Normal(ast::Expr::Ident(ident))) => ident,
+                ast::Param::Named(named) => named.name(),
+                _ => return None,
+            };
+            let types = info.types.as_slice();
+            (ident.as_str() == name && !types.is_empty()).then_some(types)
+        })
+    }
+}
+
+/// The evaluated type annotation and default value of a closure parameter.
+#[derive(Debug, Clone, Hash)]
+pub struct ClosureParam {
+    /// The types the parameter accepts. Empty if there is no annotation.
+    pub types: Vec<Type>,
+    /// The default value of a named parameter.
+    pub default: Option<Value>,
 }
 
 impl From<Closure> for Func {
@@ -0,0 +1,522 @@
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Formatter};
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+use comemo::Tracked;
+use ecow::EcoString;
+
+use crate::diag::{At, SourceResult};
+use crate::engine::Engine;
+use crate::eval::ops;
+use crate::foundations::{
+    array, cast, func, scope, ty, Array, Context, Func, IntoValue, Repr, Value,
+};
+use crate::loading::{CsvData, CsvRows, JsonData, JsonItems};
+use crate::syntax::Span;
+
+/// A lazy sequence of values.
+///
+/// Unlike the methods of [arrays]($array), the methods of an iterator don't
+/// produce a new collection right away. Instead, they describe how values
+/// are produced and transformed. The values are only computed when they are
+/// needed: When the iterator is looped over with a `{for}` loop or when it is
+/// consumed with a method like [`collect`]($iterator.collect). This way, no
+/// intermediate arrays are built, which makes processing large data sets
+/// much cheaper. The [`csv.rows`]($csv.rows) and [`json.items`]($json.items)
+/// functions produce iterators that even parse their data on demand.
+///
+/// An iterator is a value like any other: Iterating over it twice produces
+/// the same values twice, recomputing them from scratch.
+///
+/// # Example
+/// ```example
+/// #let squares = iterator(range(1, 100)).map(x => x * x).filter(calc.odd)
+///
+/// #squares.take(5).collect() \
+/// #for (i, x) in squares.enumerate().skip(47) [
+///   #i: #x \
+/// ]
+/// ```
+#[ty(scope, cast, name = "iterator")]
+#[derive(Clone, PartialEq, Hash)]
+pub struct Iter(Arc<Stage>);
+
+/// A step in the pipeline of an iterator.
+#[derive(Debug, PartialEq, Hash)]
+enum Stage {
+    /// Yields the items of an array.
+    Array(Array),
+    /// Yields the rows of CSV data.
+    Csv(CsvData),
+    /// Yields the items of a top-level JSON array.
+    Json(JsonData),
+    /// Transforms each value with a function.
+    Map(Iter, Func),
+    /// Keeps only the values for which a function returns `true`.
+    Filter(Iter, Func),
+    /// Pairs each value with its index, starting at the given one.
+    Enumerate(Iter, i64, Span),
+    /// Yields arrays of the values of multiple iterators.
+    Zip(Vec<Iter>),
+    /// Yields at most the given number of values.
+    Take(Iter, usize),
+    /// Skips the given number of values.
+    Skip(Iter, usize),
+    /// Yields arrays of consecutive values, optionally dropping a smaller
+    /// remainder.
+    Chunks(Iter, NonZeroUsize, bool),
+    /// Yields overlapping arrays of consecutive values.
+    Windows(Iter, NonZeroUsize),
+    /// Yields the values of nested arrays and iterators.
+    Flatten(Iter),
+    /// Groups consecutive values with equal keys.
+    GroupBy(Iter, Func),
+}
+
+impl Iter {
+    /// An iterator over the rows of CSV data.
+    pub fn csv(data: CsvData) -> Self {
+        Self::new(Stage::Csv(data))
+    }
+
+    /// An iterator over the items of a top-level JSON array.
+    pub fn json(data: JsonData) -> Self {
+        Self::new(Stage::Json(data))
+    }
+
+    /// Start producing the values of this iterator.
+    pub fn cursor(&self) -> SourceResult<Cursor> {
+        Ok(match &*self.0 {
+            Stage::Array(array) => Cursor::Array(array.clone().into_iter()),
+            Stage::Csv(data) => Cursor::Csv(Box::new(data.rows()?)),
+            Stage::Json(data) => Cursor::Json(data.items()),
+            Stage::Map(inner, mapper) => {
+                Cursor::Map(Box::new(inner.cursor()?), mapper.clone())
+            }
+            Stage::Filter(inner, test) => {
+                Cursor::Filter(Box::new(inner.cursor()?), test.clone())
+            }
+            Stage::Enumerate(inner, start, span) => {
+                Cursor::Enumerate(Box::new(inner.cursor()?), *start, *span)
+            }
+            Stage::Zip(inners) => {
+                Cursor::Zip(inners.iter().map(Iter::cursor).collect::<SourceResult<_>>()?)
+            }
+            Stage::Take(inner, count) => Cursor::Take(Box::new(inner.cursor()?), *count),
+            Stage::Skip(inner, count) => Cursor::Skip(Box::new(inner.cursor()?), *count),
+            Stage::Chunks(inner, size, exact) => {
+                Cursor::Chunks(Box::new(inner.cursor()?), size.get(), *exact)
+            }
+            Stage::Windows(inner, size) => Cursor::Windows(
+                Box::new(inner.cursor()?),
+                VecDeque::with_capacity(size.get()),
+                size.get(),
+            ),
+            Stage::Flatten(inner) => Cursor::Flatten(vec![inner.cursor()?]),
+            Stage::GroupBy(inner, key) => {
+                Cursor::GroupBy(Box::new(inner.cursor()?), key.clone(), None)
+            }
+        })
+    }
+
+    /// Create an iterator from a pipeline stage.
+    fn new(stage: Stage) -> Self {
+        Self(Arc::new(stage))
+    }
+}
+
+#[scope]
+impl Iter {
+    /// Creates an iterator over the items of an array.
+    ///
+    /// If the value is already an iterator, it is returned unchanged.
+    ///
+    /// ```example
+    /// #iterator((1, 2, 3)).map(x => x * 2).collect()
+    /// ```
+    #[func(constructor)]
+    pub fn construct(
+        /// The array to iterate over.
+        iterable: Iter,
+    ) -> Iter {
+        iterable
+    }
+
+    /// Transforms each value with the given function.
+    #[func]
+    pub fn map(
+        self,
+        /// The function to apply to each value.
+        mapper: Func,
+    ) -> Iter {
+        Self::new(Stage::Map(self, mapper))
+    }
+
+    /// Keeps only the values for which the given function returns `{true}`.
+    #[func]
+    pub fn filter(
+        self,
+        /// The function to apply to each value. Must return a boolean.
+        test: Func,
+    ) -> Iter {
+        Self::new(Stage::Filter(self, test))
+    }
+
+    /// Pairs each value with its index.
+    ///
+    /// The values are `(index, value)` pairs in the form of length-2 arrays.
+    /// These can be [destructured]($scripting/#bindings) with a let binding
+    /// or for loop.
+    #[func]
+    pub fn enumerate(
+        self,
+        /// The callsite span.
+        span: Span,
+        /// The index returned for the first value.
+        #[named]
+        #[default(0)]
+        start: i64,
+    ) -> Iter {
+        Self::new(Stage::Enumerate(self, start, span))
+    }
+
+    /// Zips the iterator with other iterators or arrays.
+    ///
+    /// Produces arrays whose `i`th item is the `i`th value of each iterator.
+    /// Stops as soon as one of the iterators is exhausted.
+    #[func]
+    pub fn zip(
+        self,
+        /// The iterators or arrays to zip with.
+        #[variadic]
+        others: Vec<Iter>,
+    ) -> Iter {
+        let mut inners = Vec::with_capacity(1 + others.len());
+        inners.push(self);
+        inners.extend(others);
+        Self::new(Stage::Zip(inners))
+    }
+
+    /// Produces at most the given number of values.
+    #[func]
+    pub fn take(
+        self,
+        /// How many values to produce.
+        count: usize,
+    ) -> Iter {
+        Self::new(Stage::Take(self, count))
+    }
+
+    /// Skips the given number of values.
+    #[func]
+    pub fn skip(
+        self,
+        /// How many values to skip.
+        count: usize,
+    ) -> Iter {
+        Self::new(Stage::Skip(self, count))
+    }
+
+    /// Splits the values into arrays of `chunk-size` consecutive values.
+    ///
+    /// The last chunk contains less than `chunk-size` values if the number
+    /// of values isn't divisible by it, unless `exact` is `{true}`. Then, the
+    /// remainder is dropped.
+    #[func]
+    pub fn chunks(
+        self,
+        /// How many values each chunk may at most contain.
+        chunk_size: NonZeroUsize,
+        /// Whether to drop the remainder if its size is less than
+        /// `chunk-size`.
+        #[named]
+        #[default(false)]
+        exact: bool,
+    ) -> Iter {
+        Self::new(Stage::Chunks(self, chunk_size, exact))
+    }
+
+    /// Produces arrays of `window-size` consecutive values, advancing by one
+    /// value at a time.
+    ///
+    /// ```example
+    /// #iterator((1, 2, 3, 4)).windows(2).collect()
+    /// ```
+    #[func]
+    pub fn windows(
+        self,
+        /// How many values each window contains.
+        window_size: NonZeroUsize,
+    ) -> Iter {
+        Self::new(Stage::Windows(self, window_size))
+    }
+
+    /// Produces the values of nested arrays and iterators instead of the
+    /// arrays and iterators themselves.
+    #[func]
+    pub fn flatten(self) -> Iter {
+        Self::new(Stage::Flatten(self))
+    }
+
+    /// Groups consecutive values for which the given function returns equal
+    /// keys.
+    ///
+    /// Produces `(key, values)` pairs, where `values` is an array of the
+    /// values in the group. To group all values with the same key, sort them
+    /// first.
+    ///
+    /// ```example
+    /// #let words = iterator(("apple", "avocado", "banana"))
+    /// #for (initial, group) in words.group-by(word => word.first()) [
+    ///   #initial: #group.join(", ") \
+    /// ]
+    /// ```
+    #[func]
+    pub fn group_by(
+        self,
+        /// The function that computes the key of a value.
+        key: Func,
+    ) -> Iter {
+        Self::new(Stage::GroupBy(self, key))
+    }
+
+    /// Computes all values and collects them into an array.
+    #[func]
+    pub fn collect(
+        &self,
+        /// The engine.
+        engine: &mut Engine,
+        /// The callsite context.
+        context: Tracked<Context>,
+        /// The callsite span.
+        span: Span,
+    ) -> SourceResult<Array> {
+        let limits = engine.limits();
+        let mut cursor = self.cursor()?;
+        let mut array = Array::new();
+        while let Some(value) = cursor.next(engine, context)? {
+            engine.check_interrupt(span)?;
+            limits.check_collection_len("array", array.len() + 1, span)?;
+            array.push(value);
+        }
+        Ok(array)
+    }
+
+    /// Computes all values and counts them.
+    #[func]
+    pub fn count(
+        &self,
+        /// The engine.
+        engine: &mut Engine,
+        /// The callsite context.
+        context: Tracked<Context>,
+        /// The callsite span.
+        span: Span,
+    ) -> SourceResult<usize> {
+        let mut cursor = self.cursor()?;
+        let mut count = 0;
+        while cursor.next(engine, context)?.is_some() {
+            engine.check_interrupt(span)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Folds all values into a single value using an accumulator function.
+    #[func]
+    pub fn fold(
+        &self,
+        /// The engine.
+        engine: &mut Engine,
+        /// The callsite context.
+        context: Tracked<Context>,
+        /// The callsite span.
+        span: Span,
+        /// The initial value to start with.
+        init: Value,
+        /// The folding function. Must have two parameters: One for the
+        /// accumulated value and one for a value of the iterator.
+        folder: Func,
+    ) -> SourceResult<Value> {
+        let mut cursor = self.cursor()?;
+        let mut acc = init;
+        while let Some(value) = cursor.next(engine, context)? {
+            engine.check_interrupt(span)?;
+            acc = folder.call(engine, context, [acc, value])?;
+        }
+        Ok(acc)
+    }
+}
+
+impl Debug for Iter {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Repr for Iter {
+    fn repr(&self) -> EcoString {
+        "<iterator>".into()
+    }
+}
+
+impl From<Array> for Iter {
+    fn from(array: Array) -> Self {
+        Self::new(Stage::Array(array))
+    }
+}
+
+cast! {
+    type Iter,
+    array: Array => Self::from(array),
+}
+
+/// Produces the values of an iterator one at a time.
+pub enum Cursor {
+    Array(ecow::vec::IntoIter<Value>),
+    Csv(Box<CsvRows>),
+    Json(JsonItems),
+    Map(Box<Cursor>, Func),
+    Filter(Box<Cursor>, Func),
+    Enumerate(Box<Cursor>, i64, Span),
+    Zip(Vec<Cursor>),
+    Take(Box<Cursor>, usize),
+    Skip(Box<Cursor>, usize),
+    Chunks(Box<Cursor>, usize, bool),
+    Windows(Box<Cursor>, VecDeque<Value>, usize),
+    Flatten(Vec<Cursor>),
+    GroupBy(Box<Cursor>, Func, Option<(Value, Value)>),
+}
+
+impl Cursor {
+    /// Produce the next value, or `None` if the iterator is exhausted.
+    pub fn next(
+        &mut self,
+        engine: &mut Engine,
+        context: Tracked<Context>,
+    ) -> SourceResult<Option<Value>> {
+        Ok(match self {
+            Self::Array(iter) => iter.next(),
+            Self::Csv(rows) => rows.next_row()?,
+            Self::Json(items) => items.next_item()?,
+            Self::Map(inner, mapper) => match inner.next(engine, context)? {
+                Some(value) => Some(mapper.call(engine, context, [value])?),
+                None => None,
+            },
+            Self::Filter(inner, test) => loop {
+                let Some(value) = inner.next(engine, context)? else { break None };
+                if test
+                    .call(engine, context, [value.clone()])?
+                    .cast::<bool>()
+                    .at(test.span())?
+                {
+                    break Some(value);
+                }
+            },
+            Self::Enumerate(inner, index, span) => match inner.next(engine, context)? {
+                Some(value) => {
+                    let pair = array![*index, value].into_value();
+                    *index = index
+                        .checked_add(1)
+                        .ok_or("array index is too large")
+                        .at(*span)?;
+                    Some(pair)
+                }
+                None => None,
+            },
+            Self::Zip(inners) => {
+                let mut items = Array::with_capacity(inners.len());
+                for inner in inners {
+                    let Some(value) = inner.next(engine, context)? else {
+                        return Ok(None);
+                    };
+                    items.push(value);
+                }
+                Some(items.into_value())
+            }
+            Self::Take(inner, remaining) => {
+                if *remaining == 0 {
+                    return Ok(None);
+                }
+                *remaining -= 1;
+                inner.next(engine, context)?
+            }
+            Self::Skip(inner, remaining) => {
+                while *remaining > 0 {
+                    *remaining -= 1;
+                    if inner.next(engine, context)?.is_none() {
+                        return Ok(None);
+                    }
+                }
+                inner.next(engine, context)?
+            }
+            Self::Chunks(inner, size, exact) => {
+                let mut chunk = Array::with_capacity(*size);
+                while chunk.len() < *size {
+                    let Some(value) = inner.next(engine, context)? else { break };
+                    chunk.push(value);
+                }
+                if chunk.is_empty() || (*exact && chunk.len() < *size) {
+                    return Ok(None);
+                }
+                Some(chunk.into_value())
+            }
+            Self::Windows(inner, window, size) => {
+                while window.len() < *size {
+                    let Some(value) = inner.next(engine, context)? else {
+                        return Ok(None);
+                    };
+                    window.push_back(value);
+                }
+                let items: Array = window.iter().cloned().collect();
+                window.pop_front();
+                Some(items.into_value())
+            }
+            Self::Flatten(stack) => loop {
+                let Some(inner) = stack.last_mut() else { break None };
+                match inner.next(engine, context)? {
+                    Some(Value::Array(array)) => {
+                        stack.push(Self::Array(array.into_iter()))
+                    }
+                    Some(Value::Dyn(dynamic)) if dynamic.is::<Iter>() => {
+                        stack.push(dynamic.downcast::<Iter>().unwrap().cursor()?);
+                    }
+                    Some(value) => break Some(value),
+                    None => {
+                        stack.pop();
+                    }
+                }
+            },
+            Self::GroupBy(inner, key, pending) => {
+                let (group, first) = match pending.take() {
+                    Some(pending) => pending,
+                    None => match inner.next(engine, context)? {
+                        Some(value) => {
+                            (key.call(engine, context, [value.clone()])?, value)
+                        }
+                        None => return Ok(None),
+                    },
+                };
+
+                let mut values = array![first];
+                while let Some(value) = inner.next(engine, context)? {
+                    let k = key.call(engine, context, [value.clone()])?;
+                    if !ops::equal(&k, &group) {
+                        *pending = Some((k, value));
+                        break;
+                    }
+                    values.push(value);
+                }
+
+                Some(array![group, values].into_value())
+            }
+        })
+    }
+}
+
+impl Debug for Cursor {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.pad("Cursor(..)")
+    }
+}
@@ -1,6 +1,7 @@
 //! Foundational types and functions.
 
 pub mod calc;
+pub mod record;
 pub mod repr;
 pub mod sys;
 
@@ -12,14 +13,17 @@ mod bytes;
 mod cast;
 mod content;
 mod context;
+mod custom;
 mod datetime;
 mod dict;
 mod duration;
 mod element;
+mod error;
 mod fields;
 mod float;
 mod func;
 mod int;
+mod iterator;
 mod label;
 mod methods;
 mod module;
@@ -40,19 +44,23 @@ pub use self::bytes::*;
 pub use self::cast::*;
 pub use self::content::*;
 pub use self::context::*;
+pub use self::custom::*;
 pub use self::datetime::*;
 pub use self::dict::*;
 pub use self::duration::*;
 pub use self::element::*;
+pub use self::error::*;
 pub use self::fields::*;
 pub use self::float::*;
 pub use self::func::*;
 pub use self::int::*;
+pub use self::iterator::*;
 pub use self::label::*;
 pub use self::methods::*;
 pub use self::module::*;
 pub use self::none::*;
 pub use self::plugin::*;
+pub use self::record::*;
 pub use self::repr::Repr;
 pub use self::scope::*;
 pub use self::selector::*;
@@ -106,11 +114,16 @@ pub(super) fn define(global: &mut Scope, inputs: Dict) {
     global.define_type::<Duration>();
     global.define_type::<Version>();
     global.define_type::<Plugin>();
+    global.define_type::<Error>();
+    global.define_type::<Iter>();
     global.define_func::<repr::repr>();
     global.define_func::<panic>();
     global.define_func::<assert>();
+    global.define_func::<catch>();
     global.define_func::<eval>();
     global.define_func::<style>();
+    global.define_func::<define_element>();
+    global.define_func::<record::record>();
     global.define_module(calc::module());
     global.define_module(sys::module(inputs));
 }
@@ -5,6 +5,7 @@ use ecow::{eco_format, EcoString};
 
 use crate::diag::StrResult;
 use crate::foundations::{repr, ty, Content, Scope, Value};
+use crate::syntax::FileId;
 
 /// An evaluated module, either built-in or resulting from a file.
 ///
@@ -43,6 +44,8 @@ struct Repr {
     scope: Scope,
     /// The module's layoutable contents.
     content: Content,
+    /// The file the module was evaluated from, if any.
+    file: Option<FileId>,
 }
 
 impl Module {
@@ -50,7 +53,7 @@ impl Module {
     pub fn new(name: impl Into<EcoString>, scope: Scope) -> Self {
         Self {
             name: name.into(),
-            inner: Arc::new(Repr { scope, content: Content::empty() }),
+            inner: Arc::new(Repr { scope, content: Content::empty(), file: None }),
         }
     }
 
@@ -72,6 +75,12 @@ impl Module {
         self
     }
 
+    /// Update the file the module was evaluated from.
+    pub fn with_file(mut self, file: FileId) -> Self {
+        Arc::make_mut(&mut self.inner).file = Some(file);
+        self
+    }
+
     /// Get the module's name.
     pub fn name(&self) -> &EcoString {
         &self.name
@@ -87,6 +96,11 @@ impl Module {
         &mut Arc::make_mut(&mut self.inner).scope
     }
 
+    /// The file the module was evaluated from, if it isn't built-in.
+    pub fn file(&self) -> Option<FileId> {
+        self.inner.file
+    }
+
     /// Try to access a definition in the module.
     pub fn field(&self, name: &str) -> StrResult<&Value> {
         self.scope().get(name).ok_or_else(|| {
@@ -109,6 +123,7 @@ impl Debug for Module {
             .field("name", &self.name)
             .field("scope", &self.inner.scope)
             .field("content", &self.inner.content)
+            .field("file", &self.inner.file)
             .finish()
     }
 }
@@ -0,0 +1,245 @@
+//! User-defined record types.
+
+use std::fmt::{self, Debug, Formatter};
+
+use ecow::{eco_format, EcoString};
+use once_cell::sync::Lazy;
+
+use crate::diag::{bail, SourceResult, StrResult};
+use crate::foundations::{
+    cast, func, repr, title_case, Args, CustomField, Definitions, Dict, Func, NativeFunc,
+    NativeType, NativeTypeData, ParamInfo, Repr, Scope, Str, Type, Value,
+};
+use crate::syntax::{is_ident, Span};
+use crate::util::{hash128, PicoStr};
+
+/// Defines a new type with named fields and methods.
+///
+/// Records bundle related values, just like dictionaries, but their fields
+/// are fixed and checked: Constructing a record validates that all required
+/// fields are given and that each field has the correct type. A record's
+/// fields are accessed just like a dictionary's, but can't be modified.
+///
+/// The defined type can be called to construct a new instance. `type` of an
+/// instance returns the defined type, and two instances are equal if they are
+/// of the same type and all their fields are equal.
+///
+/// ```example
+/// #let item = record(
+///   "item",
+///   fields: (
+///     name: str,
+///     price: float,
+///     qty: (type: int, default: 1),
+///   ),
+///   methods: (
+///     total: self => self.price * self.qty,
+///   ),
+/// )
+///
+/// #let apple = item(name: "Apple", price: 0.5, qty: 4)
+/// #apple.name costs #apple.total().
+///
+/// #repr(item(name: "Pear", price: 1))
+/// ```
+///
+/// # Fields
+/// Fields are specified in the same way as for [custom elements]($element):
+/// Through a type, an array of types, or a dictionary with the keys `type`,
+/// `default`, and `positional`. Fields without a default are required.
+///
+/// # Methods
+/// Each method is a function that receives the instance as its first
+/// argument, followed by the arguments of the method call.
+#[func]
+pub fn record(
+    /// The call span of this function.
+    span: Span,
+    /// The type's name.
+    name: Str,
+    /// The type's fields, as a dictionary from field names to their
+    /// specification.
+    #[named]
+    #[default]
+    fields: Dict,
+    /// The type's methods, as a dictionary from method names to functions.
+    #[named]
+    #[default]
+    methods: Dict,
+) -> StrResult<Type> {
+    if !is_ident(&name) {
+        bail!("invalid type name: {}", name.repr());
+    }
+
+    // Reuse the type of an identical definition so that types stay the same
+    // across re-evaluations.
+    let key = hash128(&(&name, &fields, &methods));
+    let data = TYPES.get_or_define(span, key, || {
+        let fields = fields
+            .into_iter()
+            .map(|(name, spec)| CustomField::parse(&name, spec))
+            .collect::<StrResult<Vec<_>>>()?;
+
+        let mut scope = Scope::new();
+        for (name, method) in methods {
+            if fields.iter().any(|field| field.name == name.as_str()) {
+                bail!("method {} conflicts with field of the same name", name.repr());
+            }
+            let func = method
+                .cast::<Func>()
+                .map_err(|_| eco_format!("method {} must be a function", name.repr()))?;
+            scope.define(name, func);
+        }
+
+        let params = fields
+            .iter()
+            .map(|field| ParamInfo { settable: false, ..field.param() })
+            .collect();
+        let names = fields.iter().map(|field| field.name).collect();
+        let custom = CustomTypeData { fields, names, params, scope };
+
+        let name = PicoStr::new(&name).resolve();
+        Ok(NativeTypeData {
+            name,
+            long_name: name,
+            title: PicoStr::new(&title_case(name)).resolve(),
+            docs: "",
+            keywords: &[],
+            constructor: Lazy::new(|| None),
+            scope: Lazy::new(Scope::new),
+            custom: Some(custom),
+        })
+    })?;
+
+    Ok(Type::from(data))
+}
+
+/// All types defined so far, by their call site.
+static TYPES: Lazy<Definitions<NativeTypeData>> = Lazy::new(Definitions::new);
+
+/// The definition of a type that was defined in Typst code.
+#[derive(Debug)]
+pub struct CustomTypeData {
+    fields: Vec<CustomField>,
+    names: Vec<&'static str>,
+    params: Vec<ParamInfo>,
+    scope: Scope,
+}
+
+impl CustomTypeData {
+    /// The function that constructs instances of user-defined types. It
+    /// expects the type as its first argument.
+    pub(crate) fn constructor() -> Func {
+        construct_record::func()
+    }
+
+    /// The type's methods.
+    pub(crate) fn scope(&'static self) -> &'static Scope {
+        &self.scope
+    }
+
+    /// The names of the type's fields.
+    pub fn fields(&'static self) -> &'static [&'static str] {
+        &self.names
+    }
+
+    /// Details about the parameters of the type's constructor.
+    pub fn params(&'static self) -> &'static [ParamInfo] {
+        &self.params
+    }
+}
+
+/// Constructs an instance of a user-defined type.
+#[func]
+fn construct_record(
+    /// The type to construct and the values of its fields.
+    args: &mut Args,
+) -> SourceResult<Record> {
+    let ty: Type = args.expect("type")?;
+    let Some(custom) = ty.custom() else {
+        bail!(args.span, "expected user-defined type, found {}", ty.repr());
+    };
+
+    let fields = custom
+        .fields
+        .iter()
+        .map(|field| {
+            let value = field.take(args)?;
+            Ok(value.or_else(|| field.default.clone()).unwrap_or_default())
+        })
+        .collect::<SourceResult<_>>()?;
+
+    Ok(Record { ty, fields })
+}
+
+/// An instance of a type that was defined in Typst code.
+#[derive(Clone, PartialEq, Hash)]
+pub struct Record {
+    /// The type this is an instance of.
+    ty: Type,
+    /// The values of the fields, in the order of the type's definition.
+    fields: Vec<Value>,
+}
+
+impl Record {
+    /// Get the value of the field with the given name.
+    pub fn field(&self, name: &str) -> StrResult<&Value> {
+        self.names()
+            .iter()
+            .position(|&field| field == name)
+            .map(|i| &self.fields[i])
+            .ok_or_else(|| eco_format!("{} does not contain field \"{name}\"", self.ty))
+    }
+
+    /// The names of the fields.
+    fn names(&self) -> &'static [&'static str] {
+        match self.ty.custom() {
+            Some(custom) => custom.fields(),
+            None => &[],
+        }
+    }
+}
+
+impl NativeType for Record {
+    const NAME: &'static str = "record";
+
+    fn data() -> &'static NativeTypeData {
+        static DATA: NativeTypeData = NativeTypeData {
+            name: "record",
+            long_name: "record",
+            title: "Record",
+            docs: "",
+            keywords: &[],
+            constructor: Lazy::new(|| None),
+            scope: Lazy::new(Scope::new),
+            custom: None,
+        };
+        &DATA
+    }
+
+    fn instance_ty(&self) -> Type {
+        self.ty
+    }
+}
+
+impl Debug for Record {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&self.repr())
+    }
+}
+
+impl Repr for Record {
+    fn repr(&self) -> EcoString {
+        let fields = self
+            .names()
+            .iter()
+            .zip(&self.fields)
+            .map(|(name, value)| eco_format!("{name}: {}", value.repr()))
+            .collect::<Vec<_>>();
+        eco_format!("{}{}", self.ty.short_name(), repr::pretty_array_like(&fields, false))
+    }
+}
+
+cast! {
+    type Record,
+}
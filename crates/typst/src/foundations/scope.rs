@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::fmt::{self, Debug, Formatter};
 use std::hash::{Hash, Hasher};
 
@@ -74,6 +75,39 @@ impl<'a> Scopes<'a> {
                 }
             })?
     }
+
+    /// Find the qualified names like `calc.pow` under which a variable that is
+    /// not in scope itself can be accessed through a module that is.
+    ///
+    /// Symbols are skipped as short names like `b` are defined by the symbol
+    /// modules without the user likely meaning them.
+    pub fn qualified(&self, var: &str, in_math: bool) -> Vec<EcoString> {
+        let base = self.base.map(|base| if in_math { &base.math } else { &base.global });
+        let mut seen = HashSet::new();
+        let mut names = vec![];
+        for scope in std::iter::once(&self.top)
+            .chain(self.scopes.iter().rev())
+            .chain(base.map(|base| base.scope()))
+        {
+            for (name, value) in scope.iter() {
+                // Inner definitions shadow outer ones.
+                if !seen.insert(name) {
+                    continue;
+                }
+
+                if let Value::Module(module) = value {
+                    if module
+                        .scope()
+                        .get(var)
+                        .is_some_and(|value| !matches!(value, Value::Symbol(_)))
+                    {
+                        names.push(eco_format!("{name}.{var}"));
+                    }
+                }
+            }
+        }
+        names
+    }
 }
 
 /// The error message when a variable is not found.
@@ -129,15 +163,42 @@ impl Scope {
     /// Bind a value to a name.
     #[track_caller]
     pub fn define(&mut self, name: impl Into<EcoString>, value: impl IntoValue) {
-        let name = name.into();
+        let slot = Slot::new(value.into_value(), Kind::Normal, self.category);
+        self.insert(name.into(), slot);
+    }
+
+    /// Bind a value to a name that is not part of the module this scope
+    /// belongs to.
+    #[track_caller]
+    pub fn define_private(&mut self, name: impl Into<EcoString>, value: impl IntoValue) {
+        let mut slot = Slot::new(value.into_value(), Kind::Normal, self.category);
+        slot.private = true;
+        self.insert(name.into(), slot);
+    }
+
+    /// Bind a value that was brought into scope by an import or export.
+    #[track_caller]
+    pub fn define_import(
+        &mut self,
+        name: impl Into<EcoString>,
+        value: impl IntoValue,
+        import: Import,
+    ) {
+        let mut slot = Slot::new(value.into_value(), Kind::Normal, self.category);
+        slot.private = import.private;
+        slot.glob = import.glob;
+        self.insert(name.into(), slot);
+    }
 
+    /// Insert a slot, checking for duplicates if requested.
+    #[track_caller]
+    fn insert(&mut self, name: EcoString, slot: Slot) {
         #[cfg(debug_assertions)]
         if self.deduplicate && self.map.contains_key(&name) {
             panic!("duplicate definition: {name}");
         }
 
-        self.map
-            .insert(name, Slot::new(value.into_value(), Kind::Normal, self.category));
+        self.map.insert(name, slot);
     }
 
     /// Define a native function through a Rust type that shadows the function.
@@ -199,6 +260,31 @@ impl Scope {
         self.map.get(var)?.category
     }
 
+    /// Whether a definition was brought into scope by a wildcard import.
+    pub fn is_glob_import(&self, var: &str) -> bool {
+        self.map.get(var).is_some_and(|slot| slot.glob)
+    }
+
+    /// The definitions that are visible from outside of a module with this
+    /// scope.
+    ///
+    /// These are all definitions, including imported ones, except for those
+    /// that are marked as `private`.
+    pub fn exports(&self) -> Scope {
+        let map = self
+            .map
+            .iter()
+            .filter(|(_, slot)| !slot.private)
+            .map(|(name, slot)| (name.clone(), slot.clone()))
+            .collect();
+
+        Self {
+            map,
+            deduplicate: self.deduplicate,
+            category: self.category,
+        }
+    }
+
     /// Iterate over all definitions.
     pub fn iter(&self) -> impl Iterator<Item = (&EcoString, &Value)> {
         self.map.iter().map(|(k, v)| (k, v.read()))
@@ -234,6 +320,15 @@ pub trait NativeScope {
     fn scope() -> Scope;
 }
 
+/// How a binding was brought into scope by an import or export.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Import {
+    /// Whether the import is marked as `private`.
+    pub private: bool,
+    /// Whether the binding was brought into scope by a wildcard import.
+    pub glob: bool,
+}
+
 /// A slot where a value is stored.
 #[derive(Clone, Hash)]
 struct Slot {
@@ -243,6 +338,10 @@ struct Slot {
     kind: Kind,
     /// The category of the slot.
     category: Option<Category>,
+    /// Whether the slot is not part of the module its scope belongs to.
+    private: bool,
+    /// Whether the slot was filled by a wildcard import.
+    glob: bool,
 }
 
 /// The different kinds of slots.
@@ -266,7 +365,7 @@ pub enum Capturer {
 impl Slot {
     /// Create a new slot.
     fn new(value: Value, kind: Kind, category: Option<Category>) -> Self {
-        Self { value, kind, category }
+        Self { value, kind, category, private: false, glob: false }
     }
 
     /// Read the value.
@@ -244,12 +244,15 @@ impl Property {
         E: NativeElement,
         T: Debug + Clone + Hash + Send + Sync + 'static,
     {
-        Self {
-            elem: E::elem(),
-            id,
-            value: Block::new(value),
-            span: None,
-        }
+        Self::of(E::elem(), id, value)
+    }
+
+    /// Create a new property for an element that is only known at runtime.
+    pub fn of<T>(elem: Element, id: u8, value: T) -> Self
+    where
+        T: Debug + Clone + Hash + Send + Sync + 'static,
+    {
+        Self { elem, id, value: Block::new(value), span: None }
     }
 
     /// Whether this property is the given one.
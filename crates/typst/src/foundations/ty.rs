@@ -5,7 +5,12 @@ use ecow::{eco_format, EcoString};
 use once_cell::sync::Lazy;
 
 use crate::diag::StrResult;
-use crate::foundations::{cast, func, Func, NativeFuncData, Repr, Scope, Value};
+use crate::foundations::{
+    cast, func, Args, Content, CustomTypeData, Func, NativeFuncData, Repr, Scope, Str,
+    Value,
+};
+use crate::layout::{Length, Rel};
+use crate::syntax::Span;
 use crate::util::Static;
 
 #[rustfmt::skip]
@@ -90,6 +95,11 @@ impl Type {
 
     /// This type's constructor function.
     pub fn constructor(&self) -> StrResult<Func> {
+        if self.custom().is_some() {
+            let mut args = Args::new(Span::detached(), [*self]);
+            return Ok(CustomTypeData::constructor().with(&mut args));
+        }
+
         self.0
             .constructor
             .as_ref()
@@ -99,7 +109,10 @@ impl Type {
 
     /// The type's associated scope that holds sub-definitions.
     pub fn scope(&self) -> &'static Scope {
-        &(self.0).0.scope
+        match self.custom() {
+            Some(custom) => custom.scope(),
+            None => &(self.0).0.scope,
+        }
     }
 
     /// Get a field from this type's scope, if possible.
@@ -108,6 +121,39 @@ impl Type {
             .get(field)
             .ok_or_else(|| eco_format!("type {self} does not contain field `{field}`"))
     }
+
+    /// The definition of the type if it was defined in Typst code.
+    pub fn custom(self) -> Option<&'static CustomTypeData> {
+        (self.0).0.custom.as_ref()
+    }
+
+    /// Check whether a value is of this type, performing the same implicit
+    /// conversions as native functions do when casting their arguments (e.g.
+    /// from an integer to a float).
+    ///
+    /// Returns the possibly converted value if it is compatible.
+    pub fn coerce(self, value: &Value) -> Option<Value> {
+        if value.ty() == self {
+            return Some(value.clone());
+        }
+
+        Some(match value {
+            Value::Int(v) if self == Type::of::<f64>() => Value::Float(*v as f64),
+            Value::Length(v) if self == Type::of::<Rel<Length>>() => {
+                Value::Relative((*v).into())
+            }
+            Value::Ratio(v) if self == Type::of::<Rel<Length>>() => {
+                Value::Relative((*v).into())
+            }
+            Value::Symbol(v) if self == Type::of::<Str>() => Value::Str(v.get().into()),
+            Value::None | Value::Symbol(_) | Value::Str(_)
+                if self == Type::of::<Content>() =>
+            {
+                Value::Content(value.clone().display())
+            }
+            _ => return None,
+        })
+    }
 }
 
 // Type compatibility.
@@ -184,6 +230,17 @@ pub trait NativeType {
 
     // Get the type data for the native Rust type.
     fn data() -> &'static NativeTypeData;
+
+    /// Get the type of this instance.
+    ///
+    /// This is the same as [`ty`](Self::ty) except for user-defined types,
+    /// whose instances all share the same Rust type.
+    fn instance_ty(&self) -> Type
+    where
+        Self: Sized,
+    {
+        Self::ty()
+    }
 }
 
 /// Defines a native type.
@@ -196,6 +253,7 @@ pub struct NativeTypeData {
     pub keywords: &'static [&'static str],
     pub constructor: Lazy<Option<&'static NativeFuncData>>,
     pub scope: Lazy<Scope>,
+    pub custom: Option<CustomTypeData>,
 }
 
 impl From<&'static NativeTypeData> for Type {
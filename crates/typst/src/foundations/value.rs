@@ -561,7 +561,7 @@ where
     }
 
     fn dyn_ty(&self) -> Type {
-        Type::of::<T>()
+        self.instance_ty()
     }
 
     fn dyn_hash(&self, mut state: &mut dyn Hasher) {
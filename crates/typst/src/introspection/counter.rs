@@ -316,6 +316,7 @@ impl Counter {
         let mut stops = eco_vec![(state.clone(), page)];
 
         for elem in introspector.query(&self.selector()) {
+            engine.check_interrupt(elem.span())?;
             if self.is_page() {
                 let prev = page;
                 page = introspector.page(elem.location().unwrap());
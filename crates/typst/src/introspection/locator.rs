@@ -1,10 +1,11 @@
-use std::cell::RefCell;
 use std::collections::HashMap;
 
 use comemo::{Track, Tracked, Validate};
+use parking_lot::Mutex;
 
 use crate::introspection::{Location, Meta};
 use crate::layout::{Frame, FrameItem};
+use crate::util::hash128;
 
 /// Provides locations for elements in the document.
 ///
@@ -24,11 +25,14 @@ use crate::layout::{Frame, FrameItem};
 /// [^1]: Well, we could with [`TrackedMut`](comemo::TrackedMut), but the
 /// overhead is quite high, especially since we need to save & undo the counting
 /// when only measuring.
-#[derive(Default, Clone)]
+#[derive(Default)]
 pub struct Locator<'a> {
     /// Maps from a hash to the maximum number we've seen for this hash. This
     /// number becomes the `disambiguator`.
-    hashes: RefCell<HashMap<u128, usize>>,
+    ///
+    /// This is behind a mutex so that locators can be shared by tasks that
+    /// run in parallel.
+    hashes: Mutex<HashMap<u128, usize>>,
     /// An outer `Locator`, from which we can get disambiguator for hashes
     /// outside of the current "layout run".
     ///
@@ -36,6 +40,10 @@ pub struct Locator<'a> {
     /// covariant over the constraint. If it becomes invariant, we're in for a
     /// world of lifetime pain.
     outer: Option<Tracked<'a, Self, <Locator<'static> as Validate>::Constraint>>,
+    /// Mixed into the hashes of all locations this locator and the locators
+    /// chained to it produce. This keeps the locations of parts of the
+    /// document that are laid out independently of each other distinct.
+    salt: u128,
 }
 
 impl<'a> Locator<'a> {
@@ -46,22 +54,41 @@ impl<'a> Locator<'a> {
 
     /// Create a new chained locator.
     pub fn chained(outer: Tracked<'a, Self>) -> Self {
-        Self { outer: Some(outer), ..Default::default() }
+        Self {
+            outer: Some(outer),
+            salt: outer.salt(),
+            ..Default::default()
+        }
+    }
+
+    /// Mix a salt into the hashes of all locations this locator and the
+    /// locators chained to it produce.
+    ///
+    /// Locators with different salts can't produce the same locations, even if
+    /// they don't know of each other, for instance because they are used by
+    /// tasks that run in parallel.
+    pub fn with_salt(self, salt: u128) -> Self {
+        Self { salt: hash128(&(self.salt, salt)), ..self }
     }
 
     /// Start tracking this locator.
     ///
     /// In comparison to [`Track::track`], this method skips this chain link
-    /// if it does not contribute anything.
+    /// if it does not contribute anything. A link with its own salt always
+    /// contributes, even if it hasn't produced any locations yet.
     pub fn track(&self) -> Tracked<'_, Self> {
         match self.outer {
-            Some(outer) if self.hashes.borrow().is_empty() => outer,
+            Some(outer) if self.hashes.lock().is_empty() && outer.salt() == self.salt => {
+                outer
+            }
             _ => Track::track(self),
         }
     }
 
     /// Produce a stable identifier for this call site.
     pub fn locate(&mut self, hash: u128) -> Location {
+        let hash = if self.salt == 0 { hash } else { hash128(&(hash, self.salt)) };
+
         // Get the current disambiguator for this hash.
         let disambiguator = self.disambiguator_impl(hash);
 
@@ -102,16 +129,31 @@ impl<'a> Locator<'a> {
     fn disambiguator_impl(&self, hash: u128) -> usize {
         *self
             .hashes
-            .borrow_mut()
+            .lock()
             .entry(hash)
             .or_insert_with(|| self.outer.map_or(0, |outer| outer.disambiguator(hash)))
     }
 }
 
+impl Clone for Locator<'_> {
+    fn clone(&self) -> Self {
+        Self {
+            hashes: Mutex::new(self.hashes.lock().clone()),
+            outer: self.outer,
+            salt: self.salt,
+        }
+    }
+}
+
 #[comemo::track]
 impl<'a> Locator<'a> {
     /// The current disambiguator for the hash.
     fn disambiguator(&self, hash: u128) -> usize {
         self.disambiguator_impl(hash)
     }
+
+    /// The salt mixed into the hashes of locations.
+    fn salt(&self) -> u128 {
+        self.salt
+    }
 }
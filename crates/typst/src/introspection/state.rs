@@ -245,6 +245,7 @@ impl State {
         let mut stops = eco_vec![state.clone()];
 
         for elem in introspector.query(&self.selector()) {
+            engine.check_interrupt(elem.span())?;
             let elem = elem.to_packed::<StateUpdateElem>().unwrap();
             match elem.update() {
                 StateUpdate::Set(value) => state = value.clone(),
@@ -25,7 +25,7 @@ use crate::layout::{
 };
 use crate::syntax::Span;
 use crate::text::TextElem;
-use crate::util::{MaybeReverseIter, NonZeroExt, Numeric};
+use crate::util::{hash128, MaybeReverseIter, NonZeroExt, Numeric};
 use crate::visualize::{Geometry, Paint, Stroke};
 
 /// A value that can be configured per cell.
@@ -2696,7 +2696,10 @@ impl<'a> GridLayouter<'a> {
             bail!(self.span, "cannot create grid with infinite height");
         }
 
-        let mut output = Frame::soft(Size::new(self.width, height));
+        // The cells of a row don't depend on each other, so we lay them out
+        // in parallel. Their locations are salted with their position.
+        let mut tasks = vec![];
+        let mut positions = vec![];
         let mut pos = Point::zero();
 
         // Reverse the column order when using RTL.
@@ -2715,7 +2718,6 @@ impl<'a> GridLayouter<'a> {
                         // rows.
                         pod.full = self.regions.full;
                     }
-                    let frame = cell.layout(engine, self.styles, pod)?.into_frame();
                     let mut pos = pos;
                     if self.is_rtl {
                         // In the grid, cell colspans expand to the right,
@@ -2730,13 +2732,25 @@ impl<'a> GridLayouter<'a> {
                         let offset = -width + rcol;
                         pos.x += offset;
                     }
-                    output.push_frame(pos, frame);
+                    tasks.push((hash128(&(x, y)), (cell, pod)));
+                    positions.push(pos);
                 }
             }
 
             pos.x += rcol;
         }
 
+        let styles = self.styles;
+        let fragments = engine
+            .parallelize(tasks, |engine, (cell, pod)| cell.layout(engine, styles, pod));
+
+        let mut output = Frame::soft(Size::new(self.width, height));
+        for (pos, fragment) in positions.into_iter().zip(fragments) {
+            let frame = fragment?.into_frame();
+            engine.locator.visit_frame(&frame);
+            output.push_frame(pos, frame);
+        }
+
         Ok(output)
     }
 
@@ -2759,7 +2773,9 @@ impl<'a> GridLayouter<'a> {
         pod.full = self.regions.full;
         pod.backlog = &heights[1..];
 
-        // Layout the row.
+        // Layout the row's cells in parallel, like in `layout_single_row`.
+        let mut tasks = vec![];
+        let mut positions = vec![];
         let mut pos = Point::zero();
         for (x, &rcol) in self.rcols.iter().enumerate().rev_if(self.is_rtl) {
             if let Some(cell) = self.grid.cell(x, y) {
@@ -2768,22 +2784,32 @@ impl<'a> GridLayouter<'a> {
                     let width = self.cell_spanned_width(cell, x);
                     pod.size.x = width;
 
-                    // Push the layouted frames into the individual output frames.
-                    let fragment = cell.layout(engine, self.styles, pod)?;
-                    for (output, frame) in outputs.iter_mut().zip(fragment) {
-                        let mut pos = pos;
-                        if self.is_rtl {
-                            let offset = -width + rcol;
-                            pos.x += offset;
-                        }
-                        output.push_frame(pos, frame);
+                    let mut pos = pos;
+                    if self.is_rtl {
+                        let offset = -width + rcol;
+                        pos.x += offset;
                     }
+                    tasks.push((hash128(&(x, y)), (cell, pod)));
+                    positions.push(pos);
                 }
             }
 
             pos.x += rcol;
         }
 
+        let styles = self.styles;
+        let fragments = engine
+            .parallelize(tasks, |engine, (cell, pod)| cell.layout(engine, styles, pod));
+
+        // Push the layouted frames into the individual output frames.
+        for (pos, fragment) in positions.into_iter().zip(fragments) {
+            let fragment = fragment?;
+            engine.locator.visit_frames(&fragment);
+            for (output, frame) in outputs.iter_mut().zip(fragment) {
+                output.push_frame(pos, frame);
+            }
+        }
+
         Ok(Fragment::frames(outputs))
     }
 
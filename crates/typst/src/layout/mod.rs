@@ -71,7 +71,7 @@ pub(crate) use self::inline::*;
 
 use comemo::{Tracked, TrackedMut};
 
-use crate::diag::{bail, SourceResult};
+use crate::diag::{bail, error, SourceResult};
 use crate::engine::{Engine, Route};
 use crate::eval::Tracer;
 use crate::foundations::{category, Category, Content, Scope, StyleChain};
@@ -241,12 +241,15 @@ impl LayoutMultiple for Content {
             };
 
             if !engine.route.within(Route::MAX_LAYOUT_DEPTH) {
-                bail!(
+                bail!(error!(
                     content.span(), "maximum layout depth exceeded";
                     hint: "try to reduce the amount of nesting in your layout",
-                );
+                )
+                .into_fatal());
             }
 
+            engine.check_interrupt(content.span())?;
+
             let arenas = Arenas::default();
             let (realized, styles) =
                 realize_block(&mut engine, &arenas, content, styles)?;
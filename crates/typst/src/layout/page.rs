@@ -334,7 +334,6 @@ impl Packed<PageElem> {
     /// while we post-process the pages in this function. This function returns
     /// a fragment consisting of multiple frames, one per output page of this
     /// page run.
-    #[typst_macros::time(name = "page", span = self.span())]
     pub fn layout(
         &self,
         engine: &mut Engine,
@@ -342,38 +341,22 @@ impl Packed<PageElem> {
         page_counter: &mut ManualPageCounter,
         extend_to: Option<Parity>,
     ) -> SourceResult<Vec<Page>> {
-        // When one of the lengths is infinite the page fits its content along
-        // that axis.
-        let width = self.width(styles).unwrap_or(Abs::inf());
-        let height = self.height(styles).unwrap_or(Abs::inf());
-        let mut size = Size::new(width, height);
-        if self.flipped(styles) {
-            std::mem::swap(&mut size.x, &mut size.y);
-        }
-
-        let mut min = width.min(height);
-        if !min.is_finite() {
-            min = Paper::A4.width();
-        }
-
-        // Determine the margins.
-        let default = Rel::<Length>::from((2.5 / 21.0) * min);
-        let margin = self.margin(styles);
-        let two_sided = margin.two_sided.unwrap_or(false);
-        let margin = margin
-            .sides
-            .map(|side| side.and_then(Smart::custom).unwrap_or(default))
-            .resolve(styles)
-            .relative_to(size);
-
-        // Determine the binding.
-        let binding =
-            self.binding(styles)
-                .unwrap_or_else(|| match TextElem::dir_in(styles) {
-                    Dir::LTR => Binding::Left,
-                    _ => Binding::Right,
-                });
+        let frames = self.layout_body(engine, styles)?;
+        self.finalize(engine, styles, page_counter, extend_to, frames)
+    }
 
+    /// Lays out the body of this page run into one frame per output page.
+    ///
+    /// The result does not depend on the page number the run starts at, so
+    /// the bodies of multiple page runs can be laid out independently of
+    /// each other. The frames must then be [finalized](Self::finalize) in
+    /// document order.
+    #[typst_macros::time(name = "page", span = self.span())]
+    pub fn layout_body(
+        &self,
+        engine: &mut Engine,
+        styles: StyleChain,
+    ) -> SourceResult<Vec<Frame>> {
         // Realize columns.
         let mut child = self.body().clone();
         let columns = self.columns(styles);
@@ -384,12 +367,25 @@ impl Packed<PageElem> {
                 .spanned(self.span());
         }
 
-        let area = size - margin.sum_by_axis();
+        let area = self.geometry(styles).area;
         let mut regions = Regions::repeat(area, area.map(Abs::is_finite));
         regions.root = true;
 
         // Layout the child.
-        let mut frames = child.layout(engine, styles, regions)?.into_frames();
+        Ok(child.layout(engine, styles, regions)?.into_frames())
+    }
+
+    /// Turns the frames produced by [`layout_body`](Self::layout_body) into
+    /// pages by adding margins, marginals, and fills and numbering them.
+    pub fn finalize(
+        &self,
+        engine: &mut Engine,
+        styles: StyleChain,
+        page_counter: &mut ManualPageCounter,
+        extend_to: Option<Parity>,
+        mut frames: Vec<Frame>,
+    ) -> SourceResult<Vec<Page>> {
+        let Geometry { margin, two_sided, binding, area } = self.geometry(styles);
 
         // Align the child to the pagebreak's parity.
         // Check for page count after adding the pending frames
@@ -513,6 +509,57 @@ impl Packed<PageElem> {
 
         Ok(pages)
     }
+
+    /// Determines the size of the page's content area, its margins, and its
+    /// binding.
+    fn geometry(&self, styles: StyleChain) -> Geometry {
+        // When one of the lengths is infinite the page fits its content along
+        // that axis.
+        let width = self.width(styles).unwrap_or(Abs::inf());
+        let height = self.height(styles).unwrap_or(Abs::inf());
+        let mut size = Size::new(width, height);
+        if self.flipped(styles) {
+            std::mem::swap(&mut size.x, &mut size.y);
+        }
+
+        let mut min = width.min(height);
+        if !min.is_finite() {
+            min = Paper::A4.width();
+        }
+
+        // Determine the margins.
+        let default = Rel::<Length>::from((2.5 / 21.0) * min);
+        let margin = self.margin(styles);
+        let two_sided = margin.two_sided.unwrap_or(false);
+        let margin = margin
+            .sides
+            .map(|side| side.and_then(Smart::custom).unwrap_or(default))
+            .resolve(styles)
+            .relative_to(size);
+
+        // Determine the binding.
+        let binding =
+            self.binding(styles)
+                .unwrap_or_else(|| match TextElem::dir_in(styles) {
+                    Dir::LTR => Binding::Left,
+                    _ => Binding::Right,
+                });
+
+        let area = size - margin.sum_by_axis();
+        Geometry { margin, two_sided, binding, area }
+    }
+}
+
+/// The resolved geometry of a page run.
+struct Geometry {
+    /// The page margins.
+    margin: Sides<Abs>,
+    /// Whether the left and right margins are swapped on every other page.
+    two_sided: bool,
+    /// The side the pages are bound on.
+    binding: Binding,
+    /// The size of the area available to the page's content.
+    area: Size,
 }
 
 /// A finished page.
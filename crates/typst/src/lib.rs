@@ -64,7 +64,7 @@ use ecow::{EcoString, EcoVec};
 use typst_timing::{timed, TimingScope};
 
 use crate::diag::{warning, FileResult, SourceDiagnostic, SourceResult};
-use crate::engine::{Engine, Route};
+use crate::engine::{CancellationToken, Cancelled, Engine, ResourceLimits, Route};
 use crate::eval::Tracer;
 use crate::foundations::{
     Array, Bytes, Content, Datetime, Dict, Module, Scope, StyleChain, Styles,
@@ -90,22 +90,59 @@ pub fn compile(world: &dyn World, tracer: &mut Tracer) -> SourceResult<Document>
     // Call `track` on the world just once to keep comemo's ID stable.
     let world = world.track();
 
+    // The root route keeps track of the time budget.
+    let route = Route::with_budget(world.library().limits.time_budget);
+    compile_impl(world, &route, tracer)
+}
+
+/// Compile a source file into a fully layouted document, aborting as soon as
+/// possible once the given token is cancelled.
+///
+/// - Returns `Ok(result)` if the compilation ran to completion. The result is
+///   the same as the one of [`compile`].
+/// - Returns `Err(Cancelled)` if the compilation was aborted because the token
+///   was cancelled.
+///
+/// This is useful for hosts that want to start a new compilation as soon as
+/// the inputs change, without waiting for a running compilation to finish.
+#[typst_macros::time(name = "compile")]
+pub fn compile_cancellable(
+    world: &dyn World,
+    tracer: &mut Tracer,
+    token: &CancellationToken,
+) -> Result<SourceResult<Document>, Cancelled> {
+    // Call `track` on the world just once to keep comemo's ID stable.
+    let world = world.track();
+
+    // The root route keeps track of the time budget and the token.
+    let route = Route::with_budget(world.library().limits.time_budget)
+        .with_cancellation(token.clone());
+
+    match compile_impl(world, &route, tracer) {
+        Err(_) if token.is_cancelled() => Err(Cancelled),
+        result => Ok(result),
+    }
+}
+
+/// Evaluate the main source file and typeset the resulting content.
+fn compile_impl(
+    world: Tracked<dyn World + '_>,
+    route: &Route,
+    tracer: &mut Tracer,
+) -> SourceResult<Document> {
     // Try to evaluate the source file into a module.
-    let module = crate::eval::eval(
-        world,
-        Route::default().track(),
-        tracer.track_mut(),
-        &world.main(),
-    )
-    .map_err(deduplicate)?;
+    let module =
+        crate::eval::eval(world, route.track(), tracer.track_mut(), &world.main())
+            .map_err(deduplicate)?;
 
     // Typeset the module's content, relayouting until convergence.
-    typeset(world, tracer, &module.content()).map_err(deduplicate)
+    typeset(world, route, tracer, &module.content()).map_err(deduplicate)
 }
 
 /// Relayout until introspection converges.
 fn typeset(
     world: Tracked<dyn World + '_>,
+    route: &Route,
     tracer: &mut Tracer,
     content: &Content,
 ) -> SourceResult<Document> {
@@ -115,14 +152,16 @@ fn typeset(
 
     let library = world.library();
     let styles = StyleChain::new(&library.styles);
+    let max_iterations = library.limits.max_layout_iterations.max(1);
 
     let mut iter = 0;
     let mut document = Document::default();
 
     // Relayout until all introspections stabilize.
-    // If that doesn't happen within five attempts, we give up.
+    // If that doesn't happen within the configured number of attempts, we
+    // give up.
     loop {
-        let _scope = TimingScope::new(ITER_NAMES[iter], None);
+        let _scope = TimingScope::new(ITER_NAMES.get(iter).unwrap_or(&"typeset"), None);
 
         // Clear delayed errors.
         tracer.delayed();
@@ -131,13 +170,14 @@ fn typeset(
         let mut locator = Locator::new();
         let mut engine = Engine {
             world,
-            route: Route::default(),
+            route: route.clone(),
             tracer: tracer.track_mut(),
             locator: &mut locator,
             introspector: document.introspector.track_with(&constraint),
         };
 
         // Layout!
+        engine.check_interrupt(Span::detached())?;
         document = content.layout_root(&mut engine, styles)?;
         document.introspector.rebuild(&document.pages);
         iter += 1;
@@ -146,9 +186,10 @@ fn typeset(
             break;
         }
 
-        if iter >= 5 {
+        if iter >= max_iterations {
             tracer.warn(warning!(
-                Span::detached(), "layout did not converge within 5 attempts";
+                Span::detached(),
+                "layout did not converge within {max_iterations} attempts";
                 hint: "check if any states or queries are updating themselves"
             ));
             break;
@@ -189,8 +230,11 @@ fn deduplicate(mut diags: EcoVec<SourceDiagnostic>) -> EcoVec<SourceDiagnostic>
 /// clients like language servers can also retain the source files and
 /// [edit](Source::edit) them in-place to benefit from better incremental
 /// performance.
+///
+/// Worlds must be thread-safe because independent parts of a document, like
+/// separate page runs, are laid out in parallel.
 #[comemo::track]
-pub trait World {
+pub trait World: Send + Sync {
     /// The standard library.
     ///
     /// Can be created through `Library::build()`.
@@ -229,6 +273,14 @@ pub trait World {
     fn packages(&self) -> &[(PackageSpec, Option<EcoString>)] {
         &[]
     }
+
+    /// A list of all files in the project.
+    ///
+    /// This function is optional to implement. It enhances the user experience
+    /// by enabling autocompletion for file paths.
+    fn files(&self) -> Vec<FileId> {
+        vec![]
+    }
 }
 
 /// Helper methods on [`World`] implementations.
@@ -255,6 +307,8 @@ pub struct Library {
     /// The default style properties (for page size, font selection, and
     /// everything else configurable via set and show rules).
     pub styles: Styles,
+    /// The limits on the resources a compilation may use.
+    pub limits: ResourceLimits,
 }
 
 impl Library {
@@ -277,6 +331,7 @@ impl Default for Library {
 #[derive(Debug, Clone, Default)]
 pub struct LibraryBuilder {
     inputs: Option<Dict>,
+    limits: ResourceLimits,
 }
 
 impl LibraryBuilder {
@@ -286,12 +341,23 @@ impl LibraryBuilder {
         self
     }
 
+    /// Configure the limits on the resources a compilation may use.
+    pub fn with_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
     /// Consumes the builder and returns a `Library`.
     pub fn build(self) -> Library {
         let math = math::module();
         let inputs = self.inputs.unwrap_or_default();
         let global = global(math.clone(), inputs);
-        Library { global, math, styles: Styles::new() }
+        Library {
+            global,
+            math,
+            styles: Styles::new(),
+            limits: self.limits,
+        }
     }
 }
 
@@ -352,3 +418,101 @@ fn prelude(global: &mut Scope) {
     global.define("horizon", Alignment::HORIZON);
     global.define("bottom", Alignment::BOTTOM);
 }
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::diag::FileError;
+
+    /// A world with a single source file and no fonts.
+    struct TestWorld {
+        library: Prehashed<Library>,
+        book: Prehashed<FontBook>,
+        main: Source,
+    }
+
+    impl TestWorld {
+        fn new(text: &str) -> Self {
+            Self {
+                library: Prehashed::new(Library::default()),
+                book: Prehashed::new(FontBook::new()),
+                main: Source::detached(text),
+            }
+        }
+    }
+
+    impl World for TestWorld {
+        fn library(&self) -> &Prehashed<Library> {
+            &self.library
+        }
+
+        fn book(&self) -> &Prehashed<FontBook> {
+            &self.book
+        }
+
+        fn main(&self) -> Source {
+            self.main.clone()
+        }
+
+        fn source(&self, id: FileId) -> FileResult<Source> {
+            if id == self.main.id() {
+                Ok(self.main.clone())
+            } else {
+                Err(FileError::NotFound(id.vpath().as_rootless_path().into()))
+            }
+        }
+
+        fn file(&self, id: FileId) -> FileResult<Bytes> {
+            Err(FileError::NotFound(id.vpath().as_rootless_path().into()))
+        }
+
+        fn font(&self, _: usize) -> Option<Font> {
+            None
+        }
+
+        fn today(&self, _: Option<i64>) -> Option<Datetime> {
+            None
+        }
+    }
+
+    #[test]
+    fn test_compile_cancelled_before_start() {
+        let world = TestWorld::new("Hello");
+        let token = CancellationToken::new();
+        token.cancel();
+        let result = compile_cancellable(&world, &mut Tracer::new(), &token);
+        assert_eq!(result.err(), Some(Cancelled));
+
+        // The aborted compilation must not leave behind memoized results that
+        // a later compilation would reuse.
+        let token = CancellationToken::new();
+        let result = compile_cancellable(&world, &mut Tracer::new(), &token);
+        assert!(matches!(result, Ok(Ok(_))));
+    }
+
+    #[test]
+    fn test_compile_cancelled_in_show_rule() {
+        // The show rule runs practically forever, so the compilation must be
+        // aborted from within it.
+        let world = TestWorld::new(
+            "#show heading: it => {
+               for i in range(1000) { for j in range(1000) { for k in range(1000) {} } }
+             }
+             = Heading",
+        );
+
+        let token = CancellationToken::new();
+        let canceller = token.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            canceller.cancel();
+        });
+
+        let result = compile_cancellable(&world, &mut Tracer::new(), &token);
+        handle.join().unwrap();
+        assert_eq!(result.err(), Some(Cancelled));
+    }
+}
@@ -1,10 +1,14 @@
+use std::io;
+
 use ecow::{eco_format, EcoString};
 
 use crate::diag::{bail, At, SourceResult};
 use crate::engine::Engine;
-use crate::foundations::{cast, func, scope, Array, Dict, IntoValue, Type, Value};
+use crate::foundations::{
+    cast, func, scope, Array, Bytes, Dict, IntoValue, Iter, Type, Value,
+};
 use crate::loading::Readable;
-use crate::syntax::Spanned;
+use crate::syntax::{Span, Spanned};
 use crate::World;
 
 /// Reads structured data from a CSV file.
@@ -24,6 +28,10 @@ use crate::World;
 ///   ..results.flatten(),
 /// )
 /// ```
+///
+/// # Large files
+/// To process files with hundreds of thousands of rows without holding them
+/// all in memory, use [`csv.rows`]($csv.rows) instead.
 #[func(scope, title = "CSV")]
 pub fn csv(
     /// The engine.
@@ -75,51 +83,187 @@ impl csv {
         #[default(RowType::Array)]
         row_type: RowType,
     ) -> SourceResult<Array> {
+        let data = CsvData::new(data, delimiter, row_type);
+        let mut rows = data.rows()?;
+        let mut array = Array::new();
+        while let Some(row) = rows.next_row()? {
+            array.push(row);
+        }
+
+        Ok(array)
+    }
+
+    /// Reads structured data from a CSV file row by row.
+    ///
+    /// Instead of an array, this returns an [iterator] that parses the rows
+    /// one at a time while it is being iterated over. Combined with the
+    /// iterator's adaptors, this makes it possible to process files with
+    /// hundreds of thousands of rows without holding them all in memory.
+    ///
+    /// ```typ
+    /// #let total = csv.rows("sales.csv", row-type: dictionary)
+    ///   .map(row => float(row.amount))
+    ///   .fold(0, (sum, x) => sum + x)
+    /// ```
+    #[func(title = "CSV Rows")]
+    pub fn rows(
+        /// The engine.
+        engine: &mut Engine,
+        /// Path to a CSV file.
+        path: Spanned<EcoString>,
+        /// The delimiter that separates columns in the CSV file.
+        /// Must be a single ASCII character.
+        #[named]
+        #[default]
+        delimiter: Delimiter,
+        /// How to represent the file's rows.
+        ///
+        /// - If set to `array`, each row is represented as a plain array of
+        ///   strings.
+        /// - If set to `dictionary`, each row is represented as a dictionary
+        ///   mapping from header keys to strings. This option only makes sense
+        ///   when a header row is present in the CSV file.
+        #[named]
+        #[default(RowType::Array)]
+        row_type: RowType,
+    ) -> SourceResult<Iter> {
+        let Spanned { v: path, span } = path;
+        let id = span.resolve_path(&path).at(span)?;
+        let data = engine.world.file(id).at(span)?;
+        let data = Spanned::new(Readable::Bytes(data), span);
+        Ok(self::csv::decode_rows(data, delimiter, row_type))
+    }
+
+    /// Reads structured data from a CSV string/bytes row by row.
+    #[func(title = "Decode CSV Rows")]
+    pub fn decode_rows(
+        /// CSV data.
+        data: Spanned<Readable>,
+        /// The delimiter that separates columns in the CSV file.
+        /// Must be a single ASCII character.
+        #[named]
+        #[default]
+        delimiter: Delimiter,
+        /// How to represent the file's rows.
+        ///
+        /// - If set to `array`, each row is represented as a plain array of
+        ///   strings.
+        /// - If set to `dictionary`, each row is represented as a dictionary
+        ///   mapping from header keys to strings. This option only makes sense
+        ///   when a header row is present in the CSV file.
+        #[named]
+        #[default(RowType::Array)]
+        row_type: RowType,
+    ) -> Iter {
+        Iter::csv(CsvData::new(data, delimiter, row_type))
+    }
+}
+
+/// CSV data whose rows can be parsed on demand.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct CsvData {
+    /// The raw data.
+    data: Bytes,
+    /// The delimiter that separates columns.
+    delimiter: u8,
+    /// How to represent the rows.
+    row_type: RowType,
+    /// The span of the data, for error reporting.
+    span: Span,
+}
+
+impl CsvData {
+    /// Prepare data for parsing.
+    fn new(data: Spanned<Readable>, delimiter: Delimiter, row_type: RowType) -> Self {
         let Spanned { v: data, span } = data;
-        let has_headers = row_type == RowType::Dict;
+        Self {
+            data: data.into(),
+            delimiter: delimiter.0 as u8,
+            row_type,
+            span,
+        }
+    }
+
+    /// Start parsing the rows from the beginning.
+    pub fn rows(&self) -> SourceResult<CsvRows> {
+        let has_headers = self.row_type == RowType::Dict;
 
         let mut builder = ::csv::ReaderBuilder::new();
         builder.has_headers(has_headers);
-        builder.delimiter(delimiter.0 as u8);
+        builder.delimiter(self.delimiter);
 
         // Counting lines from 1 by default.
-        let mut line_offset: usize = 1;
-        let mut reader = builder.from_reader(data.as_slice());
+        let mut line: usize = 1;
+        let mut reader = builder.from_reader(io::Cursor::new(self.data.clone()));
         let mut headers: Option<::csv::StringRecord> = None;
 
         if has_headers {
             // Counting lines from 2 because we have a header.
-            line_offset += 1;
+            line += 1;
             headers = Some(
                 reader
                     .headers()
                     .map_err(|err| format_csv_error(err, 1))
-                    .at(span)?
+                    .at(self.span)?
                     .clone(),
             );
         }
 
-        let mut array = Array::new();
-        for (line, result) in reader.records().enumerate() {
-            // Original solution was to use line from error, but that is
-            // incorrect with `has_headers` set to `false`. See issue:
-            // https://github.com/BurntSushi/rust-csv/issues/184
-            let line = line + line_offset;
-            let row = result.map_err(|err| format_csv_error(err, line)).at(span)?;
-            let item = if let Some(headers) = &headers {
-                let mut dict = Dict::new();
-                for (field, value) in headers.iter().zip(&row) {
-                    dict.insert(field.into(), value.into_value());
-                }
-                dict.into_value()
-            } else {
-                let sub = row.into_iter().map(|field| field.into_value()).collect();
-                Value::Array(sub)
-            };
-            array.push(item);
+        Ok(CsvRows {
+            reader,
+            headers,
+            record: ::csv::StringRecord::new(),
+            line,
+            span: self.span,
+        })
+    }
+}
+
+/// The rows of CSV data, parsed one at a time.
+pub struct CsvRows {
+    /// The underlying reader.
+    reader: ::csv::Reader<io::Cursor<Bytes>>,
+    /// The header row, if rows are represented as dictionaries.
+    headers: Option<::csv::StringRecord>,
+    /// A buffer for the current record.
+    record: ::csv::StringRecord,
+    /// The line of the next record.
+    line: usize,
+    /// The span of the data, for error reporting.
+    span: Span,
+}
+
+impl CsvRows {
+    /// Parse the next row.
+    pub fn next_row(&mut self) -> SourceResult<Option<Value>> {
+        // Original solution was to use line from error, but that is
+        // incorrect with `has_headers` set to `false`. See issue:
+        // https://github.com/BurntSushi/rust-csv/issues/184
+        let line = self.line;
+        let found = self
+            .reader
+            .read_record(&mut self.record)
+            .map_err(|err| format_csv_error(err, line))
+            .at(self.span)?;
+
+        if !found {
+            return Ok(None);
         }
 
-        Ok(array)
+        self.line += 1;
+        let row = &self.record;
+        let item = if let Some(headers) = &self.headers {
+            let mut dict = Dict::new();
+            for (field, value) in headers.iter().zip(row) {
+                dict.insert(field.into(), value.into_value());
+            }
+            dict.into_value()
+        } else {
+            let sub = row.into_iter().map(|field| field.into_value()).collect();
+            Value::Array(sub)
+        };
+
+        Ok(Some(item))
     }
 }
 
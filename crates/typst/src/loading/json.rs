@@ -1,10 +1,10 @@
 use ecow::{eco_format, EcoString};
 
-use crate::diag::{At, SourceResult};
+use crate::diag::{bail, At, SourceResult};
 use crate::engine::Engine;
-use crate::foundations::{func, scope, Str, Value};
+use crate::foundations::{func, scope, Bytes, Iter, Str, Value};
 use crate::loading::Readable;
-use crate::syntax::Spanned;
+use crate::syntax::{Span, Spanned};
 use crate::World;
 
 /// Reads structured data from a JSON file.
@@ -49,6 +49,10 @@ use crate::World;
 /// #forecast(json("monday.json"))
 /// #forecast(json("tuesday.json"))
 /// ```
+///
+/// # Large files
+/// To process a large top-level array without holding all of its items in
+/// memory, use [`json.items`]($json.items) instead.
 #[func(scope, title = "JSON")]
 pub fn json(
     /// The engine.
@@ -76,6 +80,41 @@ impl json {
             .at(span)
     }
 
+    /// Reads the items of a top-level array from a JSON file one by one.
+    ///
+    /// The file must contain an array. Instead of the array, this returns an
+    /// [iterator] that parses its items one at a time while it is being
+    /// iterated over.
+    ///
+    /// ```typ
+    /// #for event in json.items("events.json") {
+    ///   [- #event.name]
+    /// }
+    /// ```
+    #[func(title = "JSON Items")]
+    pub fn items(
+        /// The engine.
+        engine: &mut Engine,
+        /// Path to a JSON file.
+        path: Spanned<EcoString>,
+    ) -> SourceResult<Iter> {
+        let Spanned { v: path, span } = path;
+        let id = span.resolve_path(&path).at(span)?;
+        let data = engine.world.file(id).at(span)?;
+        Ok(json::decode_items(Spanned::new(Readable::Bytes(data), span)))
+    }
+
+    /// Reads the items of a top-level array from a JSON string/bytes one by
+    /// one.
+    #[func(title = "Decode JSON Items")]
+    pub fn decode_items(
+        /// JSON data.
+        data: Spanned<Readable>,
+    ) -> Iter {
+        let Spanned { v: data, span } = data;
+        Iter::json(JsonData { data: data.into(), span })
+    }
+
     /// Encodes structured data into a JSON string.
     #[func(title = "Encode JSON")]
     pub fn encode(
@@ -97,3 +136,124 @@ impl json {
         .at(span)
     }
 }
+
+/// A top-level JSON array whose items can be parsed on demand.
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct JsonData {
+    /// The raw data.
+    data: Bytes,
+    /// The span of the data, for error reporting.
+    span: Span,
+}
+
+impl JsonData {
+    /// Start parsing the items from the beginning.
+    pub fn items(&self) -> JsonItems {
+        JsonItems { data: self.clone(), offset: 0, state: State::Start }
+    }
+}
+
+/// The items of a top-level JSON array, parsed one at a time.
+pub struct JsonItems {
+    /// The data that is parsed.
+    data: JsonData,
+    /// The byte offset after the last consumed token.
+    offset: usize,
+    /// Where in the array we are.
+    state: State,
+}
+
+/// Where in the array a [`JsonItems`] parser is.
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum State {
+    /// Before the opening bracket.
+    Start,
+    /// After an item.
+    Rest,
+    /// After the closing bracket.
+    Done,
+}
+
+impl JsonItems {
+    /// Parse the next item.
+    pub fn next_item(&mut self) -> SourceResult<Option<Value>> {
+        let span = self.data.span;
+        let data = self.data.data.as_slice();
+        let mut pos = skip_whitespace(data, self.offset);
+
+        match self.state {
+            State::Start => {
+                if data.get(pos) != Some(&b'[') {
+                    bail!(span, "failed to parse JSON (expected a top-level array)");
+                }
+                pos = skip_whitespace(data, pos + 1);
+                if data.get(pos) != Some(&b']') {
+                    return self.item(pos);
+                }
+            }
+            State::Rest => match data.get(pos) {
+                Some(b',') => return self.item(pos + 1),
+                Some(b']') => {}
+                _ => bail!(span, "failed to parse JSON (expected `,` or `]`)"),
+            },
+            State::Done => return Ok(None),
+        }
+
+        // We are at the closing bracket.
+        self.state = State::Done;
+        if skip_whitespace(data, pos + 1) < data.len() {
+            bail!(span, "failed to parse JSON (trailing characters after array)");
+        }
+
+        Ok(None)
+    }
+
+    /// Parse the item that starts at the given offset.
+    fn item(&mut self, start: usize) -> SourceResult<Option<Value>> {
+        let data = self.data.data.as_slice();
+        let mut stream =
+            serde_json::Deserializer::from_slice(&data[start..]).into_iter::<Value>();
+        let value = match stream.next() {
+            Some(Ok(value)) => value,
+            Some(Err(err)) => {
+                let err = format_json_error(&err, data, start);
+                bail!(self.data.span, "failed to parse JSON ({err})")
+            }
+            None => {
+                bail!(self.data.span, "failed to parse JSON (unexpected end of data)")
+            }
+        };
+        self.offset = start + stream.byte_offset();
+        self.state = State::Rest;
+        Ok(Some(value))
+    }
+}
+
+/// The offset of the first non-whitespace byte at or after `pos`.
+fn skip_whitespace(data: &[u8], pos: usize) -> usize {
+    pos + data[pos.min(data.len())..]
+        .iter()
+        .take_while(|b| matches!(b, b' ' | b'\n' | b'\r' | b'\t'))
+        .count()
+}
+
+/// Format an error that occurred while parsing the data from `start` on, with
+/// a location relative to the start of the whole data.
+fn format_json_error(err: &serde_json::Error, data: &[u8], start: usize) -> EcoString {
+    let message = err.to_string();
+    let suffix = format!(" at line {} column {}", err.line(), err.column());
+    let Some(message) = message.strip_suffix(&suffix) else {
+        return message.into();
+    };
+
+    let before = &data[..start];
+    let line = err.line() + before.iter().filter(|&&b| b == b'\n').count();
+    let column = if err.line() == 1 {
+        let line_start = before.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        err.column() + (start - line_start)
+    } else {
+        err.column()
+    };
+
+    eco_format!("{message} at line {line} column {column}")
+}
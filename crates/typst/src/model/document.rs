@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use ecow::EcoString;
 
 use crate::diag::{bail, SourceResult, StrResult};
@@ -8,6 +10,7 @@ use crate::foundations::{
 };
 use crate::introspection::{Introspector, ManualPageCounter};
 use crate::layout::{LayoutRoot, Page, PageElem};
+use crate::util::hash128;
 
 /// The root element of a document and its metadata.
 ///
@@ -76,18 +79,17 @@ impl LayoutRoot for Packed<DocumentElem> {
         engine: &mut Engine,
         styles: StyleChain,
     ) -> SourceResult<Document> {
-        let mut pages = Vec::with_capacity(self.children().len());
-        let mut page_counter = ManualPageCounter::new();
-
         let children = self.children();
         let mut iter = children.iter().peekable();
 
+        // Collect the page runs along with the parity their last page must be
+        // extended to.
+        let mut runs = Vec::with_capacity(children.len());
         while let Some(mut child) = iter.next() {
-            let outer = styles;
-            let mut styles = styles;
+            let mut run_styles = styles;
             if let Some(styled) = child.to_packed::<StyledElem>() {
                 child = &styled.child;
-                styles = outer.chain(&styled.styles);
+                run_styles = styles.chain(&styled.styles);
             }
 
             if let Some(page) = child.to_packed::<PageElem>() {
@@ -98,13 +100,41 @@ impl LayoutRoot for Packed<DocumentElem> {
                         .to_packed::<PageElem>()?
                         .clear_to()?
                 });
-                let run = page.layout(engine, styles, &mut page_counter, extend_to)?;
-                pages.extend(run);
+                runs.push((page, run_styles, extend_to));
             } else {
                 bail!(child.span(), "unexpected document child");
             }
         }
 
+        // The bodies of the page runs don't depend on each other, so we lay
+        // them out in parallel. Each run's locations are salted with the span
+        // of its page element, so they stay the same when other runs change.
+        // Runs produced by the same code are told apart by their occurrence.
+        let mut occurrences = HashMap::new();
+        let tasks = runs
+            .iter()
+            .map(|&(page, styles, _)| {
+                let count = occurrences.entry(page.span()).or_insert(0_usize);
+                *count += 1;
+                (hash128(&(page.span(), *count)), (page, styles))
+            })
+            .collect();
+        let bodies = engine.parallelize(tasks, |engine, (page, styles)| {
+            page.layout_body(engine, styles)
+        });
+
+        // Number the pages and add their marginals in document order. This
+        // also surfaces the first error deterministically.
+        let mut pages = Vec::with_capacity(runs.len());
+        let mut page_counter = ManualPageCounter::new();
+        for (&(page, styles, extend_to), frames) in runs.iter().zip(bodies) {
+            let frames = frames?;
+            engine.locator.visit_frames(&frames);
+            let run =
+                page.finalize(engine, styles, &mut page_counter, extend_to, frames)?;
+            pages.extend(run);
+        }
+
         Ok(Document {
             pages,
             title: DocumentElem::title_in(styles).map(|content| content.plain_text()),
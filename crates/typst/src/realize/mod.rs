@@ -12,7 +12,7 @@ use std::borrow::Cow;
 
 use std::mem;
 
-use crate::diag::{bail, SourceResult};
+use crate::diag::{bail, error, SourceResult};
 use crate::engine::{Engine, Route};
 use crate::foundations::{
     Content, NativeElement, Packed, SequenceElem, StyleChain, StyledElem, Styles,
@@ -110,12 +110,14 @@ impl<'a, 'v, 't> Builder<'a, 'v, 't> {
         }
 
         if let Some(realized) = process(self.engine, content, styles)? {
+            self.engine.check_interrupt(content.span())?;
             self.engine.route.increase();
             if !self.engine.route.within(Route::MAX_SHOW_RULE_DEPTH) {
-                bail!(
+                bail!(error!(
                     content.span(), "maximum show rule depth exceeded";
                     hint: "check whether the show rule matches its own output"
-                );
+                )
+                .into_fatal());
             }
             let result = self.accept(self.arenas.store(realized), styles);
             self.engine.route.decrease();
@@ -243,3 +243,68 @@ pub trait Numeric:
 pub fn round_2(value: f64) -> f64 {
     (value * 100.0).round() / 100.0
 }
+
+/// Find the candidate that is most similar to a misspelled name.
+///
+/// Returns `None` if no candidate is close enough to be a likely fix.
+pub fn closest<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let max = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .filter(|&candidate| candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|&(distance, _)| distance <= max)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The number of character insertions, deletions, and substitutions that turn
+/// one string into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("fill", ""), 4);
+        assert_eq!(edit_distance("", "fill"), 4);
+        assert_eq!(edit_distance("fill", "fill"), 0);
+        assert_eq!(edit_distance("fil", "fill"), 1);
+        assert_eq!(edit_distance("fiil", "fill"), 1);
+        assert_eq!(edit_distance("flil", "fill"), 2);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("größe", "grösse"), 2);
+    }
+
+    #[test]
+    fn test_closest() {
+        let names = ["fill", "stroke", "inset", "outset"];
+        assert_eq!(closest("fil", names), Some("fill"));
+        assert_eq!(closest("strok", names), Some("stroke"));
+        assert_eq!(closest("insets", names), Some("inset"));
+        assert_eq!(closest("ouset", names), Some("outset"));
+        assert_eq!(closest("fill", names), None);
+        assert_eq!(closest("radius", names), None);
+        assert_eq!(closest("x", names), None);
+        assert_eq!(closest("fill", []), None);
+    }
+}
@@ -0,0 +1,60 @@
+// Test catching and throwing errors.
+// Ref: false
+
+---
+// Test that successful calls are not affected.
+#test(catch(() => 1 + 2), 3)
+#test(catch(() => none, err => 1), none)
+
+---
+// Test catching a built-in error.
+#let err = catch(() => (a: 1).b)
+#test(type(err), error)
+#test(err.message, "dictionary does not contain key \"b\"")
+#test(err.hints, ())
+#test(type(err.line), int)
+#test(catch(() => (a: 1).b, err => err.message.len()), 35)
+#test(catch(() => catch(() => panic("x")).message), "panicked with: \"x\"")
+
+---
+// Test custom errors.
+#let parse(text) = {
+  if not text.starts-with("v") {
+    error("invalid version", hints: ("add a `v`",)).throw()
+  }
+  text.slice(1)
+}
+
+#test(parse("v1"), "1")
+#let err = catch(() => parse("1"))
+#test(err.message, "invalid version")
+#test(err.hints, ("add a `v`",))
+#test(catch(() => parse("1"), err => err.hints.len()), 1)
+
+---
+#let err = error("a", hints: ("b",))
+#test(err.message, "a")
+#test(err.line, none)
+#test(repr(err), "error(\"a\", hints: (\"b\",))")
+
+---
+// Error: 2-62 something went wrong
+// Hint: 2-62 try again
+#error("something went wrong", hints: ("try again",)).throw()
+
+---
+// Rethrowing raises the error where it originally occurred.
+// Error: 31-32 dictionary does not contain key "b"
+#let err = catch(() => (a: 1).b)
+#err.throw()
+
+---
+// Errors in the handler are not caught.
+// Error: 30-37 panicked
+#catch(() => panic(), err => panic())
+
+---
+// Exceeded limits are not caught.
+// Error: 15-21 maximum function call depth exceeded
+#let rec(n) = rec(n) + 1
+#catch(() => rec(1), err => "caught")
@@ -0,0 +1,91 @@
+// Test type annotations of closure parameters and return values.
+// Ref: false
+
+---
+#let add(x as int, y as int) -> int = x + y
+#test(add(1, 2), 3)
+
+// Integers are converted to floats.
+#let scale(factor as float, size as length: 1pt) -> length = factor * size
+#test(scale(2), 2pt)
+#test(scale(0.5, size: 4pt), 2pt)
+
+// Multiple accepted types.
+#let name(it as (str, none)) = if it == none { "anon" } else { it }
+#test(name(none), "anon")
+#test(name("Typst"), "Typst")
+
+// Strings are converted to content.
+#let wrap = (body as content) -> content => body
+#test(wrap("A"), [A])
+
+// Single parameter without parentheses.
+#let double = x -> int => x * 2
+#test(double(2), 4)
+
+// Destructuring and argument sinks.
+#let sum((a, b) as array, ..rest) -> int = a + b + rest.pos().sum(default: 0)
+#test(sum((1, 2), 3), 6)
+
+---
+// Test annotations with user-defined types.
+#let item = record("item", fields: (price: float))
+#let total(it as item, qty as int: 1) -> float = it.price * qty
+#test(total(item(price: 2), qty: 2), 4.0)
+
+---
+// Test that annotations stay with their parameter regardless of the order.
+#let f(size as length: 1pt, body as content, ..rest, gap as int: 2) = (size, body, gap)
+#test(f("a", gap: 3), (1pt, [a], 3))
+#test(f(size: 2pt, "b"), (2pt, [b], 2))
+
+---
+// Test that annotations are captured.
+#let make(ty) = (x as ty) => x
+#test(make(str)("a"), "a")
+
+---
+#let f(x as int) = x
+
+// Error: 4-7 expected integer, found string
+#f("a")
+
+---
+#let f(x as (str, none)) = x
+
+// Error: 4-5 expected string or none, found integer
+#f(1)
+
+---
+#let f(size as length: 1pt) = size
+
+// Error: 10-12 expected length, found integer: a length needs a unit - did you mean 12pt?
+#f(size: 12)
+
+---
+// Error: 10-16 expected integer, found string
+// Hint: 10-16 the returned value must match the return type annotation
+#let f() -> int = "a"
+#f()
+
+---
+// Error: 10-16 expected integer, found string
+// Hint: 10-16 the returned value must match the return type annotation
+#let f() -> int = {
+  return "a"
+}
+#f()
+
+---
+// Error: 14-20 expected integer, found string
+// Hint: 14-20 the returned value must match the return type annotation
+#let g = (x) -> int => x
+#g("a")
+
+---
+// Error: 21-22 expected string, found integer
+#let f(name as str: 1) = name
+
+---
+// Error: 13-14 expected type, found integer
+#let f(x as 1) = x
@@ -0,0 +1,101 @@
+// Test user-defined elements.
+// Ref: false
+
+---
+#let note = element("note", fields: (
+  body: (type: content, positional: true),
+  kind: (type: str, default: "info"),
+))
+
+#let n = note[Hello]
+#test(repr(note), "note")
+#test(n.func(), note)
+#test(n.body, [Hello])
+#test(n.has("kind"), false)
+#test(note(kind: "warn")[Hi].kind, "warn")
+#test(note("Hi").body, [Hi])
+
+---
+// Test set rules and show rules.
+#let note = element("note", fields: (
+  body: (type: content, positional: true),
+  kind: (type: str, default: "info"),
+))
+
+#set note(kind: "warn")
+#show note: it => test(it.kind, "warn")
+#show note.where(body: [B]): it => test(it.body, [B])
+#note[A]
+#note[B]
+
+---
+// Test the display function.
+#let pill = element(
+  "pill",
+  fields: (text: str),
+  display: it => {
+    test(it.text, "A")
+    box(it.text)
+  },
+)
+
+#pill(text: "A")
+
+---
+// Test counting and referencing.
+#let thm = element(
+  "theorem",
+  fields: (
+    body: (type: content, positional: true),
+    numbering: (type: (str, none), default: "1"),
+  ),
+)
+
+#thm[A] <a>
+#thm(numbering: none)[B]
+#thm[C] <c>
+
+See @a.
+#context test(query(thm).len(), 3)
+#context test(counter(thm).at(<c>), (2,))
+
+---
+// Error: 12-26 invalid element name: "a b"
+#let bad = element("a b")
+
+---
+// Error: 12-46 invalid field name: "label"
+#let bad = element("x", fields: (label: str))
+
+---
+// Error: 12-40 expected type, found integer
+#let bad = element("x", fields: (a: 1))
+
+---
+#let note = element("note", fields: (kind: str))
+
+// Error: 13-14 expected string, found integer
+#note(kind: 1)
+
+---
+#let note = element("note", fields: (kind: str))
+
+// Error: 2-8 missing argument: kind
+#note()
+
+---
+#let note = element("note", fields: (kind: str))
+
+// Error: 11-20 unexpected argument: kind
+#set note(kind: "a")
+
+---
+// Test that redefining an element with the same inputs yields the same
+// element, no matter how many other definitions happened in between.
+#let elems = ()
+#for kind in ("a", "b") + range(2000).map(str) + ("a", "b") {
+  elems.push(element("note", fields: (kind: (type: str, default: kind))))
+}
+#test(elems.at(0) == elems.at(1), false)
+#test(elems.at(-2), elems.at(0))
+#test(elems.at(-1), elems.at(1))
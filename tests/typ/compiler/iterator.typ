@@ -0,0 +1,91 @@
+// Test lazy iterators.
+// Ref: false
+
+---
+// Test adaptors.
+#let it = iterator((1, 2, 3, 4, 5))
+#test(type(it), iterator)
+#test(repr(it), "<iterator>")
+#test(iterator(it), it)
+#test(it.collect(), (1, 2, 3, 4, 5))
+#test(it.map(x => x * 2).collect(), (2, 4, 6, 8, 10))
+#test(it.filter(calc.odd).collect(), (1, 3, 5))
+#test(it.enumerate().take(2).collect(), ((0, 1), (1, 2)))
+#test(it.enumerate(start: 1).skip(3).collect(), ((4, 4), (5, 5)))
+#test(it.zip((6, 7), iterator((8, 9, 10))).collect(), ((1, 6, 8), (2, 7, 9)))
+#test(it.skip(10).collect(), ())
+#test(it.chunks(2).collect(), ((1, 2), (3, 4), (5,)))
+#test(it.chunks(2, exact: true).collect(), ((1, 2), (3, 4)))
+#test(it.windows(4).collect(), ((1, 2, 3, 4), (2, 3, 4, 5)))
+#test(it.windows(6).collect(), ())
+#test(iterator((1, (2, (3,)), iterator((4, 5)))).flatten().collect(), (1, 2, 3, 4, 5))
+
+---
+// Test grouping.
+#let words = ("apple", "avocado", "banana", "blueberry", "cherry", "apricot")
+#test(
+  iterator(words).group-by(w => w.first()).collect(),
+  (
+    ("a", ("apple", "avocado")),
+    ("b", ("banana", "blueberry")),
+    ("c", ("cherry",)),
+    ("a", ("apricot",)),
+  ),
+)
+#test(iterator(()).group-by(x => x).collect(), ())
+
+---
+// Test consumers.
+#let it = iterator(range(1, 100)).map(x => x * x).filter(calc.odd)
+#test(it.count(), 50)
+#test(it.take(4).fold(0, (acc, x) => acc + x), 1 + 9 + 25 + 49)
+#test(iterator(()).fold("init", (acc, x) => none), "init")
+
+---
+// Test that values are computed on demand.
+#let it = iterator(range(3)).map(x => 1 / (2 - x))
+#test(it.take(2).collect(), (0.5, 1.0))
+
+---
+// Test for loops over iterators.
+#let out = ()
+#for (i, x) in iterator(("a", "b", "c")).enumerate() {
+  if i == 1 { continue }
+  out.push(x)
+}
+#test(out, ("a", "c"))
+
+#let sum = 0
+#for x in iterator(range(1, 1000)) {
+  if x > 4 { break }
+  sum += x
+}
+#test(sum, 10)
+
+---
+// Test lazily decoded data.
+#let rows = csv.decode-rows("a,b\n1,2\n3,4")
+#test(type(rows), iterator)
+#test(rows.collect(), (("a", "b"), ("1", "2"), ("3", "4")))
+#test(rows.count(), 3)
+#test(type(csv.decode("a,b\n1,2")), array)
+
+#let rows = csv.decode-rows("a,b\n1,2\n3,4", row-type: dictionary)
+#test(rows.map(row => row.b).collect(), ("2", "4"))
+
+#let items = json.decode-items("[1, [2, 3],\n {\"a\": 4}]")
+#test(type(items), iterator)
+#test(items.collect(), (1, (2, 3), (a: 4)))
+#test(json.decode-items(" [ ] ").collect(), ())
+
+---
+// Error: 20-25 failed to parse JSON (expected a top-level array)
+#json.decode-items("1 2").collect()
+
+---
+// Error: 20-30 failed to parse JSON (expected value at line 2 column 3)
+#json.decode-items("[1,\n  ]").collect()
+
+---
+// Error: 26-27 expected boolean, found integer
+#iterator((1, 2)).filter(x => x).collect()
@@ -0,0 +1,4 @@
+// Ref: false
+
+#let b = 1
+#let d = 4
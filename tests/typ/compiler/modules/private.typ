@@ -0,0 +1,6 @@
+// Ref: false
+
+#private let secret = 1
+#let _underscored = 2
+#let public = secret + 1
+#let export = 4
@@ -0,0 +1,6 @@
+// Ref: false
+
+#import "private.typ": public
+#private import "../module.typ": item
+#import "glob.typ": b
+#let own = 3
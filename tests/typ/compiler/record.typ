@@ -0,0 +1,92 @@
+// Test user-defined types.
+// Ref: false
+
+---
+#let item = record(
+  "item",
+  fields: (
+    name: str,
+    price: float,
+    qty: (type: int, default: 1),
+  ),
+  methods: (
+    total: self => self.price * self.qty,
+    scaled: (self, factor) => self.total() * factor,
+  ),
+)
+
+#let apple = item(name: "Apple", price: 0.5, qty: 4)
+#test(type(apple), item)
+#test(repr(item), "item")
+#test(apple.name, "Apple")
+#test(apple.qty, 4)
+#test(apple.total(), 2.0)
+#test(apple.scaled(2), 4.0)
+#test(item(name: "Pear", price: 1).price, 1.0)
+#test(repr(item(name: "Pear", price: 1)), "item(name: \"Pear\", price: 1.0, qty: 1)")
+#test(apple, item(name: "Apple", price: 0.5, qty: 4))
+#test(apple == item(name: "Apple", price: 0.5), false)
+
+---
+// Test positional fields.
+#let point = record("point", fields: (
+  x: (type: (int, float), positional: true),
+  y: (type: (int, float), positional: true),
+))
+
+#test(point(1, 2).y, 2)
+
+---
+// Test that instances of different types are different.
+#let a = record("a", fields: (x: int))
+#let b = record("a", fields: (x: int))
+#test(a(x: 1) == b(x: 1), false)
+
+---
+// Test that redefining a type with the same inputs yields the same type, no
+// matter how many other definitions happened in between.
+#let types = ()
+#for x in ("a", "b") + range(2000).map(str) + ("a", "b") {
+  types.push(record("value", fields: (x: (type: str, default: x))))
+}
+#test(types.at(0) == types.at(1), false)
+#test(types.at(-2), types.at(0))
+#test(types.at(-1), types.at(1))
+
+---
+#let item = record("item", fields: (price: float))
+
+// Error: 14-19 expected float, found string
+#item(price: "one")
+
+---
+#let item = record("item", fields: (price: float))
+
+// Error: 2-8 missing argument: price
+#item()
+
+---
+#let item = record("item", fields: (price: float))
+
+// Error: 19-25 unexpected argument: qty
+#item(price: 1.0, qty: 2)
+
+---
+#let item = record("item", fields: (price: float))
+#let x = item(price: 1.0)
+
+// Error: 4-7 item does not contain field "qty"
+#x.qty
+
+---
+#let item = record("item", fields: (price: float))
+#{
+  let x = item(price: 1.0)
+  // Error: 3-4 fields on item are not yet mutable
+  // Hint: 3-4 try creating a new item with the updated field value instead
+  x.price = 2.0
+}
+
+---
+// Error: 12-71 method "price" conflicts with field of the same name
+#let bad = record("item", fields: (price: float), methods: (price: 1))
@@ -0,0 +1,58 @@
+// Test private bindings, re-exports, and ambiguous wildcard imports.
+// Ref: false
+
+---
+#import "modules/private.typ"
+#test(private.public, 2)
+#test("secret" in dictionary(private), false)
+#test(private._underscored, 2)
+#test(private.export, 4)
+
+#import "modules/private.typ": *
+#test(public, 2)
+#test(export, 4)
+
+---
+// Error: 32-38 unresolved import
+#import "modules/private.typ": secret
+
+---
+// Plain imports are passed on, private imports are not.
+#import "modules/reexport.typ"
+#test(reexport.public, 2)
+#test(reexport.b, 1)
+#test(reexport.own, 3)
+#test("item" in dictionary(reexport), false)
+
+---
+// Error: 33-37 unresolved import
+#import "modules/reexport.typ": item
+
+---
+// `private` and `export` remain usable as identifiers.
+#let export = (private: 1)
+#test(export.private, 1)
+#let private(x) = x + 1
+#test(private(1), 2)
+#{
+  let export = "text"
+  test(export, "text")
+}
+
+---
+#import "module.typ": *
+// Warning: 9-27 `d` is imported by multiple wildcard imports
+// Hint: 9-27 this import shadows the earlier definition of `d`
+// Hint: 9-27 import `d` explicitly to choose which one to use
+#import "modules/glob.typ": *
+#test(d, 4)
+#test(b, 1)
+
+---
+// In markup, `private` in front of `let` or `import` starts a statement
+// instead of inserting a variable followed by text.
+#let private = [P]
+#private let x = 1
+#test(x, 1)
+#private import "modules/private.typ": public
+#test(public, 2)
@@ -0,0 +1,32 @@
+// Test that introspection works across independently laid out page runs.
+// Ref: false
+
+---
+#set page(width: 80pt, height: 60pt)
+#set heading(numbering: "1")
+#let s = state("s", 0)
+
+= A <a>
+#s.update(1)
+
+#set page(fill: aqua)
+= B <b>
+#s.update(x => x + 1)
+
+#set page(fill: none)
+= C <c>
+#context test(s.get(), 2)
+#context test(query(heading).map(it => it.body), ([A], [B], [C]))
+#context test(counter(heading).at(<c>), (3,))
+#context test(locate(<b>).page(), 2)
+#context test(counter(page).at(<c>), (3,))
+
+---
+// Equal elements in different runs have distinct locations.
+#set page(width: 80pt, height: 60pt)
+#let body = [#metadata(1) <x> A]
+#body
+#set page(fill: aqua)
+#body
+#context test(query(<x>).len(), 2)
+#context test(query(<x>).map(it => it.location().page()), (1, 2))